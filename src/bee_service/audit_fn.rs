@@ -0,0 +1,164 @@
+use std::path::Path;
+
+use anyhow::Result;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+
+use crate::{
+    core::database::BeeDatabase,
+    models::{
+        audit::{AuditEntry, AuditQuery},
+        config::Config,
+    },
+};
+
+/// Writes to the file and the database independently, logging each failure, so
+/// one failing sink doesn't cost the other its entry.
+pub async fn record_audit_entry(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    entry: AuditEntry,
+) -> Result<()> {
+    let file_result = match &config.audit.file {
+        Some(file) => append_audit_entry(file, &entry).await.inspect_err(|err| {
+            tracing::error!(
+                "Failed to append audit entry for {} to '{}': {}",
+                entry.action,
+                file.display(),
+                err
+            )
+        }),
+        None => Ok(()),
+    };
+    let action = entry.action.to_owned();
+    let db_result = db
+        .add_audit_entry(entry)
+        .await
+        .inspect_err(|err| tracing::error!("Failed to store audit entry for {}: {}", action, err));
+    file_result.and(db_result)
+}
+
+/// The line is written in a single call so concurrent appends don't interleave.
+async fn append_audit_entry(file: &Path, entry: &AuditEntry) -> Result<()> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)
+        .await?;
+    file.write_all(&line).await?;
+    Ok(())
+}
+
+pub async fn get_audit_entries(
+    db: Box<dyn BeeDatabase>,
+    query: &AuditQuery,
+) -> Result<Vec<AuditEntry>> {
+    db.get_audit_entries(query).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::database::MockDbService, models::config::Audit};
+
+    fn entry(timestamp: u64, action: &str, bee_id: u8) -> AuditEntry {
+        AuditEntry {
+            timestamp,
+            principal: "admin".to_owned(),
+            action: action.to_owned(),
+            bee_ids: vec![bee_id],
+            status: 200,
+            success: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn should_return_newest_entries_first() {
+        let db = Box::new(MockDbService::default());
+        let config = Config::default();
+        record_audit_entry(&config, db.clone(), entry(1, "bee.start", 1))
+            .await
+            .unwrap();
+        record_audit_entry(&config, db.clone(), entry(2, "bee.stop", 1))
+            .await
+            .unwrap();
+
+        let entries = get_audit_entries(db, &AuditQuery::default()).await.unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, "bee.stop");
+        assert_eq!(entries[1].action, "bee.start");
+    }
+
+    #[tokio::test]
+    async fn should_filter_and_limit_entries() {
+        let db = Box::new(MockDbService::default());
+        let config = Config::default();
+        for timestamp in 1..=5 {
+            record_audit_entry(&config, db.clone(), entry(timestamp, "bee.stop", 2))
+                .await
+                .unwrap();
+        }
+        record_audit_entry(&config, db.clone(), entry(6, "bee.stop", 3))
+            .await
+            .unwrap();
+
+        let query = AuditQuery {
+            bee_id: Some(2),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let entries = get_audit_entries(db, &query).await.unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timestamp, 5);
+        assert_eq!(entries[1].timestamp, 4);
+    }
+
+    #[tokio::test]
+    async fn should_append_entries_to_jsonl_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("audit.jsonl");
+        let db = Box::new(MockDbService::default());
+        let config = Config {
+            audit: Audit {
+                file: Some(file.clone()),
+            },
+            ..Default::default()
+        };
+
+        record_audit_entry(&config, db.clone(), entry(1, "bee.start", 1))
+            .await
+            .unwrap();
+        record_audit_entry(&config, db, entry(2, "bee.delete", 1))
+            .await
+            .unwrap();
+
+        let content = tokio::fs::read_to_string(&file).await.unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let last: AuditEntry = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(last.action, "bee.delete");
+    }
+
+    #[tokio::test]
+    async fn should_store_entry_when_file_append_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Box::new(MockDbService::default());
+        let config = Config {
+            audit: Audit {
+                file: Some(dir.path().to_path_buf()),
+            },
+            ..Default::default()
+        };
+
+        let result = record_audit_entry(&config, db.clone(), entry(1, "bee.start", 1)).await;
+
+        assert!(result.is_err());
+        let entries = get_audit_entries(db, &AuditQuery::default()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+}
@@ -0,0 +1,606 @@
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use regex::Regex;
+use tokio::task::JoinHandle;
+
+use crate::{
+    core::{
+        database::BeeDatabase,
+        docker::BeeDocker,
+        jobs::Jobs,
+        reservations::{BeeIdReservation, BeeIdReservations},
+    },
+    models::{
+        backup::BackupInfo,
+        bee::BeeData,
+        config::Config,
+        http_error::ApiError,
+        job::{Job, JobKind, JobStatus},
+    },
+    utils::{regex::BACKUP_NAME_REGEX, time::now_secs},
+};
+
+use super::{
+    bee_fn::{bee_data_to_info, get_node_name, reserve_bee_id, save_bee, stop_bee_for_copy},
+    storage_fn::{check_node_dir, create_node_dir, BEE_DATA_DIRS},
+};
+
+/// Node settings stored at the start of every archive.
+const MANIFEST_NAME: &str = "bee.json";
+const SWARM_KEY_PATH: &str = "keys/swarm.key";
+
+pub fn get_backup_dir(config: &Config) -> Result<PathBuf> {
+    config
+        .backup
+        .dir
+        .to_owned()
+        .ok_or_else(|| ApiError::Validation("Backups are not configured".to_owned()).into())
+}
+
+pub fn get_backup_name(bee_id: u8, created_at: u64, include_localstore: bool) -> String {
+    let suffix = if include_localstore { "-full" } else { "" };
+    format!("{}-{}{}.tar.gz", get_node_name(bee_id), created_at, suffix)
+}
+
+/// Reads the node, timestamp and content of a backup from its name, `None` for any other file.
+pub fn parse_backup_name(name: &str) -> Option<BackupInfo> {
+    let captures = Regex::new(BACKUP_NAME_REGEX).ok()?.captures(name)?;
+    Some(BackupInfo {
+        name: name.to_owned(),
+        bee_id: captures[1].parse().ok()?,
+        created_at: captures[2].parse().ok()?,
+        include_localstore: captures.get(3).is_some(),
+        size_bytes: 0,
+    })
+}
+
+/// Backups of a node, or of every node, oldest first.
+pub fn get_backups(config: &Config, bee_id: Option<u8>) -> Result<Vec<BackupInfo>> {
+    let backup_dir = get_backup_dir(config)?;
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(&backup_dir)? {
+        let entry = entry?;
+        let Some(backup) = parse_backup_name(&entry.file_name().to_string_lossy()) else {
+            continue;
+        };
+        if bee_id.is_some_and(|bee_id| bee_id != backup.bee_id) {
+            continue;
+        }
+        backups.push(BackupInfo {
+            size_bytes: entry.metadata()?.len(),
+            ..backup
+        });
+    }
+    backups.sort_by_key(|backup| (backup.created_at, backup.name.to_owned()));
+    Ok(backups)
+}
+
+/// Path of a node backup, refusing names that don't belong to the node.
+pub fn get_backup_path(config: &Config, bee_id: u8, name: &str) -> Result<PathBuf> {
+    let backup = parse_backup_name(name)
+        .filter(|backup| backup.bee_id == bee_id)
+        .ok_or_else(|| {
+            ApiError::Validation(format!(
+                "'{}' isn't a backup of {}",
+                name,
+                get_node_name(bee_id)
+            ))
+        })?;
+    Ok(get_backup_dir(config)?.join(backup.name))
+}
+
+/// Archives the manifest, keys and statestore, then the localstore when asked.
+/// The node must be stopped, LevelDB files copied while it writes are inconsistent.
+pub fn write_backup(bee_data: &BeeData, path: &Path, include_localstore: bool) -> Result<()> {
+    let partial_path = path.with_extension("partial");
+    let result = (|| {
+        let encoder = GzEncoder::new(File::create(&partial_path)?, Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        builder.follow_symlinks(false);
+
+        let manifest = serde_json::to_vec_pretty(bee_data)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(now_secs());
+        header.set_cksum();
+        builder.append_data(&mut header, MANIFEST_NAME, manifest.as_slice())?;
+
+        for dir in BEE_DATA_DIRS {
+            let source = bee_data.data_dir.join(dir);
+            if (dir == "localstore" && !include_localstore) || !source.exists() {
+                continue;
+            }
+            builder.append_dir_all(dir, &source)?;
+        }
+
+        builder.into_inner()?.finish()?.sync_all()?;
+        std::fs::rename(&partial_path, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial_path);
+    }
+    result
+}
+
+/// Removes the oldest backups of a node beyond the retention, returns their names.
+pub fn prune_backups(config: &Config, bee_id: u8) -> Result<Vec<String>> {
+    let retention = config.backup.retention;
+    let backups = get_backups(config, Some(bee_id))?;
+    if retention == 0 || backups.len() <= retention {
+        return Ok(Vec::new());
+    }
+
+    let backup_dir = get_backup_dir(config)?;
+    let expired = &backups[..backups.len() - retention];
+    for backup in expired {
+        std::fs::remove_file(backup_dir.join(&backup.name))?;
+    }
+    Ok(expired
+        .iter()
+        .map(|backup| backup.name.to_owned())
+        .collect())
+}
+
+pub async fn create_backup(
+    config: &Config,
+    bee_data: &BeeData,
+    include_localstore: bool,
+) -> Result<BackupInfo> {
+    let config = config.to_owned();
+    let bee_data = bee_data.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let backup_dir = get_backup_dir(&config)?;
+        std::fs::create_dir_all(&backup_dir)?;
+
+        let name = get_free_backup_name(&backup_dir, bee_data.id, include_localstore);
+        let path = backup_dir.join(&name);
+        write_backup(&bee_data, &path, include_localstore)?;
+        prune_backups(&config, bee_data.id)?;
+
+        get_backups(&config, Some(bee_data.id))?
+            .into_iter()
+            .find(|backup| backup.name == name)
+            .ok_or_else(|| anyhow!("Backup '{}' was pruned right away", name))
+    })
+    .await?
+}
+
+/// Names backups by the second they're taken, a later second when that one is taken
+/// already, so backups in quick succession don't collide.
+fn get_free_backup_name(backup_dir: &Path, bee_id: u8, include_localstore: bool) -> String {
+    (now_secs()..)
+        .map(|created_at| get_backup_name(bee_id, created_at, include_localstore))
+        .find(|name| !backup_dir.join(name).exists())
+        .expect("timestamps run out")
+}
+
+/// Stops the node for the archive and starts it again if it was running.
+async fn back_up_stopped_bee(
+    config: &Config,
+    docker: Box<dyn BeeDocker>,
+    jobs: &Jobs,
+    job_id: &str,
+    bee_data: &BeeData,
+    include_localstore: bool,
+) -> Result<BackupInfo> {
+    jobs.update(job_id, |job| job.step = "stopping".to_owned());
+    let was_running = stop_bee_for_copy(docker.clone(), &bee_data.name()).await?;
+
+    jobs.update(job_id, |job| job.step = "archiving".to_owned());
+    let result = create_backup(config, bee_data, include_localstore).await;
+
+    if was_running {
+        jobs.update(job_id, |job| job.step = "starting".to_owned());
+        docker.start_bee_container(&bee_data.name()).await?;
+    }
+    result
+}
+
+/// Runs the backup in the background, returns `None` when the node already has a running job.
+/// The handle resolves once the job has finished.
+pub fn start_bee_backup(
+    config: &Config,
+    docker: Box<dyn BeeDocker>,
+    jobs: &Jobs,
+    bee_data: &BeeData,
+    include_localstore: bool,
+) -> Option<(Job, JoinHandle<()>)> {
+    let job = jobs.start(JobKind::Backup, bee_data.id)?;
+
+    let config = config.to_owned();
+    let jobs = jobs.to_owned();
+    let bee_data = bee_data.to_owned();
+    let job_id = job.id.to_owned();
+    let handle = tokio::spawn(async move {
+        let result = back_up_stopped_bee(
+            &config,
+            docker,
+            &jobs,
+            &job_id,
+            &bee_data,
+            include_localstore,
+        )
+        .await;
+        jobs.update(&job_id, |job| {
+            match result {
+                Ok(backup) => {
+                    job.status = JobStatus::Completed;
+                    job.step = "done".to_owned();
+                    job.processed_bytes = backup.size_bytes;
+                    job.output = Some(backup.name);
+                }
+                Err(err) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(err.to_string());
+                }
+            }
+            job.finished_at = Some(now_secs());
+        });
+    });
+
+    Some((job, handle))
+}
+
+/// Backs up every node at the configured interval, does nothing when it's `0`.
+/// One node at a time, as each is stopped during its backup.
+pub async fn run_scheduled_backups(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+    jobs: &Jobs,
+) {
+    if config.backup.dir.is_none() || config.backup.interval_hours == 0 {
+        return;
+    }
+
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(config.backup.interval_hours * 60 * 60));
+    // The first tick completes right away, backups start one interval after startup.
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let bees = match db.get_bees().await {
+            Ok(bees) => bees,
+            Err(err) => {
+                tracing::error!("Failed to list nodes for scheduled backups: {}", err);
+                continue;
+            }
+        };
+        for bee_data in bees {
+            let include_localstore = config.backup.include_localstore;
+            match start_bee_backup(config, docker.clone(), jobs, &bee_data, include_localstore) {
+                Some((job, handle)) => {
+                    let _ = handle.await;
+                    match jobs.get(&job.id).and_then(|job| job.error) {
+                        Some(err) => {
+                            tracing::error!("Failed to back up {}: {}", bee_data.name(), err)
+                        }
+                        None => tracing::info!("Backed up {}", bee_data.name()),
+                    }
+                }
+                None => tracing::warn!("Skipped backup of {}, a job is running", bee_data.name()),
+            }
+        }
+    }
+}
+
+/// Reads the manifest and the swarm key, which come first in the archive.
+pub fn read_backup_identity(path: &Path) -> Result<(BeeData, Vec<u8>)> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    let mut manifest = None;
+    let mut swarm_key = None;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_path_buf();
+        if entry_path == Path::new(MANIFEST_NAME) {
+            manifest = Some(serde_json::from_reader::<_, BeeData>(&mut entry)?);
+        } else if entry_path == Path::new(SWARM_KEY_PATH) {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            swarm_key = Some(content);
+        }
+        if manifest.is_some() && swarm_key.is_some() {
+            break;
+        }
+    }
+
+    match (manifest, swarm_key) {
+        (Some(manifest), Some(swarm_key)) => Ok((manifest, swarm_key)),
+        _ => Err(ApiError::Validation(format!(
+            "'{}' is missing the node manifest or keys",
+            path.display()
+        ))
+        .into()),
+    }
+}
+
+/// Finds the registered node already holding this identity, if any.
+pub fn find_bee_with_swarm_key(bees: &[BeeData], swarm_key: &[u8]) -> Option<u8> {
+    bees.iter()
+        .find(|bee| {
+            std::fs::read(bee.data_dir.join(SWARM_KEY_PATH))
+                .is_ok_and(|content| content == swarm_key)
+        })
+        .map(|bee| bee.id)
+}
+
+/// Only the bee data dirs are extracted, the manifest stays in the archive.
+pub fn unpack_backup(path: &Path, data_dir: &Path) -> Result<()> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    archive.set_preserve_permissions(true);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let is_bee_data = entry.path()?.components().next().is_some_and(|component| {
+            BEE_DATA_DIRS
+                .iter()
+                .any(|dir| component.as_os_str() == *dir)
+        });
+        if is_bee_data {
+            entry.unpack_in(data_dir)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks the backup can be restored and reserves the node data dir. The node
+/// keeps its original id unless it has been taken since.
+pub async fn prepare_bee_restore(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    jobs: &Jobs,
+    reservations: &BeeIdReservations,
+    name: &str,
+) -> Result<(PathBuf, BeeData, BeeIdReservation)> {
+    let backup = parse_backup_name(name)
+        .ok_or_else(|| ApiError::Validation(format!("'{}' isn't a backup", name)))?;
+    // A node being moved is in two places, its identity check would miss one.
+    if jobs.is_busy(backup.bee_id) {
+        return Err(ApiError::Conflict(format!(
+            "A job is already running on {}",
+            get_node_name(backup.bee_id)
+        ))
+        .into());
+    }
+    let path = get_backup_dir(config)?.join(&backup.name);
+    if !path.exists() {
+        return Err(ApiError::NotFound(format!("Backup '{}' doesn't exist", name)).into());
+    }
+    let bees = db.get_bees().await?;
+    let manifest = {
+        let path = path.to_owned();
+        tokio::task::spawn_blocking(move || {
+            let (manifest, swarm_key) = read_backup_identity(&path)?;
+            if let Some(bee_id) = find_bee_with_swarm_key(&bees, &swarm_key) {
+                return Err(anyhow::Error::new(ApiError::Conflict(format!(
+                    "The identity in '{}' is already used by {}",
+                    path.display(),
+                    get_node_name(bee_id)
+                ))));
+            }
+            Ok(manifest)
+        })
+        .await??
+    };
+
+    let reservation = reserve_bee_id(db.clone(), reservations, Some(manifest.id))
+        .await?
+        .ok_or_else(|| ApiError::Capacity("Max capacity reached".to_owned()))?;
+    let data_dir = create_node_dir(config, db, reservations, &reservation).await?;
+
+    Ok((
+        path,
+        BeeData {
+            id: reservation.id,
+            data_dir,
+            created_at: now_secs(),
+            ..manifest
+        },
+        reservation,
+    ))
+}
+
+/// Extracts the backup into the reserved data dir, then registers and starts the node.
+pub fn start_bee_restore(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+    jobs: &Jobs,
+    path: &Path,
+    bee_data: &BeeData,
+    reservation: BeeIdReservation,
+) -> Option<Job> {
+    let Some(job) = jobs.start(JobKind::Restore, bee_data.id) else {
+        // Only the empty dir reserved by `prepare_bee_restore` is removed.
+        if let Err(err) = std::fs::remove_dir(&bee_data.data_dir) {
+            tracing::warn!(
+                "Failed to remove '{}': {}",
+                bee_data.data_dir.display(),
+                err
+            );
+        }
+        return None;
+    };
+
+    let config = config.to_owned();
+    let jobs = jobs.to_owned();
+    let path = path.to_owned();
+    let bee_data = bee_data.to_owned();
+    let job_id = job.id.to_owned();
+    tokio::spawn(async move {
+        let result = restore_bee(&config, db, docker, &jobs, &job_id, &path, &bee_data).await;
+        drop(reservation);
+        if let Err(err) = &result {
+            tracing::error!("Failed to restore {}: {}", bee_data.name(), err);
+            if let Ok(true) = check_node_dir(&config, &bee_data.data_dir) {
+                let _ = tokio::fs::remove_dir_all(&bee_data.data_dir).await;
+            }
+        }
+        jobs.update(&job_id, |job| {
+            match result {
+                Ok(()) => job.status = JobStatus::Completed,
+                Err(err) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(err.to_string());
+                }
+            }
+            job.finished_at = Some(now_secs());
+        });
+    });
+
+    Some(job)
+}
+
+async fn restore_bee(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+    jobs: &Jobs,
+    job_id: &str,
+    path: &Path,
+    bee_data: &BeeData,
+) -> Result<()> {
+    jobs.update(job_id, |job| job.step = "extracting".to_owned());
+    {
+        let path = path.to_owned();
+        let data_dir = bee_data.data_dir.to_owned();
+        tokio::task::spawn_blocking(move || unpack_backup(&path, &data_dir)).await??;
+    }
+
+    jobs.update(job_id, |job| job.step = "starting".to_owned());
+    let bee = bee_data_to_info(config, bee_data)?;
+    docker.create_bee_container(&bee, config).await?;
+    if let Err(err) = save_bee(db, bee_data).await {
+        let _ = docker.remove_bee_container(&bee.name).await;
+        return Err(err);
+    }
+    docker.start_bee_container(&bee.name).await?;
+
+    jobs.update(job_id, |job| job.step = "done".to_owned());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (tempfile::TempDir, Config, BeeData) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.backup.dir = Some(temp_dir.path().join("backups"));
+        config.backup.retention = 2;
+
+        let bee_data = BeeData {
+            id: 5,
+            neighborhood: "0101".to_owned(),
+            full_node: true,
+            data_dir: temp_dir.path().join("node_05"),
+            ..Default::default()
+        };
+        let data_dir = &bee_data.data_dir;
+        std::fs::create_dir_all(data_dir.join("keys")).unwrap();
+        std::fs::create_dir_all(data_dir.join("statestore")).unwrap();
+        std::fs::create_dir_all(data_dir.join("localstore")).unwrap();
+        std::fs::write(data_dir.join(SWARM_KEY_PATH), "swarm-key").unwrap();
+        std::fs::write(data_dir.join("statestore").join("000001.log"), "state").unwrap();
+        std::fs::write(data_dir.join("localstore").join("chunks"), "chunks").unwrap();
+        (temp_dir, config, bee_data)
+    }
+
+    #[test]
+    fn should_parse_backup_names() {
+        let name = get_backup_name(5, 1700000000, true);
+        assert_eq!(name, "node_05-1700000000-full.tar.gz");
+
+        let backup = parse_backup_name(&name).unwrap();
+        assert_eq!(backup.bee_id, 5);
+        assert_eq!(backup.created_at, 1700000000);
+        assert!(backup.include_localstore);
+
+        assert!(parse_backup_name("node_05-1700000000.tar.gz.partial").is_none());
+        assert!(parse_backup_name("../node_05-1700000000.tar.gz").is_none());
+    }
+
+    #[test]
+    fn should_name_backups_in_same_second_apart() {
+        let (temp_dir, _, _) = setup();
+        let first = get_free_backup_name(temp_dir.path(), 5, false);
+        std::fs::write(temp_dir.path().join(&first), "").unwrap();
+
+        let second = get_free_backup_name(temp_dir.path(), 5, false);
+
+        assert_ne!(first, second);
+        let created_at = |name: &str| parse_backup_name(name).unwrap().created_at;
+        assert!(created_at(&second) > created_at(&first));
+    }
+
+    #[test]
+    fn should_refuse_backup_of_another_node() {
+        let (_temp_dir, config, _) = setup();
+
+        assert!(get_backup_path(&config, 5, "node_05-1.tar.gz").is_ok());
+        assert!(get_backup_path(&config, 6, "node_05-1.tar.gz").is_err());
+        assert!(get_backup_path(&config, 5, "../../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn should_restore_identity_without_localstore() {
+        let (temp_dir, _config, bee_data) = setup();
+        let path = temp_dir.path().join("node_05-1.tar.gz");
+
+        write_backup(&bee_data, &path, false).unwrap();
+        let (manifest, swarm_key) = read_backup_identity(&path).unwrap();
+        assert_eq!(manifest.neighborhood, "0101");
+        assert_eq!(swarm_key, b"swarm-key");
+        assert_eq!(find_bee_with_swarm_key(&[bee_data], &swarm_key), Some(5));
+
+        let restored = temp_dir.path().join("restored");
+        std::fs::create_dir_all(&restored).unwrap();
+        unpack_backup(&path, &restored).unwrap();
+        assert!(restored.join(SWARM_KEY_PATH).is_file());
+        assert!(restored.join("statestore").join("000001.log").is_file());
+        assert!(!restored.join("localstore").exists());
+        assert!(!restored.join(MANIFEST_NAME).exists());
+    }
+
+    #[test]
+    fn should_prune_oldest_backups() {
+        let (_temp_dir, config, bee_data) = setup();
+        let backup_dir = get_backup_dir(&config).unwrap();
+        std::fs::create_dir_all(&backup_dir).unwrap();
+        for created_at in [3, 1, 2] {
+            let name = get_backup_name(bee_data.id, created_at, false);
+            write_backup(&bee_data, &backup_dir.join(name), false).unwrap();
+        }
+        let other = get_backup_name(6, 0, false);
+        std::fs::write(backup_dir.join(&other), "").unwrap();
+
+        let pruned = prune_backups(&config, bee_data.id).unwrap();
+
+        assert_eq!(pruned, vec!["node_05-1.tar.gz"]);
+        let names = get_backups(&config, None)
+            .unwrap()
+            .into_iter()
+            .map(|backup| backup.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![other.as_str(), "node_05-2.tar.gz", "node_05-3.tar.gz"]
+        );
+    }
+}
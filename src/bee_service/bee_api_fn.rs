@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+
+use crate::models::{
+    bee::BeeInfo,
+    bee_api::{BeeStake, BeeStatus, BeeWallet},
+};
+
+const BEE_API_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn get_bee_api_url(bee: &BeeInfo) -> String {
+    format!("http://127.0.0.1:{}", bee.api_port)
+}
+
+async fn get_from_bee<T: DeserializeOwned>(api_url: &str, path: &str) -> Result<T> {
+    Ok(reqwest::Client::new()
+        .get(format!("{}{}", api_url, path))
+        .timeout(BEE_API_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<T>()
+        .await?)
+}
+
+pub async fn get_bee_status(api_url: &str) -> Result<BeeStatus> {
+    get_from_bee(api_url, "/status").await
+}
+
+pub async fn get_bee_stake(api_url: &str) -> Result<BeeStake> {
+    get_from_bee(api_url, "/stake").await
+}
+
+pub async fn get_bee_wallet(api_url: &str) -> Result<BeeWallet> {
+    get_from_bee(api_url, "/wallet").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn should_read_status_from_bee() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "overlay": "abcd",
+                "connectedPeers": 152,
+                "storageRadius": 11,
+                "reserveSize": 4194304
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let status = get_bee_status(&mock_server.uri()).await.unwrap();
+
+        assert_eq!(status.connected_peers, 152);
+        assert_eq!(status.storage_radius, 11);
+        assert_eq!(status.reserve_size, 4194304);
+    }
+
+    #[tokio::test]
+    async fn should_fail_on_bee_error_status() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/stake"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+
+        assert!(get_bee_stake(&mock_server.uri()).await.is_err());
+    }
+
+    #[test]
+    fn should_target_local_api_port() {
+        let bee = BeeInfo {
+            api_port: "1712".to_owned(),
+            ..Default::default()
+        };
+
+        assert_eq!(get_bee_api_url(&bee), "http://127.0.0.1:1712");
+    }
+}
@@ -1,19 +1,24 @@
-use std::path::PathBuf;
+use std::{collections::HashSet, ops::RangeInclusive, path::PathBuf};
 
 use crate::{
-    core::{database::BeeDatabase, docker::BeeDocker},
+    core::{
+        database::BeeDatabase,
+        docker::BeeDocker,
+        reservations::{BeeIdReservation, BeeIdReservations},
+    },
     models::{
-        bee::{BeeData, BeeInfo},
+        bee::{BeeData, BeeDeletion, BeeInfo},
         config::Config,
+        http_error::ApiError,
     },
+    utils::time::now_secs,
 };
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use futures_util::future::try_join_all;
-use tokio::fs;
 
 use super::{
     network_fn::{get_api_port, get_p2p_port},
-    storage_fn::get_node_path,
+    storage_fn::{check_node_dir, get_dir_size},
 };
 
 pub fn format_id(id: u8) -> String {
@@ -24,17 +29,23 @@ pub fn get_node_name(id: u8) -> String {
     format!("node_{}", format_id(id))
 }
 
-pub async fn ensure_capacity(db: Box<dyn BeeDatabase>) -> Result<bool> {
-    let count = db.count_bees().await?;
-    if count >= 99 {
-        return Ok(false);
-    }
-    return Ok(true);
+pub const MAX_BEES: u64 = 99;
+
+/// Ids a node can get, one per node up to `MAX_BEES`.
+pub const BEE_IDS: RangeInclusive<u8> = 1..=MAX_BEES as u8;
+
+/// Whether the saved nodes and the `reserved` ids leave room for another node.
+pub async fn ensure_capacity(db: Box<dyn BeeDatabase>, reserved: &HashSet<u8>) -> Result<bool> {
+    let count = db.count_bees().await? + reserved.len() as u64;
+    Ok(count < MAX_BEES)
 }
 
-pub async fn get_new_bee_id(db: Box<dyn BeeDatabase>) -> Result<u8> {
+/// Lowest id neither saved nor `reserved`.
+pub async fn get_new_bee_id(db: Box<dyn BeeDatabase>, reserved: &HashSet<u8>) -> Result<u8> {
     let bees = get_bees(db).await?;
-    let mut available_ids = (1..99).collect::<Vec<u8>>();
+    let mut available_ids = BEE_IDS
+        .filter(|id| !reserved.contains(id))
+        .collect::<Vec<u8>>();
 
     for bee in bees {
         available_ids.retain(|id| *id != bee.id);
@@ -42,8 +53,31 @@ pub async fn get_new_bee_id(db: Box<dyn BeeDatabase>) -> Result<u8> {
 
     available_ids
         .first()
+        .copied()
         .ok_or(anyhow::anyhow!("Unable to get new bee id"))
-        .map(|v| v.clone())
+}
+
+/// Reserves `preferred` when free, the first available id otherwise. `None`
+/// once saved and reserved nodes fill the capacity.
+pub async fn reserve_bee_id(
+    db: Box<dyn BeeDatabase>,
+    reservations: &BeeIdReservations,
+    preferred: Option<u8>,
+) -> Result<Option<BeeIdReservation>> {
+    let _allocation = reservations.lock().await;
+    // Snapshot the reservations before reading the nodes: an id released in
+    // between was saved first, so the read still sees it.
+    let reserved = reservations.get_reserved();
+    if !ensure_capacity(db.clone(), &reserved).await? {
+        return Ok(None);
+    }
+
+    let saved = get_bees(db.clone()).await?;
+    let id = match preferred {
+        Some(id) if !reserved.contains(&id) && !saved.iter().any(|bee| bee.id == id) => id,
+        _ => get_new_bee_id(db, &reserved).await?,
+    };
+    Ok(Some(reservations.reserve(id)))
 }
 
 pub fn new_bee_data(config: &Config, id: u8, neighborhood: &str, data_dir: &PathBuf) -> BeeData {
@@ -54,15 +88,18 @@ pub fn new_bee_data(config: &Config, id: u8, neighborhood: &str, data_dir: &Path
         full_node: config.bee.full_node,
         swap_enable: config.bee.swap_enable,
         reserve_doubling: config.bee.reserve_doubling,
+        created_at: now_secs(),
+        ..Default::default()
     }
 }
 
 pub async fn save_bee(db: Box<dyn BeeDatabase>, bee_data: &BeeData) -> Result<()> {
-    if !ensure_capacity(db.clone()).await? {
-        return Err(anyhow!("Max capacity reached"));
+    if !db
+        .add_bee_within_capacity(bee_data.to_owned(), MAX_BEES)
+        .await?
+    {
+        return Err(ApiError::Capacity("Max capacity reached".to_owned()).into());
     }
-
-    db.add_bee(bee_data.to_owned()).await?;
     Ok(())
 }
 
@@ -89,11 +126,58 @@ pub async fn count_bees(db: Box<dyn BeeDatabase>) -> Result<u64> {
     db.count_bees().await
 }
 
-pub async fn delete_bee(config: &Config, db: Box<dyn BeeDatabase>, bee_id: u8) -> Result<()> {
-    let node_path = get_node_path(config, bee_id)?;
-    fs::remove_dir_all(node_path).await?;
-    db.delete_bee(bee_id).await?;
-    Ok(())
+/// The persisted data dir is authoritative, wherever the storage config places new nodes.
+/// Checks the data dir is safe to remove, without measuring it.
+pub async fn check_bee_deletion(config: &Config, bee_data: &BeeData) -> Result<BeeDeletion> {
+    let config = config.to_owned();
+    let bee_data = bee_data.to_owned();
+    tokio::task::spawn_blocking(move || {
+        Ok(BeeDeletion {
+            id: bee_data.id,
+            name: bee_data.name(),
+            data_dir_exists: check_node_dir(&config, &bee_data.data_dir)?,
+            data_dir: bee_data.data_dir,
+            size_bytes: None,
+            dry_run: true,
+        })
+    })
+    .await?
+}
+
+pub async fn get_bee_deletion(config: &Config, bee_data: &BeeData) -> Result<BeeDeletion> {
+    let deletion = check_bee_deletion(config, bee_data).await?;
+    let data_dir = deletion.data_dir.to_owned();
+    let size_bytes = tokio::task::spawn_blocking(move || get_dir_size(&data_dir)).await??;
+    Ok(BeeDeletion {
+        size_bytes: Some(size_bytes),
+        ..deletion
+    })
+}
+
+/// Removes the node from the database, then its data dir in the background,
+/// as a large localstore takes longer to remove than a request may last.
+pub async fn delete_bee(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    bee_data: &BeeData,
+) -> Result<BeeDeletion> {
+    let deletion = check_bee_deletion(config, bee_data).await?;
+    db.delete_bee(bee_data.id).await?;
+    // A node later created with the same id must not be deletable with these tokens.
+    db.delete_deletion_requests(bee_data.id).await?;
+
+    if deletion.data_dir_exists {
+        let data_dir = deletion.data_dir.to_owned();
+        tokio::task::spawn_blocking(move || {
+            if let Err(err) = std::fs::remove_dir_all(&data_dir) {
+                tracing::error!("Failed to remove '{}': {}", data_dir.display(), err);
+            }
+        });
+    }
+    Ok(BeeDeletion {
+        dry_run: false,
+        ..deletion
+    })
 }
 
 pub async fn create_bee_container(
@@ -108,6 +192,20 @@ pub async fn start_bee_container(docker: Box<dyn BeeDocker>, name: &str) -> Resu
     docker.start_bee_container(name).await
 }
 
+/// Names of the nodes whose container is labeled as managed by this instance.
+/// Containers created before labels existed are adopted when the server starts.
+pub async fn get_managed_bee_names(
+    docker: Box<dyn BeeDocker>,
+    bees: &[BeeData],
+) -> Result<Vec<String>> {
+    let container_states = docker.get_bee_container_states().await?;
+    Ok(bees
+        .iter()
+        .map(|bee| bee.name())
+        .filter(|name| container_states.contains_key(name))
+        .collect())
+}
+
 pub async fn start_bee_containers(docker: Box<dyn BeeDocker>, names: Vec<String>) -> Result<()> {
     let starts = names
         .into_iter()
@@ -138,6 +236,25 @@ pub async fn stop_bee_containers(docker: Box<dyn BeeDocker>, names: Vec<String>)
     Ok(())
 }
 
+/// Stops the node before its data dir is copied and returns whether it was running.
+/// Unless Docker reports the container stopped or gone, the node may still be
+/// writing to its data dir, so the copy must not start.
+pub async fn stop_bee_for_copy(docker: Box<dyn BeeDocker>, name: &str) -> Result<bool> {
+    match docker.stop_bee_container(name).await {
+        Ok(()) => Ok(true),
+        Err(err) if is_docker_status(&err, &[304, 404]) => Ok(false),
+        Err(err) => Err(anyhow::anyhow!("Failed to stop {}: {}", name, err)),
+    }
+}
+
+fn is_docker_status(err: &anyhow::Error, status_codes: &[u16]) -> bool {
+    matches!(
+        err.downcast_ref::<bollard::errors::Error>(),
+        Some(bollard::errors::Error::DockerResponseServerError { status_code, .. })
+            if status_codes.contains(status_code)
+    )
+}
+
 pub async fn remove_bee_container(docker: Box<dyn BeeDocker>, name: &str) -> Result<()> {
     docker.remove_bee_container(name).await
 }
@@ -200,7 +317,7 @@ mod tests {
             .unwrap();
         }
 
-        let capacity = ensure_capacity(db).await.unwrap();
+        let capacity = ensure_capacity(db, &HashSet::new()).await.unwrap();
 
         assert!(capacity, "ensure_capacity should return true when under 99");
     }
@@ -217,7 +334,7 @@ mod tests {
             .unwrap();
         }
 
-        let capacity = ensure_capacity(db).await.unwrap();
+        let capacity = ensure_capacity(db, &HashSet::new()).await.unwrap();
 
         assert!(!capacity, "ensure_capacity should return false at 99");
     }
@@ -226,7 +343,7 @@ mod tests {
     async fn ensure_capacity_returns_true_when_empty() {
         let db = Box::new(MockDbService::default());
 
-        let capacity = ensure_capacity(db).await.unwrap();
+        let capacity = ensure_capacity(db, &HashSet::new()).await.unwrap();
 
         assert!(
             capacity,
@@ -234,6 +351,23 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn ensure_capacity_counts_reserved_ids() {
+        let db = Box::new(MockDbService::default());
+        for id in 1..98 {
+            db.add_bee(BeeData {
+                id,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let capacity = ensure_capacity(db, &HashSet::from([98, 99])).await.unwrap();
+
+        assert!(!capacity, "ensure_capacity should count reserved ids");
+    }
+
     #[tokio::test]
     async fn should_get_next_bee_id() {
         let db = Box::new(MockDbService::default());
@@ -251,9 +385,12 @@ mod tests {
         .await
         .unwrap();
 
-        let new_bee_id = get_new_bee_id(db).await.unwrap();
+        let reservation = reserve_bee_id(db, &BeeIdReservations::default(), None)
+            .await
+            .unwrap()
+            .unwrap();
 
-        assert_eq!(new_bee_id, 3);
+        assert_eq!(reservation.id, 3);
     }
 
     #[tokio::test]
@@ -272,9 +409,78 @@ mod tests {
         .await
         .unwrap();
 
-        let new_bee_id = get_new_bee_id(db).await.unwrap();
+        let reservation = reserve_bee_id(db, &BeeIdReservations::default(), None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(reservation.id, 2);
+    }
+
+    #[tokio::test]
+    async fn should_keep_preferred_id_when_free() {
+        let db = Box::new(MockDbService::default());
+        let reservations = BeeIdReservations::default();
+        let _reservation = reservations.reserve(7);
+
+        let free = reserve_bee_id(db.clone(), &reservations, Some(5)).await;
+        let taken = reserve_bee_id(db, &reservations, Some(7)).await;
+
+        assert_eq!(free.unwrap().unwrap().id, 5);
+        assert_eq!(taken.unwrap().unwrap().id, 1);
+    }
+
+    #[tokio::test]
+    async fn should_not_reserve_beyond_capacity() {
+        let db = Box::new(MockDbService::default());
+        let reservations = BeeIdReservations::default();
+        let _held = (1..MAX_BEES as u8)
+            .map(|id| reservations.reserve(id))
+            .collect::<Vec<_>>();
+        db.add_bee(BeeData {
+            id: MAX_BEES as u8,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let result = reserve_bee_id(db, &reservations, None).await.unwrap();
+
+        assert!(result.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn should_allocate_distinct_ids_to_concurrent_creations() {
+        let db: Box<dyn BeeDatabase> = Box::new(MockDbService::default());
+        let reservations = BeeIdReservations::default();
+
+        let creations = (0..20).map(|_| {
+            let db = db.clone();
+            let reservations = reservations.clone();
+            tokio::spawn(async move {
+                let reservation = reserve_bee_id(db.clone(), &reservations, None)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                tokio::task::yield_now().await;
+                save_bee(
+                    db,
+                    &BeeData {
+                        id: reservation.id,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+                reservation.id
+            })
+        });
+        let mut ids = try_join_all(creations).await.unwrap();
+        ids.sort();
 
-        assert_eq!(new_bee_id, 2);
+        assert_eq!(ids, (1..=20).collect::<Vec<u8>>());
+        assert!(reservations.get_reserved().is_empty());
+        assert_eq!(db.count_bees().await.unwrap(), 20);
     }
 
     #[tokio::test]
@@ -289,7 +495,7 @@ mod tests {
             .unwrap();
         }
 
-        let result = get_new_bee_id(db).await;
+        let result = get_new_bee_id(db, &HashSet::new()).await;
 
         assert!(result.is_err());
         assert_eq!(
@@ -298,6 +504,25 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn should_not_get_reserved_bee_id() {
+        let db = Box::new(MockDbService::default());
+        for id in 1..=98 {
+            db.add_bee(BeeData {
+                id,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let free = get_new_bee_id(db.clone(), &HashSet::new()).await;
+        let reserved = get_new_bee_id(db, &HashSet::from([99])).await;
+
+        assert_eq!(free.unwrap(), 99);
+        assert!(reserved.is_err());
+    }
+
     #[tokio::test]
     async fn should_create_new_bee_data_correctly() {
         let config = Config {
@@ -385,34 +610,158 @@ mod tests {
         let config = Config {
             storage: Storage {
                 root_path: root_path.clone(),
-                parent_dir_format: "swarm_data_xx".to_string(),
-                parent_dir_capacity: 4,
                 ..Default::default()
             },
             ..Default::default()
         };
-        let bee_id = 1;
-        db.add_bee(BeeData {
-            id: bee_id,
+        let node_path = root_path.join("swarm_data_01").join("node_01");
+        let bee_data = BeeData {
+            id: 1,
+            data_dir: node_path.clone(),
             ..Default::default()
-        })
-        .await
-        .unwrap();
-        let node_path = get_node_path(&config, bee_id).unwrap();
-        tokio::fs::create_dir_all(&node_path).await.unwrap();
-        let nested_file_path = node_path.join("nested_file.txt");
+        };
+        db.add_bee(bee_data.clone()).await.unwrap();
+        tokio::fs::create_dir_all(node_path.join("statestore"))
+            .await
+            .unwrap();
+        let nested_file_path = node_path.join("statestore").join("nested_file.txt");
         tokio::fs::write(&nested_file_path, "test content")
             .await
             .unwrap();
         assert!(nested_file_path.exists());
 
-        delete_bee(&config, db.clone(), bee_id).await.unwrap();
+        let deletion = delete_bee(&config, db.clone(), &bee_data).await.unwrap();
 
-        assert!(get_bee(db, bee_id).await.unwrap().is_none());
-        assert!(!node_path.exists());
+        assert!(!deletion.dry_run);
+        assert!(deletion.data_dir_exists);
+        assert!(get_bees(db).await.unwrap().is_empty());
+        wait_until_removed(&node_path).await;
         assert!(!nested_file_path.exists());
     }
 
+    async fn wait_until_removed(path: &std::path::Path) {
+        for _ in 0..100 {
+            if !path.exists() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("'{}' wasn't removed", path.display());
+    }
+
+    #[tokio::test]
+    async fn should_delete_persisted_data_dir_in_volume() {
+        let db = Box::new(MockDbService::default());
+        let volume = tempfile::tempdir().unwrap();
+        let config = Config {
+            storage: Storage {
+                volumes: vec![crate::models::config::Volume {
+                    path: volume.path().to_path_buf(),
+                    capacity: 4,
+                    max_size_gb: None,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // Moved or imported, so not where the id would place it.
+        let node_path = volume.path().join("node_01_moved");
+        let bee_data = BeeData {
+            id: 1,
+            data_dir: node_path.clone(),
+            ..Default::default()
+        };
+        db.add_bee(bee_data.clone()).await.unwrap();
+        tokio::fs::create_dir_all(node_path.join("keys"))
+            .await
+            .unwrap();
+
+        let deletion = delete_bee(&config, db.clone(), &bee_data).await.unwrap();
+
+        assert_eq!(deletion.data_dir, node_path);
+        assert_eq!(deletion.size_bytes, None);
+        wait_until_removed(&node_path).await;
+        assert!(volume.path().exists());
+    }
+
+    #[tokio::test]
+    async fn should_plan_deletion_without_removing_anything() {
+        let root_path = tempfile::tempdir().unwrap().path().to_path_buf();
+        let config = Config {
+            storage: Storage {
+                root_path: root_path.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let node_path = root_path.join("swarm_data_01").join("node_01");
+        tokio::fs::create_dir_all(node_path.join("keys"))
+            .await
+            .unwrap();
+        tokio::fs::write(node_path.join("keys").join("swarm.key"), "{}")
+            .await
+            .unwrap();
+
+        let deletion = get_bee_deletion(
+            &config,
+            &BeeData {
+                id: 1,
+                data_dir: node_path.clone(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(deletion.dry_run);
+        assert_eq!(deletion.name, "node_01");
+        assert!(deletion.size_bytes.unwrap() > 0);
+        assert!(node_path.exists());
+    }
+
+    #[tokio::test]
+    async fn should_refuse_to_delete_dir_outside_storage_root() {
+        let db = Box::new(MockDbService::default());
+        let other_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            storage: Storage {
+                root_path: tempfile::tempdir().unwrap().path().to_path_buf(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        tokio::fs::create_dir_all(other_dir.path().join("keys"))
+            .await
+            .unwrap();
+        let bee_data = BeeData {
+            id: 1,
+            data_dir: other_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        db.add_bee(bee_data.clone()).await.unwrap();
+
+        let result = delete_bee(&config, db.clone(), &bee_data).await;
+
+        assert!(result.is_err());
+        assert!(other_dir.path().exists());
+        assert_eq!(get_bees(db).await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn should_only_accept_stopped_or_missing_containers() {
+        let docker_error = |status_code| {
+            anyhow::Error::from(bollard::errors::Error::DockerResponseServerError {
+                status_code,
+                message: "some message".to_owned(),
+            })
+        };
+
+        assert!(is_docker_status(&docker_error(304), &[304, 404]));
+        assert!(is_docker_status(&docker_error(404), &[304, 404]));
+        assert!(!is_docker_status(&docker_error(500), &[304, 404]));
+        assert!(!is_docker_status(&anyhow::anyhow!("timeout"), &[304, 404]));
+    }
+
     #[tokio::test]
     async fn should_convert_bee_data_to_info() {
         let config = Config {
@@ -0,0 +1,268 @@
+use anyhow::Result;
+
+use crate::{
+    core::database::BeeDatabase,
+    models::{
+        bee::{BeeData, DeletionRequest, PendingBeeDeletion},
+        bee_api::BeeFunds,
+        config::Config,
+        http_error::ApiError,
+        token::Principal,
+    },
+    utils::{
+        time::now_secs,
+        token::{generate_token, hash_token},
+    },
+};
+
+use super::{
+    bee_api_fn::{get_bee_api_url, get_bee_stake, get_bee_wallet},
+    bee_fn::{bee_data_to_info, get_bee_deletion, get_node_name},
+};
+
+/// Long enough to review what the deletion destroys before confirming it.
+const DELETION_TOKEN_TTL_SECS: u64 = 300;
+
+/// Funds the node reports, a stopped node leaves them unknown.
+pub async fn get_bee_funds(config: &Config, bee_data: &BeeData) -> Result<BeeFunds> {
+    let api_url = get_bee_api_url(&bee_data_to_info(config, bee_data)?);
+    let (wallet, stake) = tokio::join!(get_bee_wallet(&api_url), get_bee_stake(&api_url));
+    let wallet = wallet.ok();
+
+    Ok(BeeFunds {
+        bzz_balance: wallet.as_ref().map(|wallet| wallet.bzz_balance.to_owned()),
+        native_token_balance: wallet.map(|wallet| wallet.native_token_balance),
+        staked_amount: stake.ok().map(|stake| stake.staked_amount),
+    })
+}
+
+/// Issues a token confirming the deletion of the node with the principal's token only.
+pub async fn request_bee_deletion(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    bee_data: &BeeData,
+    principal: &Principal,
+) -> Result<PendingBeeDeletion> {
+    let (deletion, funds) = tokio::join!(
+        get_bee_deletion(config, bee_data),
+        get_bee_funds(config, bee_data)
+    );
+    let (deletion, funds) = (deletion?, funds?);
+    let token = generate_token();
+    let expires_at = now_secs() + DELETION_TOKEN_TTL_SECS;
+
+    db.put_deletion_request(DeletionRequest {
+        bee_id: bee_data.id,
+        token_id: principal.token_id.to_owned(),
+        hash: hash_token(&token),
+        expires_at,
+        funds: funds.to_owned(),
+    })
+    .await?;
+
+    Ok(PendingBeeDeletion {
+        token,
+        expires_at,
+        deletion,
+        funds,
+    })
+}
+
+/// Checks the token and the node funds, leaving the token for `take_deletion_token`.
+pub async fn check_deletion_token(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    bee_id: u8,
+    token: &str,
+    principal: &Principal,
+    force: bool,
+) -> Result<()> {
+    let message = match db.get_deletion_request(bee_id, &hash_token(token)).await? {
+        Some(request) if request.token_id != principal.token_id => {
+            "Deletion token was issued to another API token."
+        }
+        Some(request) if request.expires_at < now_secs() => "Deletion token has expired.",
+        Some(request) => return check_bee_funds(config, &request, force),
+        None => "Unknown or already used deletion token.",
+    };
+    Err(ApiError::Forbidden(message.to_owned()).into())
+}
+
+/// Consumes the token, so a single deletion starts with it.
+pub async fn take_deletion_token(
+    db: Box<dyn BeeDatabase>,
+    bee_id: u8,
+    token: &str,
+    principal: &Principal,
+) -> Result<DeletionRequest> {
+    db.take_deletion_request(bee_id, &principal.token_id, &hash_token(token))
+        .await?
+        .ok_or_else(|| {
+            ApiError::Forbidden("Unknown or already used deletion token.".to_owned()).into()
+        })
+}
+
+/// With `protect_funded`, refuses deleting a node that reported funds or stake when
+/// the deletion was requested, or didn't report them all.
+fn check_bee_funds(config: &Config, request: &DeletionRequest, force: bool) -> Result<()> {
+    if force || !config.bee.protect_funded || !request.funds.may_have_funds() {
+        return Ok(());
+    }
+    Err(ApiError::Conflict(format!(
+        "{} holds funds or stake, or didn't report them, force the deletion to proceed.",
+        get_node_name(request.bee_id)
+    ))
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::database::MockDbService, models::token::Role};
+
+    fn principal(token_id: &str) -> Principal {
+        Principal {
+            token_id: token_id.to_owned(),
+            name: "ops".to_owned(),
+            role: Role::Admin,
+            bee_ids: None,
+        }
+    }
+
+    fn empty_funds() -> BeeFunds {
+        BeeFunds {
+            bzz_balance: Some("0".to_owned()),
+            native_token_balance: Some("0".to_owned()),
+            staked_amount: Some("0".to_owned()),
+        }
+    }
+
+    async fn put_request(db: &MockDbService, token: &str, expires_at: u64, funds: BeeFunds) {
+        db.put_deletion_request(DeletionRequest {
+            bee_id: 1,
+            token_id: "t1".to_owned(),
+            hash: hash_token(token),
+            expires_at,
+            funds,
+        })
+        .await
+        .unwrap();
+    }
+
+    /// Checks then consumes the token, as a deletion does.
+    async fn confirm(
+        config: &Config,
+        db: &MockDbService,
+        bee_id: u8,
+        token_id: &str,
+        force: bool,
+    ) -> Result<()> {
+        let db = Box::new(db.to_owned());
+        let principal = principal(token_id);
+        check_deletion_token(config, db.clone(), bee_id, "some-token", &principal, force).await?;
+        take_deletion_token(db, bee_id, "some-token", &principal).await?;
+        Ok(())
+    }
+
+    fn protecting_config() -> Config {
+        let mut config = Config::default();
+        config.bee.protect_funded = true;
+        config
+    }
+
+    #[tokio::test]
+    async fn should_confirm_deletion_once() {
+        let db = MockDbService::default();
+        let config = protecting_config();
+        put_request(&db, "some-token", now_secs() + 60, empty_funds()).await;
+
+        confirm(&config, &db, 1, "t1", false).await.unwrap();
+
+        let err = confirm(&config, &db, 1, "t1", false).await.unwrap_err();
+        assert_eq!(
+            err.downcast::<ApiError>().unwrap(),
+            ApiError::Forbidden("Unknown or already used deletion token.".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn should_bind_token_to_node_and_api_token() {
+        let db = MockDbService::default();
+        let config = Config::default();
+        put_request(&db, "some-token", now_secs() + 60, empty_funds()).await;
+
+        assert!(confirm(&config, &db, 2, "t1", false).await.is_err());
+        assert!(confirm(&config, &db, 1, "t2", false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_keep_token_refused_to_another_api_token() {
+        let db = MockDbService::default();
+        put_request(&db, "some-token", now_secs() + 60, empty_funds()).await;
+
+        assert!(
+            take_deletion_token(Box::new(db.clone()), 1, "some-token", &principal("t2"))
+                .await
+                .is_err()
+        );
+
+        confirm(&Config::default(), &db, 1, "t1", false)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_reject_expired_token() {
+        let db = MockDbService::default();
+        put_request(&db, "some-token", now_secs() - 1, empty_funds()).await;
+
+        let err = confirm(&Config::default(), &db, 1, "t1", false)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.downcast::<ApiError>().unwrap(),
+            ApiError::Forbidden("Deletion token has expired.".to_owned())
+        );
+        let db = Box::new(db);
+        assert!(take_deletion_token(db, 1, "some-token", &principal("t1"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn should_keep_token_of_deletion_refused_for_funds() {
+        let db = MockDbService::default();
+        let config = protecting_config();
+        let funds = BeeFunds {
+            staked_amount: None,
+            ..empty_funds()
+        };
+        put_request(&db, "some-token", now_secs() + 60, funds).await;
+
+        let err = confirm(&config, &db, 1, "t1", false).await.unwrap_err();
+        assert_eq!(
+            err.downcast::<ApiError>().unwrap().status_code(),
+            axum::http::StatusCode::CONFLICT
+        );
+
+        confirm(&config, &db, 1, "t1", true).await.unwrap();
+    }
+
+    #[test]
+    fn should_count_unknown_funds() {
+        assert!(!empty_funds().may_have_funds());
+
+        let funds = BeeFunds {
+            native_token_balance: None,
+            ..empty_funds()
+        };
+        assert!(funds.may_have_funds());
+
+        let funds = BeeFunds {
+            staked_amount: Some("100000000000000000".to_owned()),
+            ..empty_funds()
+        };
+        assert!(funds.may_have_funds());
+    }
+}
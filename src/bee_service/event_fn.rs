@@ -0,0 +1,301 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::Result;
+use futures_util::StreamExt;
+
+use crate::{
+    core::{database::BeeDatabase, docker::BeeDocker},
+    models::event::{BeeEvent, BeeEventKind, BeeEventQuery, ContainerEvent},
+};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Last lifecycle state seen for a node container, used to tell transitions apart.
+#[derive(Default, Clone, Debug)]
+pub struct ContainerState {
+    last_action: Option<String>,
+    image: Option<String>,
+    health: Option<String>,
+}
+
+pub fn get_bee_id_from_name(name: &str) -> Option<u8> {
+    name.strip_prefix("node_")?.parse().ok()
+}
+
+pub async fn get_bee_events(
+    db: Box<dyn BeeDatabase>,
+    bee_id: u8,
+    query: &BeeEventQuery,
+) -> Result<Vec<BeeEvent>> {
+    db.get_bee_events(bee_id, query).await
+}
+
+async fn get_last_image(db: Box<dyn BeeDatabase>, bee_id: u8) -> Result<Option<String>> {
+    let query = BeeEventQuery {
+        limit: Some(usize::MAX),
+        ..Default::default()
+    };
+    Ok(db
+        .get_bee_events(bee_id, &query)
+        .await?
+        .into_iter()
+        .find_map(|event| event.image))
+}
+
+/// Turns a raw container event into the node events it stands for and stores them.
+pub async fn record_container_event(
+    db: Box<dyn BeeDatabase>,
+    states: &mut HashMap<u8, ContainerState>,
+    container_event: &ContainerEvent,
+) -> Result<Vec<BeeEvent>> {
+    let Some(bee_id) = get_bee_id_from_name(&container_event.name) else {
+        return Ok(vec![]);
+    };
+    let state = states.entry(bee_id).or_default();
+
+    let new_event = |kind| BeeEvent {
+        bee_id,
+        timestamp: container_event.timestamp,
+        kind,
+        image: container_event.image.to_owned(),
+        ..Default::default()
+    };
+
+    let mut events = vec![];
+    let action = container_event.action.as_str();
+    match action {
+        "create" => {
+            let previous_image = match &state.image {
+                Some(image) => Some(image.to_owned()),
+                None => get_last_image(db.clone(), bee_id).await?,
+            };
+            events.push(new_event(match state.last_action.as_deref() {
+                Some("destroy") => BeeEventKind::Recreated,
+                _ => BeeEventKind::Created,
+            }));
+            if previous_image.is_some() && previous_image != container_event.image {
+                events.push(BeeEvent {
+                    previous_image,
+                    ..new_event(BeeEventKind::ImageChanged)
+                });
+            }
+        }
+        "start" => events.push(new_event(match state.last_action.as_deref() {
+            Some("die") => BeeEventKind::Restarted,
+            _ => BeeEventKind::Started,
+        })),
+        "stop" => events.push(new_event(BeeEventKind::Stopped)),
+        "die" => events.push(BeeEvent {
+            exit_code: container_event.exit_code,
+            ..new_event(BeeEventKind::Died)
+        }),
+        "oom" => events.push(new_event(BeeEventKind::OomKilled)),
+        _ => {
+            if let Some(health) = action.strip_prefix("health_status: ") {
+                if state.health.as_deref() != Some(health) {
+                    state.health = Some(health.to_owned());
+                    events.push(BeeEvent {
+                        health: Some(health.to_owned()),
+                        ..new_event(BeeEventKind::Health)
+                    });
+                }
+            }
+        }
+    }
+
+    if matches!(action, "create" | "start" | "stop" | "die" | "destroy") {
+        state.last_action = Some(action.to_owned());
+    }
+    if container_event.image.is_some() {
+        state.image = container_event.image.to_owned();
+    }
+
+    for event in &events {
+        db.add_bee_event(event.to_owned()).await?;
+    }
+
+    Ok(events)
+}
+
+/// Follows the Docker events stream for as long as the process runs, reconnecting on failure.
+pub async fn watch_bee_events(db: Box<dyn BeeDatabase>, docker: Box<dyn BeeDocker>) {
+    let mut states = HashMap::new();
+
+    loop {
+        match docker.get_bee_container_events().await {
+            Ok(mut container_events) => {
+                while let Some(container_event) = container_events.next().await {
+                    match container_event {
+                        Ok(container_event) => {
+                            if let Err(err) =
+                                record_container_event(db.clone(), &mut states, &container_event)
+                                    .await
+                            {
+                                tracing::error!(
+                                    "Failed to record event of {}: {}",
+                                    container_event.name,
+                                    err
+                                );
+                            }
+                        }
+                        Err(err) => {
+                            tracing::error!("Docker events stream failed: {}", err);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => tracing::error!("Unable to subscribe to Docker events: {}", err),
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::database::MockDbService;
+
+    fn container_event(action: &str, timestamp: u64, image: &str) -> ContainerEvent {
+        ContainerEvent {
+            name: "node_12".to_owned(),
+            action: action.to_owned(),
+            timestamp,
+            image: Some(image.to_owned()),
+            exit_code: None,
+        }
+    }
+
+    async fn record_all(
+        db: Box<dyn BeeDatabase>,
+        container_events: Vec<ContainerEvent>,
+    ) -> Vec<BeeEventKind> {
+        let mut states = HashMap::new();
+        let mut kinds = vec![];
+        for container_event in container_events {
+            let events = record_container_event(db.clone(), &mut states, &container_event)
+                .await
+                .unwrap();
+            kinds.extend(events.iter().map(|event| event.kind));
+        }
+        kinds
+    }
+
+    #[test]
+    fn should_parse_bee_id_from_container_name() {
+        assert_eq!(get_bee_id_from_name("node_12"), Some(12));
+        assert_eq!(get_bee_id_from_name("node_xx"), None);
+        assert_eq!(get_bee_id_from_name("postgres"), None);
+    }
+
+    #[tokio::test]
+    async fn should_detect_restart_by_docker() {
+        let db = Box::new(MockDbService::default());
+        let image = "ethersphere/bee:2.5.0";
+
+        let kinds = record_all(
+            db,
+            vec![
+                container_event("create", 1, image),
+                container_event("start", 2, image),
+                container_event("oom", 3, image),
+                ContainerEvent {
+                    exit_code: Some(137),
+                    ..container_event("die", 3, image)
+                },
+                container_event("start", 4, image),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            kinds,
+            vec![
+                BeeEventKind::Created,
+                BeeEventKind::Started,
+                BeeEventKind::OomKilled,
+                BeeEventKind::Died,
+                BeeEventKind::Restarted
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_not_report_restart_after_stop() {
+        let db = Box::new(MockDbService::default());
+        let image = "ethersphere/bee:2.5.0";
+
+        let kinds = record_all(
+            db,
+            vec![
+                container_event("die", 1, image),
+                container_event("stop", 1, image),
+                container_event("start", 2, image),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            kinds,
+            vec![
+                BeeEventKind::Died,
+                BeeEventKind::Stopped,
+                BeeEventKind::Started
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_detect_recreation_with_new_image() {
+        let db = Box::new(MockDbService::default());
+
+        record_all(
+            db.clone(),
+            vec![container_event("create", 1, "ethersphere/bee:2.4.0")],
+        )
+        .await;
+        let kinds = record_all(
+            db.clone(),
+            vec![
+                container_event("destroy", 2, "ethersphere/bee:2.4.0"),
+                container_event("create", 3, "ethersphere/bee:2.5.0"),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            kinds,
+            vec![BeeEventKind::Recreated, BeeEventKind::ImageChanged]
+        );
+        let events = get_bee_events(db, 12, &BeeEventQuery::default())
+            .await
+            .unwrap();
+        let image_changed = events
+            .iter()
+            .find(|event| event.kind == BeeEventKind::ImageChanged)
+            .unwrap();
+        assert_eq!(
+            image_changed.previous_image,
+            Some("ethersphere/bee:2.4.0".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn should_only_record_health_transitions() {
+        let db = Box::new(MockDbService::default());
+        let image = "ethersphere/bee:2.5.0";
+
+        let kinds = record_all(
+            db,
+            vec![
+                container_event("health_status: healthy", 1, image),
+                container_event("health_status: healthy", 2, image),
+                container_event("health_status: unhealthy", 3, image),
+            ],
+        )
+        .await;
+
+        assert_eq!(kinds, vec![BeeEventKind::Health, BeeEventKind::Health]);
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use crate::{
+    core::{
+        database::{get_all_audit_entries, BeeDatabase},
+        jobs::Jobs,
+    },
+    models::{
+        config::Config,
+        export::{
+            ConflictMode, DatabaseExport, ExportImportReport, RecordChanges, RecordsImport,
+            EXPORT_VERSION,
+        },
+        http_error::ApiError,
+    },
+    utils::time::now_secs,
+};
+
+use super::bee_fn::{get_node_name, BEE_IDS, MAX_BEES};
+
+pub async fn export_database(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    jobs: &Jobs,
+) -> Result<DatabaseExport> {
+    Ok(DatabaseExport {
+        version: EXPORT_VERSION,
+        exported_at: now_secs(),
+        instance_id: config.instance_id.to_owned(),
+        bees: db.get_bees().await?,
+        tokens: db.get_tokens().await?,
+        audit: get_all_audit_entries(db.as_ref()).await?,
+        events: db.get_all_bee_events().await?,
+        jobs: jobs.get_all(),
+    })
+}
+
+fn validate_export(export: &DatabaseExport) -> Result<()> {
+    if export.version == 0 || export.version > EXPORT_VERSION {
+        return Err(ApiError::Validation(format!(
+            "Unsupported export version {}, this ruche reads up to {}",
+            export.version, EXPORT_VERSION
+        ))
+        .into());
+    }
+
+    let mut bee_ids = HashSet::new();
+    for bee in &export.bees {
+        if !BEE_IDS.contains(&bee.id) {
+            return Err(ApiError::Validation(format!("Invalid node id {}", bee.id)).into());
+        }
+        if !bee_ids.insert(bee.id) {
+            return Err(
+                ApiError::Validation(format!("{} appears twice", get_node_name(bee.id))).into(),
+            );
+        }
+    }
+    let mut token_ids = HashSet::new();
+    let mut token_hashes = HashSet::new();
+    for token in &export.tokens {
+        if !token_ids.insert(&token.id) || !token_hashes.insert(&token.hash) {
+            return Err(ApiError::Validation(format!("Token '{}' appears twice", token.id)).into());
+        }
+    }
+    Ok(())
+}
+
+fn sort_record<T>(changes: &mut RecordChanges<T>, exists: bool, mode: ConflictMode, key: T) {
+    match (exists, mode) {
+        (false, _) => changes.added.push(key),
+        (true, ConflictMode::Overwrite) => changes.overwritten.push(key),
+        (true, _) => changes.skipped.push(key),
+    }
+}
+
+/// Serialized form of a log record, as audit entries and events have no id.
+fn to_key<T: serde::Serialize>(record: &T) -> Result<String> {
+    Ok(serde_json::to_string(record)?)
+}
+
+/// Validates the dump and loads it in one transaction, nothing is written if
+/// it's invalid, conflicts in `fail` mode or a write fails.
+pub async fn import_database(
+    db: Box<dyn BeeDatabase>,
+    export: DatabaseExport,
+    mode: ConflictMode,
+    dry_run: bool,
+) -> Result<ExportImportReport> {
+    validate_export(&export)?;
+
+    let existing_bees = db.get_bees().await?;
+    let existing_tokens = db.get_tokens().await?;
+    let mut report = ExportImportReport {
+        dry_run,
+        ..Default::default()
+    };
+    for bee in &export.bees {
+        let exists = existing_bees.iter().any(|existing| existing.id == bee.id);
+        sort_record(&mut report.bees, exists, mode, bee.id);
+    }
+    for token in &export.tokens {
+        let exists = existing_tokens
+            .iter()
+            .any(|existing| existing.id == token.id);
+        sort_record(&mut report.tokens, exists, mode, token.id.to_owned());
+    }
+
+    if mode == ConflictMode::Fail {
+        let conflicts = report
+            .bees
+            .skipped
+            .iter()
+            .map(|bee_id| get_node_name(*bee_id))
+            .chain(
+                report
+                    .tokens
+                    .skipped
+                    .iter()
+                    .map(|token_id| format!("token {}", token_id)),
+            )
+            .collect::<Vec<_>>();
+        if !conflicts.is_empty() {
+            return Err(ApiError::Conflict(format!(
+                "Already registered: {}",
+                conflicts.join(", ")
+            ))
+            .into());
+        }
+    }
+    if (existing_bees.len() + report.bees.added.len()) as u64 > MAX_BEES {
+        return Err(ApiError::Capacity("Max capacity reached".to_owned()).into());
+    }
+    for token in &export.tokens {
+        if report.tokens.skipped.contains(&token.id) {
+            continue;
+        }
+        if existing_tokens.iter().any(|existing| {
+            existing.hash == token.hash
+                && existing.id != token.id
+                && !report.tokens.overwritten.contains(&existing.id)
+        }) {
+            return Err(ApiError::Conflict(format!(
+                "Token '{}' has the secret of an existing token",
+                token.id
+            ))
+            .into());
+        }
+    }
+
+    let existing_audit = get_all_audit_entries(db.as_ref())
+        .await?
+        .iter()
+        .map(to_key)
+        .collect::<Result<HashSet<_>>>()?;
+    let audit = export
+        .audit
+        .into_iter()
+        .filter(|entry| to_key(entry).is_ok_and(|key| !existing_audit.contains(&key)))
+        .collect::<Vec<_>>();
+    let existing_events = db
+        .get_all_bee_events()
+        .await?
+        .iter()
+        .map(to_key)
+        .collect::<Result<HashSet<_>>>()?;
+    let events = export
+        .events
+        .into_iter()
+        .filter(|event| to_key(event).is_ok_and(|key| !existing_events.contains(&key)))
+        .collect::<Vec<_>>();
+    report.audit_entries = audit.len();
+    report.events = events.len();
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    let (added_bees, overwritten_bees) = export
+        .bees
+        .into_iter()
+        .filter(|bee| !report.bees.skipped.contains(&bee.id))
+        .partition(|bee| report.bees.added.contains(&bee.id));
+    let tokens = export
+        .tokens
+        .into_iter()
+        .filter(|token| !report.tokens.skipped.contains(&token.id))
+        .collect();
+    db.import_records(RecordsImport {
+        added_bees,
+        overwritten_bees,
+        tokens,
+        audit,
+        events,
+    })
+    .await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::database::MockDbService,
+        models::{audit::AuditEntry, bee::BeeData, token::ApiToken},
+    };
+
+    fn bee(id: u8, neighborhood: &str) -> BeeData {
+        BeeData {
+            id,
+            neighborhood: neighborhood.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    async fn neighborhood(db: &dyn BeeDatabase, bee_id: u8) -> String {
+        let bees = db.get_bees().await.unwrap();
+        let bee = bees.into_iter().find(|bee| bee.id == bee_id).unwrap();
+        bee.neighborhood
+    }
+
+    async fn setup() -> Box<dyn BeeDatabase> {
+        let db: Box<dyn BeeDatabase> = Box::new(MockDbService::default());
+        db.add_bee(bee(1, "old")).await.unwrap();
+        db.add_token(ApiToken {
+            id: "t1".to_owned(),
+            hash: "h1".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        db.add_audit_entry(AuditEntry {
+            timestamp: 1,
+            action: "bee.create".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        db
+    }
+
+    fn dump() -> DatabaseExport {
+        DatabaseExport {
+            version: EXPORT_VERSION,
+            bees: vec![bee(1, "new"), bee(2, "new")],
+            audit: vec![
+                AuditEntry {
+                    timestamp: 1,
+                    action: "bee.create".to_owned(),
+                    ..Default::default()
+                },
+                AuditEntry {
+                    timestamp: 2,
+                    action: "bee.stop".to_owned(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn should_round_trip_export() {
+        let db = setup().await;
+        let export = export_database(&Config::default(), db, &Jobs::default())
+            .await
+            .unwrap();
+
+        let target: Box<dyn BeeDatabase> = Box::new(MockDbService::default());
+        let json = serde_json::to_string(&export).unwrap();
+        let report = import_database(
+            target.clone(),
+            serde_json::from_str(&json).unwrap(),
+            ConflictMode::Fail,
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.bees.added, vec![1]);
+        assert_eq!(report.tokens.added, vec!["t1".to_owned()]);
+        assert_eq!(report.audit_entries, 1);
+        assert_eq!(neighborhood(target.as_ref(), 1).await, "old");
+    }
+
+    #[tokio::test]
+    async fn should_fail_on_conflict_without_writing() {
+        let db = setup().await;
+
+        let result = import_database(db.clone(), dump(), ConflictMode::Fail, false).await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Already registered: node_01"
+        );
+        assert_eq!(db.count_bees().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_skip_conflicts() {
+        let db = setup().await;
+
+        let report = import_database(db.clone(), dump(), ConflictMode::Skip, false)
+            .await
+            .unwrap();
+
+        assert_eq!(report.bees.added, vec![2]);
+        assert_eq!(report.bees.skipped, vec![1]);
+        assert_eq!(report.audit_entries, 1);
+        assert_eq!(neighborhood(db.as_ref(), 1).await, "old");
+        assert_eq!(db.count_bees().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_overwrite_conflicts() {
+        let db = setup().await;
+
+        let report = import_database(db.clone(), dump(), ConflictMode::Overwrite, false)
+            .await
+            .unwrap();
+
+        assert_eq!(report.bees.overwritten, vec![1]);
+        assert_eq!(neighborhood(db.as_ref(), 1).await, "new");
+        assert_eq!(db.count_bees().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_not_write_on_dry_run() {
+        let db = setup().await;
+
+        let report = import_database(db.clone(), dump(), ConflictMode::Skip, true)
+            .await
+            .unwrap();
+
+        assert_eq!(report.bees.added, vec![2]);
+        assert_eq!(db.count_bees().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_dump() {
+        let db = setup().await;
+        let newer = DatabaseExport {
+            version: EXPORT_VERSION + 1,
+            ..dump()
+        };
+        let duplicated = DatabaseExport {
+            bees: vec![bee(3, ""), bee(3, "")],
+            ..dump()
+        };
+
+        assert!(
+            import_database(db.clone(), newer, ConflictMode::Skip, false)
+                .await
+                .is_err()
+        );
+        assert!(import_database(db, duplicated, ConflictMode::Skip, false)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn should_reject_node_ids_out_of_range() {
+        for id in [0, MAX_BEES as u8 + 1] {
+            let export = DatabaseExport {
+                bees: vec![bee(id, "")],
+                ..dump()
+            };
+
+            let result = import_database(setup().await, export, ConflictMode::Skip, true).await;
+
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                format!("Invalid node id {}", id)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn should_replace_overwritten_token() {
+        let db = setup().await;
+        let export = DatabaseExport {
+            tokens: vec![ApiToken {
+                id: "t1".to_owned(),
+                hash: "h2".to_owned(),
+                ..Default::default()
+            }],
+            ..dump()
+        };
+
+        let report = import_database(db.clone(), export, ConflictMode::Overwrite, false)
+            .await
+            .unwrap();
+
+        assert_eq!(report.tokens.overwritten, vec!["t1".to_owned()]);
+        let tokens = db.get_tokens().await.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].hash, "h2");
+    }
+}
@@ -0,0 +1,456 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+use anyhow::Result;
+
+use crate::{
+    core::{database::BeeDatabase, docker::BeeDocker},
+    models::{
+        bee::BeeData,
+        config::Config,
+        import::{BeeContainer, ImportConflict, ImportReport, ImportSource, ImportedBee},
+    },
+    utils::time::now_secs,
+};
+
+use super::{
+    bee_fn::{get_node_name, MAX_BEES},
+    event_fn::get_bee_id_from_name,
+    network_fn::{get_api_port, get_p2p_port},
+    storage_fn::{get_storage_roots, get_volumes, is_inside_storage_roots, BEE_DATA_DIRS},
+};
+
+/// Where bee keeps its data in the container unless `BEE_DATA_DIR` says otherwise.
+const DEFAULT_CONTAINER_DATA_DIR: &str = "/home/bee/.bee";
+
+/// Node id of a `node_xx` name, other spellings of the id are refused.
+fn parse_node_name(name: &str) -> Option<u8> {
+    get_bee_id_from_name(name).filter(|id| get_node_name(*id) == name)
+}
+
+fn parse_env_bool(container: &BeeContainer, key: &str, default: bool) -> Result<bool, String> {
+    match container.env.get(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("{} has an invalid value '{}'", key, value)),
+        None => Ok(default),
+    }
+}
+
+/// Checks the port bee listens on matches the one ruche assigns to the node id,
+/// otherwise recreating the container would move the node to another port.
+fn check_env_port(container: &BeeContainer, key: &str, expected: &str) -> Result<(), String> {
+    let Some(addr) = container.env.get(key) else {
+        return Ok(());
+    };
+    let port = addr.rsplit(':').next().unwrap_or_default();
+    if port != expected {
+        return Err(format!(
+            "{} uses port {}, ruche assigns {} to this node",
+            key, port, expected
+        ));
+    }
+    Ok(())
+}
+
+/// Rebuilds the node settings from the environment and bind mounts of its container.
+pub fn infer_bee_data(config: &Config, container: &BeeContainer) -> Result<BeeData, String> {
+    let id = parse_node_name(&container.name)
+        .ok_or_else(|| "container name doesn't match node_xx".to_owned())?;
+
+    let container_data_dir = container
+        .env
+        .get("BEE_DATA_DIR")
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_CONTAINER_DATA_DIR);
+    let data_dir = container
+        .mounts
+        .get(container_data_dir)
+        .ok_or_else(|| format!("no host directory mounted on {}", container_data_dir))?;
+
+    let api_port = get_api_port(config, id).map_err(|err| err.to_string())?;
+    let p2p_port = get_p2p_port(config, id).map_err(|err| err.to_string())?;
+    check_env_port(container, "BEE_API_ADDR", &api_port)?;
+    check_env_port(container, "BEE_P2P_ADDR", &p2p_port)?;
+
+    Ok(BeeData {
+        id,
+        neighborhood: container
+            .env
+            .get("BEE_TARGET_NEIGHBORHOOD")
+            .cloned()
+            .unwrap_or_default(),
+        full_node: parse_env_bool(container, "BEE_FULL_NODE", config.bee.full_node)?,
+        swap_enable: parse_env_bool(container, "BEE_SWAP_ENABLE", config.bee.swap_enable)?,
+        reserve_doubling: parse_env_bool(
+            container,
+            "BEE_RESERVE_CAPACITY_DOUBLING",
+            config.bee.reserve_doubling,
+        )?,
+        data_dir: data_dir.to_owned(),
+        created_at: now_secs(),
+        ..Default::default()
+    })
+}
+
+/// Directories which may hold nodes: the volumes, the storage roots and their
+/// subdirectories, as nodes may have been laid out differently before.
+fn get_parent_dirs(config: &Config) -> Result<BTreeSet<PathBuf>> {
+    let mut parent_dirs = get_volumes(config)?
+        .into_iter()
+        .map(|volume| volume.path)
+        .collect::<BTreeSet<_>>();
+    for root in get_storage_roots(config) {
+        if !root.is_dir() {
+            continue;
+        }
+        for entry in std::fs::read_dir(&root)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                parent_dirs.insert(path);
+            }
+        }
+        parent_dirs.insert(root);
+    }
+    Ok(parent_dirs)
+}
+
+/// `node_xx` directories holding bee data in the storage volumes and roots.
+pub fn find_node_dirs(config: &Config) -> Result<Vec<(u8, PathBuf)>> {
+    let mut node_dirs = Vec::new();
+    for parent_dir in get_parent_dirs(config)? {
+        if !parent_dir.is_dir() {
+            continue;
+        }
+        for entry in std::fs::read_dir(&parent_dir)? {
+            let path = entry?.path();
+            let id = path
+                .file_name()
+                .and_then(|name| parse_node_name(&name.to_string_lossy()));
+            let has_bee_data = BEE_DATA_DIRS.iter().any(|dir| path.join(dir).is_dir());
+            if let (Some(id), true) = (id, has_bee_data) {
+                node_dirs.push((id, path));
+            }
+        }
+    }
+    node_dirs.sort();
+    Ok(node_dirs)
+}
+
+/// A node left untouched by an import or a rebuild.
+pub fn conflict(name: &str, reason: impl Into<String>) -> ImportConflict {
+    ImportConflict {
+        name: name.to_owned(),
+        reason: reason.into(),
+    }
+}
+
+/// Decides what to register without touching anything. Containers take precedence
+/// over bare data dirs, which get the default settings of the config.
+pub fn plan_import(
+    config: &Config,
+    registered: &[BeeData],
+    containers: &[BeeContainer],
+    node_dirs: &[(u8, PathBuf)],
+) -> (Vec<BeeData>, ImportReport) {
+    let mut report = ImportReport::default();
+    let mut candidates: Vec<(BeeData, ImportSource)> = Vec::new();
+
+    for container in containers {
+        match infer_bee_data(config, container) {
+            Ok(bee_data) if !is_inside_storage_roots(config, &bee_data.data_dir) => {
+                report.conflicts.push(conflict(
+                    &container.name,
+                    format!(
+                        "'{}' isn't inside a configured storage root",
+                        bee_data.data_dir.display()
+                    ),
+                ))
+            }
+            Ok(bee_data) => candidates.push((bee_data, ImportSource::Container)),
+            Err(reason) => report.conflicts.push(conflict(&container.name, reason)),
+        }
+    }
+
+    let container_ids = containers
+        .iter()
+        .filter_map(|container| parse_node_name(&container.name))
+        .collect::<Vec<_>>();
+    for (id, path) in node_dirs {
+        let is_mounted = candidates
+            .iter()
+            .any(|(bee_data, _)| bee_data.data_dir == *path);
+        if is_mounted {
+            continue;
+        }
+        if container_ids.contains(id) {
+            report.conflicts.push(conflict(
+                &get_node_name(*id),
+                format!("'{}' isn't the data dir of its container", path.display()),
+            ));
+            continue;
+        }
+        candidates.push((
+            BeeData {
+                id: *id,
+                neighborhood: String::new(),
+                full_node: config.bee.full_node,
+                swap_enable: config.bee.swap_enable,
+                reserve_doubling: config.bee.reserve_doubling,
+                data_dir: path.to_owned(),
+                created_at: now_secs(),
+                ..Default::default()
+            },
+            ImportSource::DataDir,
+        ));
+    }
+
+    let mut counts = HashMap::<u8, usize>::new();
+    for (bee_data, _) in &candidates {
+        *counts.entry(bee_data.id).or_default() += 1;
+    }
+
+    let mut bees = Vec::new();
+    for (bee_data, source) in candidates {
+        let name = bee_data.name();
+        if counts[&bee_data.id] > 1 {
+            report.conflicts.push(conflict(
+                &name,
+                format!(
+                    "found in several places, including '{}'",
+                    bee_data.data_dir.display()
+                ),
+            ));
+            continue;
+        }
+
+        if let Some(existing) = registered.iter().find(|bee| bee.id == bee_data.id) {
+            if existing.data_dir == bee_data.data_dir {
+                report.already_registered.push(bee_data.id);
+            } else {
+                report.conflicts.push(conflict(
+                    &name,
+                    format!(
+                        "already registered with data dir '{}'",
+                        existing.data_dir.display()
+                    ),
+                ));
+            }
+            continue;
+        }
+        if let Some(existing) = registered
+            .iter()
+            .find(|bee| bee.data_dir == bee_data.data_dir)
+        {
+            report.conflicts.push(conflict(
+                &name,
+                format!(
+                    "'{}' is the data dir of {}",
+                    bee_data.data_dir.display(),
+                    existing.name()
+                ),
+            ));
+            continue;
+        }
+
+        if source == ImportSource::DataDir {
+            report.warnings.push(conflict(
+                &name,
+                "no container to read settings from, the neighborhood is unset and \
+                 the other settings are the config defaults",
+            ));
+        }
+        report.imported.push(ImportedBee {
+            id: bee_data.id,
+            name,
+            data_dir: bee_data.data_dir.to_owned(),
+            source,
+        });
+        bees.push(bee_data);
+    }
+
+    report.imported.sort_by_key(|bee| bee.id);
+    report.already_registered.sort();
+    (bees, report)
+}
+
+/// Registers existing nodes found in Docker and in the storage volumes. Nodes
+/// imported from a bare data dir have no container until they are recreated.
+pub async fn import_bees(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let registered = db.get_bees().await?;
+    let containers = docker.get_bee_containers().await?;
+    let node_dirs = {
+        let config = config.to_owned();
+        tokio::task::spawn_blocking(move || find_node_dirs(&config)).await??
+    };
+
+    let (bees, mut report) = plan_import(config, &registered, &containers, &node_dirs);
+    if !dry_run {
+        add_imported_bees(db, bees, &mut report).await;
+    }
+
+    Ok(ImportReport { dry_run, ..report })
+}
+
+/// Adds each node within the capacity on its own, moving those refused to the
+/// conflicts, so the report stays accurate whatever fails.
+async fn add_imported_bees(
+    db: Box<dyn BeeDatabase>,
+    bees: Vec<BeeData>,
+    report: &mut ImportReport,
+) {
+    for bee_data in bees {
+        let reason = match db
+            .add_bee_within_capacity(bee_data.to_owned(), MAX_BEES)
+            .await
+        {
+            Ok(true) => continue,
+            Ok(false) => "max capacity reached".to_owned(),
+            Err(err) => err.to_string(),
+        };
+        report.imported.retain(|bee| bee.id != bee_data.id);
+        report
+            .warnings
+            .retain(|warning| warning.name != bee_data.name());
+        report.conflicts.push(conflict(&bee_data.name(), reason));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::Volume;
+    use std::path::Path;
+
+    fn setup() -> (tempfile::TempDir, Config) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.network.api_port = "17xx".to_owned();
+        config.network.p2p_port = "18xx".to_owned();
+        config.bee.full_node = true;
+        config.storage.volumes = vec![Volume {
+            path: temp_dir.path().join("disk1"),
+            capacity: 4,
+            max_size_gb: None,
+        }];
+        (temp_dir, config)
+    }
+
+    fn container(id: u8, data_dir: &Path) -> BeeContainer {
+        BeeContainer {
+            name: get_node_name(id),
+            image: "ethersphere/bee:2.5.0".to_owned(),
+            labels: HashMap::new(),
+            env: HashMap::from([
+                ("BEE_API_ADDR".to_owned(), format!("0.0.0.0:17{:02}", id)),
+                ("BEE_P2P_ADDR".to_owned(), format!(":18{:02}", id)),
+                ("BEE_FULL_NODE".to_owned(), "false".to_owned()),
+                ("BEE_TARGET_NEIGHBORHOOD".to_owned(), "0110".to_owned()),
+            ]),
+            mounts: HashMap::from([(DEFAULT_CONTAINER_DATA_DIR.to_owned(), data_dir.to_owned())]),
+        }
+    }
+
+    #[test]
+    fn should_infer_bee_data_from_container() {
+        let (temp_dir, config) = setup();
+        let data_dir = temp_dir.path().join("disk1").join("node_02");
+
+        let bee_data = infer_bee_data(&config, &container(2, &data_dir)).unwrap();
+
+        assert_eq!(bee_data.id, 2);
+        assert_eq!(bee_data.neighborhood, "0110");
+        assert!(!bee_data.full_node);
+        assert_eq!(bee_data.swap_enable, config.bee.swap_enable);
+        assert_eq!(bee_data.data_dir, data_dir);
+    }
+
+    #[test]
+    fn should_refuse_containers_on_other_ports() {
+        let (temp_dir, config) = setup();
+        let mut container = container(2, temp_dir.path());
+        container
+            .env
+            .insert("BEE_API_ADDR".to_owned(), ":1633".to_owned());
+
+        let reason = infer_bee_data(&config, &container).unwrap_err();
+
+        assert_eq!(
+            reason,
+            "BEE_API_ADDR uses port 1633, ruche assigns 1702 to this node"
+        );
+    }
+
+    #[test]
+    fn should_find_node_dirs_with_bee_data() {
+        let (temp_dir, config) = setup();
+        let volume = temp_dir.path().join("disk1");
+        std::fs::create_dir_all(volume.join("node_01").join("keys")).unwrap();
+        std::fs::create_dir_all(volume.join("node_02")).unwrap();
+        std::fs::create_dir_all(volume.join("node_3").join("keys")).unwrap();
+
+        let node_dirs = find_node_dirs(&config).unwrap();
+
+        assert_eq!(node_dirs, vec![(1, volume.join("node_01"))]);
+    }
+
+    #[test]
+    fn should_find_node_dirs_under_storage_root() {
+        let (temp_dir, mut config) = setup();
+        config.storage.root_path = temp_dir.path().join("media");
+        let old_layout = temp_dir.path().join("media").join("swarm_data_01");
+        std::fs::create_dir_all(old_layout.join("node_02").join("keys")).unwrap();
+
+        let node_dirs = find_node_dirs(&config).unwrap();
+
+        assert_eq!(node_dirs, vec![(2, old_layout.join("node_02"))]);
+    }
+
+    #[test]
+    fn should_refuse_container_data_dir_outside_storage_roots() {
+        let (temp_dir, config) = setup();
+        let containers = vec![container(2, &temp_dir.path().join("etc"))];
+
+        let (bees, report) = plan_import(&config, &[], &containers, &[]);
+
+        assert!(bees.is_empty());
+        assert!(report.conflicts[0]
+            .reason
+            .ends_with("isn't inside a configured storage root"));
+    }
+
+    #[tokio::test]
+    async fn should_report_nodes_beyond_capacity() {
+        let (temp_dir, config) = setup();
+        let db = Box::new(crate::core::database::MockDbService::default());
+        // Capacity counts entries, an id out of range takes a place as well.
+        for id in (0..=MAX_BEES as u8).filter(|id| *id != 1) {
+            db.add_bee(BeeData {
+                id,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+        let (bees, mut report) = plan_import(
+            &config,
+            &[],
+            &[],
+            &[(1, temp_dir.path().join("disk1").join("node_01"))],
+        );
+
+        add_imported_bees(db.clone(), bees, &mut report).await;
+
+        assert!(report.imported.is_empty());
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.conflicts[0].reason, "max capacity reached");
+        assert_eq!(db.count_bees().await.unwrap(), MAX_BEES);
+    }
+}
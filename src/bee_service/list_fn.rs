@@ -0,0 +1,237 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use anyhow::Result;
+
+use crate::{
+    core::docker::BeeDocker,
+    models::{
+        bee::{BeeData, BeesQuery, SortOrder},
+        http_error::ApiError,
+        stats::ContainerStats,
+    },
+};
+
+use super::stats_fn::get_bees_stats;
+
+#[derive(Debug, PartialEq)]
+pub enum BeeSortKey {
+    Id,
+    CreatedAt,
+    /// Numeric field of `ContainerStats`, nodes without stats sort last.
+    Metric(String),
+}
+
+fn get_metric(stats: &ContainerStats, metric: &str) -> Option<f64> {
+    serde_json::to_value(stats).ok()?.get(metric)?.as_f64()
+}
+
+pub fn parse_sort_key(sort: Option<&str>) -> Result<BeeSortKey> {
+    match sort {
+        None | Some("id") => Ok(BeeSortKey::Id),
+        Some("created_at") => Ok(BeeSortKey::CreatedAt),
+        Some(metric) if get_metric(&ContainerStats::default(), metric).is_some() => {
+            Ok(BeeSortKey::Metric(metric.to_owned()))
+        }
+        Some(sort) => Err(ApiError::Validation(format!("Unable to sort by '{}'", sort)).into()),
+    }
+}
+
+pub fn filter_bees(
+    bees: Vec<BeeData>,
+    query: &BeesQuery,
+    container_states: &HashMap<String, String>,
+) -> Vec<BeeData> {
+    bees.into_iter()
+        .filter(|bee| {
+            query
+                .full_node
+                .is_none_or(|full_node| bee.full_node == full_node)
+        })
+        .filter(|bee| {
+            query
+                .swap_enable
+                .is_none_or(|swap_enable| bee.swap_enable == swap_enable)
+        })
+        .filter(|bee| {
+            query
+                .neighborhood
+                .as_ref()
+                .is_none_or(|prefix| bee.neighborhood.starts_with(prefix))
+        })
+        .filter(|bee| {
+            query.state.as_ref().is_none_or(|state| {
+                container_states
+                    .get(&bee.name())
+                    .map(String::as_str)
+                    .unwrap_or("missing")
+                    == state
+            })
+        })
+        .collect()
+}
+
+/// Sorts by the key then by id, so pages stay stable across calls.
+pub fn sort_bees(
+    bees: &mut [BeeData],
+    key: &BeeSortKey,
+    order: SortOrder,
+    stats: &HashMap<String, ContainerStats>,
+) {
+    let metric = |bee: &BeeData| match key {
+        BeeSortKey::Metric(metric) => stats
+            .get(&bee.name())
+            .and_then(|stats| get_metric(stats, metric)),
+        _ => None,
+    };
+    bees.sort_by(|a, b| {
+        let ordering = match key {
+            BeeSortKey::Id => a.id.cmp(&b.id),
+            BeeSortKey::CreatedAt => a.created_at.cmp(&b.created_at),
+            BeeSortKey::Metric(_) => match (metric(a), metric(b)) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        };
+        let ordering = match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        };
+        ordering.then(a.id.cmp(&b.id))
+    });
+}
+
+/// Returns the matching nodes count along with the requested page.
+pub async fn list_bees(
+    docker: Box<dyn BeeDocker>,
+    bees: Vec<BeeData>,
+    query: &BeesQuery,
+    key: &BeeSortKey,
+) -> Result<(usize, Vec<BeeData>)> {
+    let container_states = match query.state {
+        Some(_) => docker.get_bee_container_states().await?,
+        None => HashMap::new(),
+    };
+    let mut bees = filter_bees(bees, query, &container_states);
+
+    let stats = match key {
+        BeeSortKey::Metric(_) => {
+            let names = bees.iter().map(BeeData::name).collect::<Vec<_>>();
+            get_bees_stats(docker, &names)
+                .await
+                .into_iter()
+                .map(|stats| (stats.name.to_owned(), stats))
+                .collect()
+        }
+        _ => HashMap::new(),
+    };
+    sort_bees(&mut bees, key, query.order, &stats);
+
+    let total = bees.len();
+    let page = bees
+        .into_iter()
+        .skip(query.offset.unwrap_or_default())
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok((total, page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bee(id: u8, full_node: bool, neighborhood: &str, created_at: u64) -> BeeData {
+        BeeData {
+            id,
+            full_node,
+            neighborhood: neighborhood.to_owned(),
+            created_at,
+            ..Default::default()
+        }
+    }
+
+    fn ids(bees: &[BeeData]) -> Vec<u8> {
+        bees.iter().map(|bee| bee.id).collect()
+    }
+
+    #[test]
+    fn should_parse_sort_keys() {
+        assert_eq!(parse_sort_key(None).unwrap(), BeeSortKey::Id);
+        assert_eq!(
+            parse_sort_key(Some("created_at")).unwrap(),
+            BeeSortKey::CreatedAt
+        );
+        assert_eq!(
+            parse_sort_key(Some("cpu_percent")).unwrap(),
+            BeeSortKey::Metric("cpu_percent".to_owned())
+        );
+        assert!(parse_sort_key(Some("name")).is_err());
+        assert!(parse_sort_key(Some("unknown")).is_err());
+    }
+
+    #[test]
+    fn should_filter_bees() {
+        let bees = vec![
+            bee(1, true, "0101", 0),
+            bee(2, false, "0110", 0),
+            bee(3, true, "1100", 0),
+        ];
+        let states = HashMap::from([("node_01".to_owned(), "running".to_owned())]);
+        let filter = |query: BeesQuery| ids(&filter_bees(bees.clone(), &query, &states));
+
+        assert_eq!(
+            filter(BeesQuery {
+                full_node: Some(true),
+                ..Default::default()
+            }),
+            vec![1, 3]
+        );
+        assert_eq!(
+            filter(BeesQuery {
+                neighborhood: Some("01".to_owned()),
+                ..Default::default()
+            }),
+            vec![1, 2]
+        );
+        assert_eq!(
+            filter(BeesQuery {
+                state: Some("missing".to_owned()),
+                ..Default::default()
+            }),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn should_sort_bees_by_metric_with_missing_stats_last() {
+        let mut bees = vec![
+            bee(1, true, "", 30),
+            bee(2, true, "", 10),
+            bee(3, true, "", 20),
+        ];
+        let stats = HashMap::from([
+            (
+                "node_01".to_owned(),
+                ContainerStats {
+                    cpu_percent: 5.0,
+                    ..Default::default()
+                },
+            ),
+            (
+                "node_03".to_owned(),
+                ContainerStats {
+                    cpu_percent: 50.0,
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+        let metric = BeeSortKey::Metric("cpu_percent".to_owned());
+        sort_bees(&mut bees, &metric, SortOrder::Desc, &stats);
+        assert_eq!(ids(&bees), vec![3, 1, 2]);
+
+        sort_bees(&mut bees, &BeeSortKey::CreatedAt, SortOrder::Asc, &stats);
+        assert_eq!(ids(&bees), vec![2, 3, 1]);
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use futures_util::future::join_all;
+
+use crate::{
+    core::{
+        database::BeeDatabase,
+        docker::BeeDocker,
+        metrics::{BeeMetrics, Metrics},
+    },
+    models::{bee::BeeInfo, config::Config, token::Principal},
+};
+
+use super::{
+    bee_api_fn::{get_bee_api_url, get_bee_stake, get_bee_status, get_bee_wallet},
+    bee_fn::bee_data_to_info,
+};
+
+fn parse_amount(amount: &str) -> Option<f64> {
+    amount.parse().ok()
+}
+
+pub async fn get_bee_metrics(bee: &BeeInfo) -> Result<BeeMetrics> {
+    let api_url = get_bee_api_url(bee);
+    let (status, stake, wallet) = tokio::join!(
+        get_bee_status(&api_url),
+        get_bee_stake(&api_url),
+        get_bee_wallet(&api_url)
+    );
+    let status = status?;
+    let stake = stake.ok();
+    let wallet = wallet.ok();
+
+    Ok(BeeMetrics {
+        peers: status.connected_peers as f64,
+        storage_radius: status.storage_radius as f64,
+        reserve_size: status.reserve_size as f64,
+        stake: stake.and_then(|stake| parse_amount(&stake.staked_amount)),
+        bzz_balance: wallet
+            .as_ref()
+            .and_then(|wallet| parse_amount(&wallet.bzz_balance)),
+        native_balance: wallet.and_then(|wallet| parse_amount(&wallet.native_token_balance)),
+    })
+}
+
+/// Counts registered nodes by container state, `missing` when the container is gone.
+pub fn count_container_states(
+    names: &[String],
+    container_states: &HashMap<String, String>,
+) -> HashMap<String, i64> {
+    let mut counts = HashMap::new();
+    for name in names {
+        let state = container_states
+            .get(name)
+            .map(String::as_str)
+            .unwrap_or("missing");
+        *counts.entry(state.to_owned()).or_default() += 1;
+    }
+    counts
+}
+
+/// Fleet and node metrics only cover the nodes the principal can access.
+pub async fn get_metrics(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+    metrics: &Metrics,
+    principal: &Principal,
+) -> Result<String> {
+    let bees = db
+        .get_bees()
+        .await?
+        .iter()
+        .filter(|bee_data| principal.can_access(bee_data.id))
+        .map(|bee_data| bee_data_to_info(config, bee_data))
+        .collect::<Result<Vec<_>>>()?;
+    let names = bees
+        .iter()
+        .map(|bee| bee.name.to_owned())
+        .collect::<Vec<_>>();
+
+    let container_states = docker.get_bee_container_states().await?;
+    let states = count_container_states(&names, &container_states);
+
+    let scrapes = bees.iter().map(|bee| async {
+        if container_states.get(&bee.name).map(String::as_str) != Some("running") {
+            return (bee.name.to_owned(), None);
+        }
+        match get_bee_metrics(bee).await {
+            Ok(bee_metrics) => (bee.name.to_owned(), Some(bee_metrics)),
+            Err(err) => {
+                tracing::debug!("Failed to scrape {}: {}", bee.name, err);
+                (bee.name.to_owned(), None)
+            }
+        }
+    });
+    let bee_metrics = join_all(scrapes).await;
+
+    metrics.encode_scrape(&states, &bee_metrics).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_count_missing_containers() {
+        let names = vec![
+            "node_01".to_owned(),
+            "node_02".to_owned(),
+            "node_03".to_owned(),
+        ];
+        let container_states = HashMap::from([
+            ("node_01".to_owned(), "running".to_owned()),
+            ("node_02".to_owned(), "running".to_owned()),
+            ("node_99".to_owned(), "exited".to_owned()),
+        ]);
+
+        let counts = count_container_states(&names, &container_states);
+
+        assert_eq!(counts.get("running"), Some(&2));
+        assert_eq!(counts.get("missing"), Some(&1));
+        assert_eq!(counts.get("exited"), None);
+    }
+
+    #[test]
+    fn should_parse_large_amounts() {
+        assert_eq!(parse_amount("100000000000000000"), Some(1e17));
+        assert_eq!(parse_amount("invalid"), None);
+    }
+}
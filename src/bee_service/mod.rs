@@ -1,20 +1,67 @@
+mod audit_fn;
+mod backup_fn;
+mod bee_api_fn;
 mod bee_fn;
+mod deletion_fn;
+mod event_fn;
+mod export_fn;
+mod import_fn;
+mod list_fn;
+mod metrics_fn;
+mod move_fn;
 mod neighborhood_fn;
 mod network_fn;
+mod rebuild_fn;
+mod stats_fn;
 mod storage_fn;
+mod tags_fn;
+mod token_fn;
 
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use anyhow::Result;
+use audit_fn::*;
+use backup_fn::*;
 use bee_fn::*;
+use deletion_fn::*;
+use event_fn::*;
+use export_fn::*;
+use futures_util::stream::BoxStream;
+use import_fn::*;
+use list_fn::*;
+use metrics_fn::*;
+use move_fn::*;
 use neighborhood_fn::*;
+use rebuild_fn::*;
+use stats_fn::*;
 use storage_fn::*;
+use tags_fn::*;
+use token_fn::*;
 
 use crate::{
-    core::{database::BeeDatabase, docker::BeeDocker},
+    core::{
+        database::BeeDatabase,
+        docker::BeeDocker,
+        jobs::Jobs,
+        metrics::Metrics,
+        reservations::{BeeIdReservation, BeeIdReservations, MoveReservation},
+    },
     models::{
-        bee::{BeeData, BeeInfo},
+        audit::{AuditEntry, AuditQuery},
+        backup::BackupInfo,
+        bee::{BeeData, BeeDeletion, BeeInfo, BeesQuery, PendingBeeDeletion, UpdateTagsRequest},
         config::Config,
+        disk::{NodeDiskUsage, ParentDirUsage},
+        event::{BeeEvent, BeeEventQuery},
+        export::{ConflictMode, DatabaseExport, ExportImportReport},
+        http_error::ApiError,
+        import::{ImportReport, RebuildReport},
+        job::Job,
+        stats::ContainerStats,
+        token::{ApiTokenInfo, CreateTokenRequest, NewApiToken, Principal},
     },
 };
 
@@ -23,11 +70,26 @@ pub struct BeeService {
     config: Config,
     db: Box<dyn BeeDatabase>,
     docker: Box<dyn BeeDocker>,
+    metrics: Metrics,
+    jobs: Jobs,
+    reservations: BeeIdReservations,
 }
 
 impl BeeService {
-    pub fn new(config: Config, db: Box<dyn BeeDatabase>, docker: Box<dyn BeeDocker>) -> Self {
-        BeeService { config, db, docker }
+    pub fn new(
+        config: Config,
+        db: Box<dyn BeeDatabase>,
+        docker: Box<dyn BeeDocker>,
+        metrics: Metrics,
+    ) -> Self {
+        BeeService {
+            config,
+            db,
+            docker,
+            metrics,
+            jobs: Jobs::default(),
+            reservations: BeeIdReservations::default(),
+        }
     }
 
     pub fn get_node_name(id: u8) -> String {
@@ -38,16 +100,26 @@ impl BeeService {
         get_neighborhood().await
     }
 
-    pub async fn create_node_dir(&self, bee_id: u8) -> Result<PathBuf> {
-        create_node_dir(&self.config, bee_id).await
+    pub async fn create_node_dir(&self, reservation: &BeeIdReservation) -> Result<PathBuf> {
+        create_node_dir(
+            &self.config,
+            self.db.clone(),
+            &self.reservations,
+            reservation,
+        )
+        .await
+    }
+
+    pub async fn get_node_disk_usage(&self, bee_data: &BeeData) -> Result<NodeDiskUsage> {
+        get_node_disk_usage(bee_data).await
     }
 
-    pub async fn ensure_capacity(&self) -> Result<bool> {
-        ensure_capacity(self.db.clone()).await
+    pub fn get_parent_dirs_usage(&self, bees: &[BeeData]) -> Result<Vec<ParentDirUsage>> {
+        get_parent_dirs_usage(&self.config, bees)
     }
 
-    pub async fn get_new_bee_id(&self) -> Result<u8> {
-        get_new_bee_id(self.db.clone()).await
+    pub async fn reserve_bee_id(&self) -> Result<Option<BeeIdReservation>> {
+        reserve_bee_id(self.db.clone(), &self.reservations, None).await
     }
 
     pub fn new_bee_data(&self, id: u8, neighborhood: &str, data_dir: &PathBuf) -> BeeData {
@@ -70,12 +142,244 @@ impl BeeService {
         get_bees(self.db.clone()).await
     }
 
+    pub fn parse_sort_key(sort: Option<&str>) -> Result<BeeSortKey> {
+        parse_sort_key(sort)
+    }
+
+    pub async fn list_bees(
+        &self,
+        bees: Vec<BeeData>,
+        query: &BeesQuery,
+        key: &BeeSortKey,
+    ) -> Result<(usize, Vec<BeeData>)> {
+        list_bees(self.docker.clone(), bees, query, key).await
+    }
+
+    pub fn parse_tag_filters(filters: &str) -> Result<Vec<TagFilter>> {
+        parse_tag_filters(filters)
+    }
+
+    pub fn matches_tags(bee_data: &BeeData, filters: &[TagFilter]) -> bool {
+        matches_tags(bee_data, filters)
+    }
+
+    pub fn validate_tag_update(request: &UpdateTagsRequest) -> Result<()> {
+        validate_tag_update(request)
+    }
+
+    pub async fn update_bee_tags(
+        &self,
+        bee_id: u8,
+        request: UpdateTagsRequest,
+    ) -> Result<Option<BeeData>> {
+        update_bee_tags(self.db.clone(), bee_id, request).await
+    }
+
+    pub async fn delete_bee_tags(&self, bee_id: u8, keys: Option<&str>) -> Result<Option<BeeData>> {
+        delete_bee_tags(self.db.clone(), bee_id, parse_tag_keys(keys)).await
+    }
+
     pub async fn count_bees(&self) -> Result<u64> {
         count_bees(self.db.clone()).await
     }
 
-    pub async fn delete_bee(&self, bee_id: u8) -> Result<()> {
-        delete_bee(&self.config, self.db.clone(), bee_id).await
+    pub async fn import_bees(&self, dry_run: bool) -> Result<ImportReport> {
+        import_bees(&self.config, self.db.clone(), self.docker.clone(), dry_run).await
+    }
+
+    pub async fn adopt_bee_containers(&self) -> Result<Vec<String>> {
+        adopt_bee_containers(&self.config, self.db.clone(), self.docker.clone()).await
+    }
+
+    pub async fn rebuild_bees(&self, dry_run: bool) -> Result<RebuildReport> {
+        rebuild_bees(&self.config, self.db.clone(), self.docker.clone(), dry_run).await
+    }
+
+    pub async fn get_bee_deletion(&self, bee_data: &BeeData) -> Result<BeeDeletion> {
+        get_bee_deletion(&self.config, bee_data).await
+    }
+
+    /// Runs in a task of its own, so a request timing out can't stop it between
+    /// removing the container and removing the node from the database. The token is
+    /// consumed there once no job runs on the node, and given back if the deletion fails.
+    pub async fn delete_bee(
+        &self,
+        bee_data: &BeeData,
+        token: &str,
+        principal: &Principal,
+        force: bool,
+    ) -> Result<BeeDeletion> {
+        check_deletion_token(
+            &self.config,
+            self.db.clone(),
+            bee_data.id,
+            token,
+            principal,
+            force,
+        )
+        .await?;
+
+        let service = self.clone();
+        let bee_data = bee_data.to_owned();
+        let token = token.to_owned();
+        let principal = principal.to_owned();
+        tokio::spawn(async move {
+            // A move, backup or restore still reads or writes the data dir.
+            if service.is_bee_busy(bee_data.id) {
+                return Err(ApiError::Conflict(format!(
+                    "A job is already running on bee node with id {}.",
+                    bee_data.id
+                ))
+                .into());
+            }
+            let request =
+                take_deletion_token(service.db.clone(), bee_data.id, &token, &principal).await?;
+            let deleted = async {
+                // Before anything is removed, so an unsafe data dir leaves the container in place.
+                check_bee_deletion(&service.config, &bee_data).await?;
+                service.remove_bee_container(&bee_data.name()).await?;
+                delete_bee(&service.config, service.db.clone(), &bee_data).await
+            }
+            .await;
+            if deleted.is_err() {
+                // So the deletion can be retried with the same token.
+                service.db.put_deletion_request(request).await?;
+            }
+            deleted
+        })
+        .await?
+    }
+
+    pub async fn request_bee_deletion(
+        &self,
+        bee_data: &BeeData,
+        principal: &Principal,
+    ) -> Result<PendingBeeDeletion> {
+        request_bee_deletion(&self.config, self.db.clone(), bee_data, principal).await
+    }
+
+    pub async fn resolve_move_destination(
+        &self,
+        bee_data: &BeeData,
+        volume: &Path,
+    ) -> Result<(PathBuf, MoveReservation)> {
+        resolve_move_destination(
+            &self.config,
+            self.db.clone(),
+            &self.reservations,
+            bee_data,
+            volume,
+        )
+        .await
+    }
+
+    pub fn start_bee_move(
+        &self,
+        bee_data: &BeeData,
+        destination: &Path,
+        reservation: MoveReservation,
+    ) -> Option<Job> {
+        start_bee_move(
+            &self.config,
+            self.db.clone(),
+            self.docker.clone(),
+            &self.jobs,
+            bee_data,
+            destination,
+            reservation,
+        )
+    }
+
+    pub fn get_job(&self, job_id: &str) -> Option<Job> {
+        self.jobs.get(job_id)
+    }
+
+    pub fn is_bee_busy(&self, bee_id: u8) -> bool {
+        self.jobs.is_busy(bee_id)
+    }
+
+    pub fn get_bee_jobs(&self, bee_id: u8) -> Vec<Job> {
+        self.jobs.get_bee_jobs(bee_id)
+    }
+
+    pub fn get_backups(&self, bee_id: u8) -> Result<Vec<BackupInfo>> {
+        get_backups(&self.config, Some(bee_id))
+    }
+
+    pub fn get_backup_path(&self, bee_id: u8, name: &str) -> Result<PathBuf> {
+        get_backup_path(&self.config, bee_id, name)
+    }
+
+    pub fn start_bee_backup(
+        &self,
+        bee_data: &BeeData,
+        include_localstore: Option<bool>,
+    ) -> Result<Option<Job>> {
+        get_backup_dir(&self.config)?;
+        let include_localstore =
+            include_localstore.unwrap_or(self.config.backup.include_localstore);
+        Ok(start_bee_backup(
+            &self.config,
+            self.docker.clone(),
+            &self.jobs,
+            bee_data,
+            include_localstore,
+        )
+        .map(|(job, _)| job))
+    }
+
+    pub async fn prepare_bee_restore(
+        &self,
+        name: &str,
+    ) -> Result<(PathBuf, BeeData, BeeIdReservation)> {
+        prepare_bee_restore(
+            &self.config,
+            self.db.clone(),
+            &self.jobs,
+            &self.reservations,
+            name,
+        )
+        .await
+    }
+
+    pub fn start_bee_restore(
+        &self,
+        path: &Path,
+        bee_data: &BeeData,
+        reservation: BeeIdReservation,
+    ) -> Option<Job> {
+        start_bee_restore(
+            &self.config,
+            self.db.clone(),
+            self.docker.clone(),
+            &self.jobs,
+            path,
+            bee_data,
+            reservation,
+        )
+    }
+
+    pub async fn export_database(&self) -> Result<DatabaseExport> {
+        export_database(&self.config, self.db.clone(), &self.jobs).await
+    }
+
+    pub async fn import_database(
+        &self,
+        export: DatabaseExport,
+        mode: ConflictMode,
+        dry_run: bool,
+    ) -> Result<ExportImportReport> {
+        import_database(self.db.clone(), export, mode, dry_run).await
+    }
+
+    pub async fn run_scheduled_backups(&self) {
+        run_scheduled_backups(
+            &self.config,
+            self.db.clone(),
+            self.docker.clone(),
+            &self.jobs,
+        )
+        .await
     }
 
     pub async fn create_bee_container(&self, bee: &BeeInfo) -> Result<()> {
@@ -86,6 +390,10 @@ impl BeeService {
         start_bee_container(self.docker.clone(), name).await
     }
 
+    pub async fn get_managed_bee_names(&self, bees: &[BeeData]) -> Result<Vec<String>> {
+        get_managed_bee_names(self.docker.clone(), bees).await
+    }
+
     pub async fn start_bee_containers(&self, names: Vec<String>) -> Result<()> {
         start_bee_containers(self.docker.clone(), names).await
     }
@@ -113,4 +421,79 @@ impl BeeService {
     pub async fn get_bee_container_logs(&self, name: &str) -> Result<Vec<String>> {
         get_bee_container_logs(self.docker.clone(), name).await
     }
+
+    pub async fn get_bee_stats(&self, name: &str) -> Result<ContainerStats> {
+        get_bee_stats(self.docker.clone(), name).await
+    }
+
+    pub async fn get_bees_stats(&self, names: &[String]) -> Vec<ContainerStats> {
+        get_bees_stats(self.docker.clone(), names).await
+    }
+
+    pub fn stream_bees_stats(
+        &self,
+        names: Vec<String>,
+        interval: Duration,
+    ) -> BoxStream<'static, Vec<ContainerStats>> {
+        stream_bees_stats(self.docker.clone(), names, interval)
+    }
+
+    pub fn is_auth_enabled(&self) -> bool {
+        self.config.auth.enabled
+    }
+
+    pub fn is_metrics_public(&self) -> bool {
+        self.config.metrics.public
+    }
+
+    pub async fn check_auth_tokens(&self) -> Result<()> {
+        check_auth_tokens(&self.config, self.db.clone()).await
+    }
+
+    pub async fn authenticate(&self, token: &str) -> Result<Option<Principal>> {
+        authenticate(&self.config, self.db.clone(), token).await
+    }
+
+    pub async fn create_token(&self, request: &CreateTokenRequest) -> Result<NewApiToken> {
+        create_token(self.db.clone(), request).await
+    }
+
+    pub async fn get_token(&self, token_id: &str) -> Result<Option<ApiTokenInfo>> {
+        get_token(self.db.clone(), token_id).await
+    }
+
+    pub async fn get_tokens(&self) -> Result<Vec<ApiTokenInfo>> {
+        get_tokens(self.db.clone()).await
+    }
+
+    pub async fn revoke_token(&self, token_id: &str) -> Result<()> {
+        revoke_token(self.db.clone(), token_id).await
+    }
+
+    pub async fn record_audit_entry(&self, entry: AuditEntry) -> Result<()> {
+        record_audit_entry(&self.config, self.db.clone(), entry).await
+    }
+
+    pub async fn get_audit_entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        get_audit_entries(self.db.clone(), query).await
+    }
+
+    pub async fn get_bee_events(&self, bee_id: u8, query: &BeeEventQuery) -> Result<Vec<BeeEvent>> {
+        get_bee_events(self.db.clone(), bee_id, query).await
+    }
+
+    pub async fn watch_bee_events(&self) {
+        watch_bee_events(self.db.clone(), self.docker.clone()).await
+    }
+
+    pub async fn get_metrics(&self, principal: &Principal) -> Result<String> {
+        get_metrics(
+            &self.config,
+            self.db.clone(),
+            self.docker.clone(),
+            &self.metrics,
+            principal,
+        )
+        .await
+    }
 }
@@ -0,0 +1,422 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tokio::fs;
+
+use crate::{
+    core::{
+        database::BeeDatabase,
+        docker::BeeDocker,
+        jobs::Jobs,
+        reservations::{BeeIdReservations, MoveReservation},
+    },
+    models::{
+        bee::BeeData,
+        config::Config,
+        http_error::ApiError,
+        job::{Job, JobKind, JobStatus},
+    },
+    utils::time::now_secs,
+};
+
+use super::{
+    bee_fn::{bee_data_to_info, stop_bee_for_copy},
+    storage_fn::{
+        check_inside_storage_roots, check_node_dir, copy_dir, get_content_size, get_placed_bees,
+        get_volume_free_bytes, get_volumes, is_same_dir, verify_copy,
+    },
+};
+
+/// Resolves the new data dir of a node moved to the given volume, `bees` including the
+/// nodes placed or moving there but not saved yet.
+pub fn get_move_destination(
+    config: &Config,
+    bees: &[BeeData],
+    bee_data: &BeeData,
+    volume_path: &Path,
+) -> Result<PathBuf> {
+    let volume = get_volumes(config)?
+        .into_iter()
+        .find(|volume| volume.path == volume_path)
+        .ok_or_else(|| {
+            ApiError::Validation(format!(
+                "'{}' is not a configured storage volume",
+                volume_path.display()
+            ))
+        })?;
+
+    if bee_data.data_dir.parent() == Some(&volume.path) {
+        return Err(ApiError::Validation(format!(
+            "{} is already on '{}'",
+            bee_data.name(),
+            volume.path.display()
+        ))
+        .into());
+    }
+    let node_dirs = bees
+        .iter()
+        .filter(|bee| {
+            bee.data_dir
+                .parent()
+                .is_some_and(|parent| is_same_dir(parent, &volume.path))
+        })
+        .map(|bee| bee.data_dir.as_path())
+        .collect::<Vec<_>>();
+    if node_dirs.len() >= volume.capacity as usize {
+        return Err(
+            ApiError::Capacity(format!("Volume '{}' is full", volume.path.display())).into(),
+        );
+    }
+    if !check_node_dir(config, &bee_data.data_dir)? {
+        return Err(ApiError::Validation(format!(
+            "Directory '{}' doesn't exist",
+            bee_data.data_dir.display()
+        ))
+        .into());
+    }
+    if volume.max_size_gb.is_some()
+        && get_content_size(&bee_data.data_dir)? > get_volume_free_bytes(&volume, &node_dirs)?
+    {
+        return Err(ApiError::Capacity(format!(
+            "Volume '{}' lacks room for {}",
+            volume.path.display(),
+            bee_data.name()
+        ))
+        .into());
+    }
+
+    let destination = volume.path.join(bee_data.name());
+    if destination.exists() {
+        return Err(ApiError::Conflict(format!(
+            "Directory '{}' already exists",
+            destination.display()
+        ))
+        .into());
+    }
+    Ok(destination)
+}
+
+/// Resolves the destination under the allocation lock and keeps it counted until
+/// the returned reservation is dropped, so creations and other moves can't take it.
+pub async fn resolve_move_destination(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    reservations: &BeeIdReservations,
+    bee_data: &BeeData,
+    volume_path: &Path,
+) -> Result<(PathBuf, MoveReservation)> {
+    let _allocation = reservations.lock().await;
+    let bees = get_placed_bees(db, reservations).await?;
+    let destination = {
+        let config = config.to_owned();
+        let bee_data = bee_data.to_owned();
+        let volume_path = volume_path.to_owned();
+        tokio::task::spawn_blocking(move || {
+            get_move_destination(&config, &bees, &bee_data, &volume_path)
+        })
+        .await??
+    };
+    let reservation = reservations.reserve_move(bee_data.id, &destination);
+    Ok((destination, reservation))
+}
+
+/// Runs the move in the background, returns `None` when the node already has a running job.
+/// The destination stays reserved until the move is over.
+pub fn start_bee_move(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+    jobs: &Jobs,
+    bee_data: &BeeData,
+    destination: &Path,
+    reservation: MoveReservation,
+) -> Option<Job> {
+    let job = jobs.start(JobKind::Move, bee_data.id)?;
+
+    let config = config.to_owned();
+    let jobs = jobs.to_owned();
+    let bee_data = bee_data.to_owned();
+    let destination = destination.to_owned();
+    let job_id = job.id.to_owned();
+    tokio::spawn(async move {
+        let _reservation = reservation;
+        set_step(&jobs, &job_id, "stopping");
+        let (status, error) = match stop_bee_for_copy(docker.clone(), &bee_data.name()).await {
+            Err(err) => {
+                tracing::error!("Failed to move {}: {}", bee_data.name(), err);
+                (JobStatus::Failed, Some(err.to_string()))
+            }
+            Ok(was_running) => match move_bee(
+                &config,
+                db.clone(),
+                docker.clone(),
+                &jobs,
+                &job_id,
+                &bee_data,
+                &destination,
+                was_running,
+            )
+            .await
+            {
+                Ok(()) => (JobStatus::Completed, None),
+                Err(err) => {
+                    tracing::error!("Failed to move {}: {}", bee_data.name(), err);
+                    match rollback_bee_move(
+                        &config,
+                        db,
+                        docker,
+                        &bee_data,
+                        &destination,
+                        was_running,
+                    )
+                    .await
+                    {
+                        Ok(()) => (JobStatus::RolledBack, Some(err.to_string())),
+                        Err(rollback_err) => (
+                            JobStatus::Failed,
+                            Some(format!("{}, rollback failed: {}", err, rollback_err)),
+                        ),
+                    }
+                }
+            },
+        };
+        jobs.update(&job_id, |job| {
+            job.status = status;
+            job.error = error.or(job.error.take());
+            job.finished_at = Some(now_secs());
+        });
+    });
+
+    Some(job)
+}
+
+fn set_step(jobs: &Jobs, job_id: &str, step: &str) {
+    jobs.update(job_id, |job| job.step = step.to_owned());
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn move_bee(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+    jobs: &Jobs,
+    job_id: &str,
+    bee_data: &BeeData,
+    destination: &Path,
+    was_running: bool,
+) -> Result<()> {
+    let name = bee_data.name();
+    let source = bee_data.data_dir.to_owned();
+
+    set_step(jobs, job_id, "copying");
+    {
+        let jobs = jobs.to_owned();
+        let job_id = job_id.to_owned();
+        let source = source.to_owned();
+        let destination = destination.to_owned();
+        tokio::task::spawn_blocking(move || {
+            let total_bytes = get_content_size(&source)?;
+            jobs.update(&job_id, |job| job.total_bytes = total_bytes);
+            copy_dir(&source, &destination, &mut |copied| {
+                jobs.update(&job_id, |job| job.processed_bytes = copied)
+            })?;
+
+            jobs.update(&job_id, |job| job.step = "verifying".to_owned());
+            verify_copy(&source, &destination)
+        })
+        .await??;
+    }
+
+    set_step(jobs, job_id, "recreating");
+    let moved = BeeData {
+        data_dir: destination.to_owned(),
+        ..bee_data.to_owned()
+    };
+    db.update_bee(moved.to_owned()).await?;
+    docker
+        .recreate_container(&bee_data_to_info(config, &moved)?, config)
+        .await?;
+    if was_running {
+        docker.start_bee_container(&name).await?;
+    }
+
+    set_step(jobs, job_id, "cleaning");
+    let cleanup = {
+        let config = config.to_owned();
+        tokio::task::spawn_blocking(move || {
+            if check_node_dir(&config, &source)? {
+                std::fs::remove_dir_all(&source)?;
+            }
+            Ok::<_, anyhow::Error>(())
+        })
+        .await?
+    };
+    // The node already runs from its new location, a leftover copy is only reported.
+    if let Err(err) = cleanup {
+        jobs.update(job_id, |job| {
+            job.error = Some(format!(
+                "Failed to remove '{}': {}",
+                bee_data.data_dir.display(),
+                err
+            ))
+        });
+    }
+
+    set_step(jobs, job_id, "done");
+    Ok(())
+}
+
+/// Puts the node back on its original data dir, in the state it was found.
+async fn rollback_bee_move(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+    bee_data: &BeeData,
+    destination: &Path,
+    was_running: bool,
+) -> Result<()> {
+    remove_partial_copy(config, destination).await?;
+    db.update_bee(bee_data.to_owned()).await?;
+    docker
+        .recreate_container(&bee_data_to_info(config, bee_data)?, config)
+        .await?;
+    if was_running {
+        docker.start_bee_container(&bee_data.name()).await?;
+    }
+    Ok(())
+}
+
+/// The destination didn't exist before the move, so whatever the copy left
+/// there goes, even when it doesn't look like a bee data dir yet.
+async fn remove_partial_copy(config: &Config, destination: &Path) -> Result<()> {
+    if !destination.exists() {
+        return Ok(());
+    }
+    check_inside_storage_roots(config, destination)?;
+    fs::remove_dir_all(destination).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::database::MockDbService, models::config::Volume};
+
+    fn setup() -> (tempfile::TempDir, Config, BeeData) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.volumes = ["disk_a", "disk_b", "disk_c"]
+            .iter()
+            .map(|disk| Volume {
+                path: temp_dir.path().join(disk),
+                capacity: 1,
+                max_size_gb: None,
+            })
+            .collect();
+        for volume in &config.storage.volumes {
+            std::fs::create_dir_all(&volume.path).unwrap();
+        }
+        let bee_data = BeeData {
+            id: 3,
+            data_dir: temp_dir.path().join("disk_a").join("node_03"),
+            ..Default::default()
+        };
+        std::fs::create_dir_all(bee_data.data_dir.join("statestore")).unwrap();
+        (temp_dir, config, bee_data)
+    }
+
+    #[test]
+    fn should_resolve_move_destination_on_volume() {
+        let (temp_dir, config, bee_data) = setup();
+        let volume = temp_dir.path().join("disk_b");
+
+        let destination = get_move_destination(&config, &[bee_data.to_owned()], &bee_data, &volume);
+
+        assert_eq!(destination.unwrap(), volume.join("node_03"));
+    }
+
+    #[test]
+    fn should_refuse_invalid_move_destinations() {
+        let (temp_dir, config, bee_data) = setup();
+        let other = BeeData {
+            id: 4,
+            data_dir: temp_dir.path().join("disk_b").join("node_04"),
+            ..Default::default()
+        };
+        let bees = [bee_data.to_owned(), other];
+        let refuse = |volume: &str| {
+            get_move_destination(&config, &bees, &bee_data, &temp_dir.path().join(volume))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(refuse("disk_z").ends_with("is not a configured storage volume"));
+        assert!(refuse("disk_a").contains("is already on"));
+        assert!(refuse("disk_b").ends_with("is full"));
+
+        std::fs::create_dir_all(temp_dir.path().join("disk_c").join("node_03")).unwrap();
+        assert!(refuse("disk_c").ends_with("already exists"));
+    }
+
+    #[test]
+    fn should_refuse_move_past_volume_size_limit() {
+        let (temp_dir, mut config, bee_data) = setup();
+        std::fs::write(bee_data.data_dir.join("statestore").join("data"), "chunk").unwrap();
+        config.storage.volumes[1].max_size_gb = Some(0);
+        let bees = [bee_data.to_owned()];
+
+        let refused =
+            get_move_destination(&config, &bees, &bee_data, &temp_dir.path().join("disk_b"));
+
+        assert!(refused.unwrap_err().to_string().contains("lacks room for"));
+        assert!(
+            get_move_destination(&config, &bees, &bee_data, &temp_dir.path().join("disk_c"))
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_count_pending_moves_until_dropped() {
+        let (temp_dir, config, bee_data) = setup();
+        let other = BeeData {
+            id: 4,
+            data_dir: temp_dir.path().join("disk_c").join("node_04"),
+            ..Default::default()
+        };
+        std::fs::create_dir_all(&other.data_dir).unwrap();
+        let db = Box::new(MockDbService::default());
+        let reservations = BeeIdReservations::default();
+        let volume = temp_dir.path().join("disk_b");
+
+        let (destination, moving) =
+            resolve_move_destination(&config, db.clone(), &reservations, &bee_data, &volume)
+                .await
+                .unwrap();
+        let refused =
+            resolve_move_destination(&config, db.clone(), &reservations, &other, &volume).await;
+
+        assert_eq!(destination, volume.join("node_03"));
+        assert!(refused.unwrap_err().to_string().ends_with("is full"));
+        drop(moving);
+        assert!(
+            resolve_move_destination(&config, db, &reservations, &other, &volume)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_remove_partial_copy_without_bee_data() {
+        let (temp_dir, config, _) = setup();
+        let destination = temp_dir.path().join("disk_b").join("node_03");
+        std::fs::create_dir_all(&destination).unwrap();
+        std::fs::write(destination.join("partial"), "half").unwrap();
+
+        remove_partial_copy(&config, &destination).await.unwrap();
+
+        assert!(!destination.exists());
+        let outside = tempfile::tempdir().unwrap();
+        assert!(remove_partial_copy(&config, outside.path()).await.is_err());
+        assert!(outside.path().exists());
+    }
+}
@@ -0,0 +1,357 @@
+use anyhow::Result;
+
+use crate::{
+    core::{
+        database::BeeDatabase,
+        docker::{
+            BeeDocker, LABEL_INSTANCE, LABEL_MODE, LABEL_NEIGHBORHOOD, LABEL_NODE_ID,
+            LABEL_TAG_PREFIX,
+        },
+    },
+    models::{
+        bee::BeeData,
+        config::Config,
+        import::{BeeContainer, RebuildReport},
+    },
+};
+
+use super::{
+    bee_fn::{bee_data_to_info, stop_bee_for_copy},
+    import_fn::{conflict, infer_bee_data},
+};
+
+/// Reads a node from its container, the ruche labels taking precedence over the env.
+fn rebuild_bee_data(config: &Config, container: &BeeContainer) -> Result<BeeData, String> {
+    let mut bee_data = infer_bee_data(config, container)?;
+
+    if let Some(id) = container.labels.get(LABEL_NODE_ID) {
+        if id != &bee_data.id.to_string() {
+            return Err(format!(
+                "labeled as node {} but named {}",
+                id, container.name
+            ));
+        }
+    }
+    if let Some(neighborhood) = container.labels.get(LABEL_NEIGHBORHOOD) {
+        bee_data.neighborhood = neighborhood.to_owned();
+    }
+    if let Some(mode) = container.labels.get(LABEL_MODE) {
+        if mode != bee_data.mode() {
+            return Err(format!(
+                "labeled as {} node but its env runs a {} node",
+                mode,
+                bee_data.mode()
+            ));
+        }
+    }
+    bee_data.tags = container
+        .labels
+        .iter()
+        .filter_map(|(label, value)| {
+            let key = label.strip_prefix(LABEL_TAG_PREFIX)?;
+            Some((key.to_owned(), value.to_owned()))
+        })
+        .collect();
+    Ok(bee_data)
+}
+
+/// Fields of the registered node that differ from its container.
+fn get_differences(registered: &BeeData, rebuilt: &BeeData) -> Vec<&'static str> {
+    [
+        (
+            "neighborhood",
+            registered.neighborhood == rebuilt.neighborhood,
+        ),
+        ("full_node", registered.full_node == rebuilt.full_node),
+        ("swap_enable", registered.swap_enable == rebuilt.swap_enable),
+        (
+            "reserve_doubling",
+            registered.reserve_doubling == rebuilt.reserve_doubling,
+        ),
+        ("data_dir", registered.data_dir == rebuilt.data_dir),
+    ]
+    .into_iter()
+    .filter(|(_, is_equal)| !is_equal)
+    .map(|(field, _)| field)
+    .collect()
+}
+
+/// Compares the registry with the containers of this instance. Only missing
+/// nodes are restored, differing entries are reported and left untouched.
+pub fn plan_rebuild(
+    config: &Config,
+    registered: &[BeeData],
+    containers: &[BeeContainer],
+) -> (Vec<BeeData>, RebuildReport) {
+    let mut report = RebuildReport::default();
+    let mut bees = Vec::new();
+    let mut seen = Vec::new();
+
+    for container in containers {
+        match container.labels.get(LABEL_INSTANCE) {
+            Some(instance_id) if instance_id != &config.instance_id => {
+                report.other_instances.push(container.name.to_owned());
+                continue;
+            }
+            Some(_) => {}
+            None => report.unlabeled.push(container.name.to_owned()),
+        }
+
+        let bee_data = match rebuild_bee_data(config, container) {
+            Ok(bee_data) => bee_data,
+            Err(reason) => {
+                report.conflicts.push(conflict(&container.name, reason));
+                continue;
+            }
+        };
+        seen.push(bee_data.id);
+
+        if let Some(existing) = registered.iter().find(|bee| bee.id == bee_data.id) {
+            let differences = get_differences(existing, &bee_data);
+            if differences.is_empty() {
+                report.consistent.push(bee_data.id);
+            } else {
+                report.mismatched.push(conflict(
+                    &container.name,
+                    format!("differs in {}", differences.join(", ")),
+                ));
+            }
+            continue;
+        }
+        if let Some(existing) = registered
+            .iter()
+            .find(|bee| bee.data_dir == bee_data.data_dir)
+        {
+            report.conflicts.push(conflict(
+                &container.name,
+                format!(
+                    "'{}' is the data dir of {}",
+                    bee_data.data_dir.display(),
+                    existing.name()
+                ),
+            ));
+            continue;
+        }
+
+        report.restored.push(bee_data.id);
+        bees.push(bee_data);
+    }
+
+    report.missing_containers = registered
+        .iter()
+        .map(|bee| bee.id)
+        .filter(|id| !seen.contains(id))
+        .collect();
+    report.restored.sort();
+    report.consistent.sort();
+    report.missing_containers.sort();
+    (bees, report)
+}
+
+pub async fn rebuild_bees(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+    dry_run: bool,
+) -> Result<RebuildReport> {
+    let registered = db.get_bees().await?;
+    let containers = docker.get_bee_containers().await?;
+
+    let (bees, report) = plan_rebuild(config, &registered, &containers);
+    if !dry_run {
+        for bee_data in bees {
+            db.add_bee(bee_data).await?;
+        }
+    }
+
+    Ok(RebuildReport { dry_run, ..report })
+}
+
+/// Registered nodes whose container predates the ruche labels, matched on the
+/// node name and on the mounted data dir.
+pub fn plan_adoption(
+    config: &Config,
+    registered: &[BeeData],
+    containers: &[BeeContainer],
+) -> Vec<BeeData> {
+    containers
+        .iter()
+        .filter(|container| !container.labels.contains_key(LABEL_INSTANCE))
+        .filter_map(|container| {
+            let bee_data = infer_bee_data(config, container).ok()?;
+            registered
+                .iter()
+                .find(|bee| bee.id == bee_data.id && bee.data_dir == bee_data.data_dir)
+                .cloned()
+        })
+        .collect()
+}
+
+/// Brings back under management the containers left behind by a name prefix
+/// change, renamed, and those created before labels existed, recreated with them.
+/// A node is only started again if it was running. Returns the adopted node names.
+pub async fn adopt_bee_containers(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    docker: Box<dyn BeeDocker>,
+) -> Result<Vec<String>> {
+    let mut adopted = docker.rename_prefixed_bee_containers().await?;
+
+    let registered = db.get_bees().await?;
+    let containers = docker.get_bee_containers().await?;
+    for bee_data in plan_adoption(config, &registered, &containers) {
+        let name = bee_data.name();
+        let was_running = stop_bee_for_copy(docker.clone(), &name).await?;
+        docker
+            .recreate_container(&bee_data_to_info(config, &bee_data)?, config)
+            .await?;
+        if was_running {
+            docker.start_bee_container(&name).await?;
+        }
+        adopted.push(name);
+    }
+    Ok(adopted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashMap, path::PathBuf};
+
+    fn config() -> Config {
+        let mut config = Config {
+            instance_id: "host-a".to_owned(),
+            ..Default::default()
+        };
+        config.network.api_port = "17xx".to_owned();
+        config.network.p2p_port = "18xx".to_owned();
+        config
+    }
+
+    fn container(id: u8, instance_id: Option<&str>) -> BeeContainer {
+        let mut labels = HashMap::from([
+            (LABEL_NODE_ID.to_owned(), id.to_string()),
+            (LABEL_NEIGHBORHOOD.to_owned(), "1010".to_owned()),
+            (LABEL_MODE.to_owned(), "full".to_owned()),
+            (format!("{}owner", LABEL_TAG_PREFIX), "teamA".to_owned()),
+        ]);
+        match instance_id {
+            Some(instance_id) => {
+                labels.insert(LABEL_INSTANCE.to_owned(), instance_id.to_owned());
+            }
+            None => labels.clear(),
+        }
+
+        BeeContainer {
+            name: format!("node_{:02}", id),
+            labels,
+            env: HashMap::from([
+                ("BEE_FULL_NODE".to_owned(), "true".to_owned()),
+                ("BEE_SWAP_ENABLE".to_owned(), "true".to_owned()),
+                ("BEE_TARGET_NEIGHBORHOOD".to_owned(), "1111".to_owned()),
+            ]),
+            mounts: HashMap::from([("/home/bee/.bee".to_owned(), data_dir(id))]),
+            ..Default::default()
+        }
+    }
+
+    fn data_dir(id: u8) -> PathBuf {
+        PathBuf::from(format!("/media/swarm_data_01/node_{:02}", id))
+    }
+
+    #[test]
+    fn should_prefer_labels_over_env() {
+        let bee_data = rebuild_bee_data(&config(), &container(2, Some("host-a"))).unwrap();
+
+        assert_eq!(bee_data.id, 2);
+        assert_eq!(bee_data.neighborhood, "1010");
+        assert!(bee_data.full_node);
+        assert_eq!(bee_data.data_dir, data_dir(2));
+        assert_eq!(bee_data.tags["owner"], "teamA");
+    }
+
+    #[test]
+    fn should_refuse_mode_not_matching_env() {
+        let mut container = container(2, Some("host-a"));
+        container
+            .env
+            .insert("BEE_FULL_NODE".to_owned(), "false".to_owned());
+
+        let reason = rebuild_bee_data(&config(), &container).unwrap_err();
+
+        assert_eq!(reason, "labeled as full node but its env runs a light node");
+    }
+
+    #[test]
+    fn should_report_registry_consistency() {
+        let registered = vec![
+            BeeData {
+                id: 1,
+                neighborhood: "1010".to_owned(),
+                full_node: true,
+                swap_enable: true,
+                data_dir: data_dir(1),
+                ..Default::default()
+            },
+            BeeData {
+                id: 2,
+                neighborhood: "0000".to_owned(),
+                full_node: true,
+                swap_enable: true,
+                data_dir: data_dir(2),
+                ..Default::default()
+            },
+            BeeData {
+                id: 9,
+                data_dir: data_dir(9),
+                ..Default::default()
+            },
+        ];
+        let containers = vec![
+            container(1, Some("host-a")),
+            container(2, Some("host-a")),
+            container(3, Some("host-a")),
+            container(4, Some("host-b")),
+            container(5, None),
+        ];
+
+        let (bees, report) = plan_rebuild(&config(), &registered, &containers);
+
+        assert_eq!(report.restored, vec![3, 5]);
+        assert_eq!(bees.len(), 2);
+        assert_eq!(bees[1].neighborhood, "1111");
+        assert_eq!(report.consistent, vec![1]);
+        assert_eq!(report.mismatched[0].reason, "differs in neighborhood");
+        assert_eq!(report.missing_containers, vec![9]);
+        assert_eq!(report.other_instances, vec!["node_04"]);
+        assert_eq!(report.unlabeled, vec!["node_05"]);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn should_adopt_unlabeled_containers_of_registered_nodes() {
+        let bee = |id: u8, data_dir: PathBuf| BeeData {
+            id,
+            data_dir,
+            ..Default::default()
+        };
+        let registered = vec![
+            bee(1, data_dir(1)),
+            bee(2, data_dir(2)),
+            bee(3, PathBuf::from("/elsewhere/node_03")),
+        ];
+        let containers = vec![
+            container(1, None),
+            container(2, Some("host-a")),
+            container(3, None),
+            container(4, None),
+        ];
+
+        let adopted = plan_adoption(&config(), &registered, &containers);
+
+        assert_eq!(
+            adopted.iter().map(|bee| bee.id).collect::<Vec<_>>(),
+            vec![1]
+        );
+    }
+}
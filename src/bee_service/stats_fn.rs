@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use futures_util::{
+    future::join_all,
+    stream::{self, BoxStream},
+    StreamExt,
+};
+
+use crate::{core::docker::BeeDocker, models::stats::ContainerStats};
+
+pub async fn get_bee_stats(docker: Box<dyn BeeDocker>, name: &str) -> Result<ContainerStats> {
+    docker.get_bee_container_stats(name).await
+}
+
+/// Containers that can't be inspected, e.g. removed by hand, are left out.
+pub async fn get_bees_stats(docker: Box<dyn BeeDocker>, names: &[String]) -> Vec<ContainerStats> {
+    let stats = names.iter().map(|name| {
+        let docker = docker.clone();
+        async move {
+            docker
+                .get_bee_container_stats(name)
+                .await
+                .inspect_err(|err| tracing::debug!("Failed to get stats of {}: {}", name, err))
+                .ok()
+        }
+    });
+    join_all(stats).await.into_iter().flatten().collect()
+}
+
+/// Polls the stats of the given containers, the first sample being sent right away.
+pub fn stream_bees_stats(
+    docker: Box<dyn BeeDocker>,
+    names: Vec<String>,
+    interval: Duration,
+) -> BoxStream<'static, Vec<ContainerStats>> {
+    stream::unfold(true, move |first| {
+        let docker = docker.clone();
+        let names = names.clone();
+        async move {
+            if !first {
+                tokio::time::sleep(interval).await;
+            }
+            Some((get_bees_stats(docker, &names).await, false))
+        }
+    })
+    .boxed()
+}
@@ -1,15 +1,32 @@
 use std::{
-    os::unix::fs::PermissionsExt,
+    cmp::Reverse,
+    collections::BTreeMap,
+    io::{Read, Write},
+    os::unix::fs::{MetadataExt, PermissionsExt},
     path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, Result};
+use nix::sys::statvfs::statvfs;
 use regex::Regex;
+use sha2::{Digest, Sha256};
 use tokio::fs;
 
-use crate::{models::config::Config, utils::regex::VOLUME_NAME_REGEX};
+use crate::{
+    core::{
+        database::BeeDatabase,
+        reservations::{BeeIdReservation, BeeIdReservations},
+    },
+    models::{
+        bee::BeeData,
+        config::{Config, Placement, Volume},
+        disk::{NodeDiskUsage, ParentDirUsage},
+        http_error::ApiError,
+    },
+    utils::{regex::VOLUME_NAME_REGEX, time::now_secs},
+};
 
-use super::bee_fn::{format_id, get_node_name};
+use super::bee_fn::{format_id, get_node_name, BEE_IDS};
 
 pub fn get_dir_id(config: &Config, bee_id: u8) -> u8 {
     ((bee_id - 1) / config.storage.parent_dir_capacity) + 1
@@ -26,6 +43,33 @@ pub fn get_parent_dir_name(config: &Config, bee_id: u8) -> Result<String> {
     Ok(dir_name_format.replace("xx", &format_id(get_dir_id(config, bee_id))))
 }
 
+/// Volumes from the config, or one per parent dir of the `root_path/parent_dir_format` layout.
+pub fn get_volumes(config: &Config) -> Result<Vec<Volume>> {
+    if !config.storage.volumes.is_empty() {
+        return Ok(config.storage.volumes.to_owned());
+    }
+
+    let capacity = config.storage.parent_dir_capacity;
+    if capacity == 0 {
+        return Err(anyhow!("Invalid parent dir capacity '{}'", capacity));
+    }
+
+    BEE_IDS
+        .step_by(capacity as usize)
+        .map(|first_id| {
+            Ok(Volume {
+                path: config
+                    .storage
+                    .root_path
+                    .join(get_parent_dir_name(config, first_id)?),
+                capacity,
+                max_size_gb: None,
+            })
+        })
+        .collect()
+}
+
+/// Path of a node in the `root_path/parent_dir_format` layout, its id picks the parent dir.
 pub fn get_node_path(config: &Config, bee_id: u8) -> Result<PathBuf> {
     let root_path = &config.storage.root_path;
     let parent_name = get_parent_dir_name(config, bee_id)?;
@@ -33,8 +77,149 @@ pub fn get_node_path(config: &Config, bee_id: u8) -> Result<PathBuf> {
     Ok(parent_path.join(get_node_name(bee_id)))
 }
 
-pub async fn create_node_dir(config: &Config, bee_id: u8) -> Result<PathBuf> {
-    let node_path = get_node_path(config, bee_id)?;
+/// Room left for node data in the volume, bounded by its size limit if any, which
+/// counts the data of the nodes in `node_dirs` only, whatever else shares the disk.
+/// The volume may not exist yet, its nearest existing ancestor is used instead.
+pub fn get_volume_free_bytes(volume: &Volume, node_dirs: &[&Path]) -> Result<u64> {
+    let existing = volume
+        .path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .ok_or_else(|| anyhow!("No existing ancestor for '{}'", volume.path.display()))?;
+    let stat = statvfs(existing)?;
+    let fragment_size = stat.fragment_size() as u64;
+    let available_bytes = stat.blocks_available() as u64 * fragment_size;
+    match volume.max_size_gb {
+        Some(max_size_gb) => {
+            let max_bytes = max_size_gb.saturating_mul(1024 * 1024 * 1024);
+            let mut used_bytes = 0;
+            for node_dir in node_dirs.iter().filter(|node_dir| node_dir.exists()) {
+                used_bytes += get_content_size(node_dir)?;
+            }
+            Ok(available_bytes.min(max_bytes.saturating_sub(used_bytes)))
+        }
+        None => Ok(available_bytes),
+    }
+}
+
+/// Compares resolved paths, so symlinks and `..` don't hide a node in a volume.
+pub fn is_same_dir(path: &Path, other: &Path) -> bool {
+    match (path.canonicalize(), other.canonicalize()) {
+        (Ok(path), Ok(other)) => path == other,
+        _ => path == other,
+    }
+}
+
+/// Picks among `(volume index, free bytes)` candidates, all having room for a node.
+pub fn pick_volume(
+    placement: Placement,
+    candidates: &[(usize, u64)],
+    volume_count: usize,
+    last_used: Option<usize>,
+) -> Option<usize> {
+    match placement {
+        Placement::FillFirst => candidates.first().map(|(index, _)| *index),
+        Placement::MostFree => candidates
+            .iter()
+            .max_by_key(|(index, free_bytes)| (*free_bytes, Reverse(*index)))
+            .map(|(index, _)| *index),
+        Placement::RoundRobin => {
+            let start = last_used.map_or(0, |last_used| last_used + 1);
+            candidates
+                .iter()
+                .min_by_key(|(index, _)| {
+                    (index + volume_count - start % volume_count) % volume_count
+                })
+                .map(|(index, _)| *index)
+        }
+    }
+}
+
+/// Chooses the volume of a new node according to the placement policy, `bees`
+/// including the nodes placed but not saved yet.
+pub fn select_volume(config: &Config, bees: &[BeeData]) -> Result<Volume> {
+    let volumes = get_volumes(config)?;
+    let is_in_volume = |bee: &BeeData, volume: &Volume| {
+        bee.data_dir
+            .parent()
+            .is_some_and(|parent| is_same_dir(parent, &volume.path))
+    };
+
+    let mut candidates = Vec::new();
+    for (index, volume) in volumes.iter().enumerate() {
+        let node_dirs = bees
+            .iter()
+            .filter(|bee| is_in_volume(bee, volume))
+            .map(|bee| bee.data_dir.as_path())
+            .collect::<Vec<_>>();
+        if node_dirs.len() >= volume.capacity as usize {
+            continue;
+        }
+        let free_bytes = get_volume_free_bytes(volume, &node_dirs)?;
+        if free_bytes > 0 {
+            candidates.push((index, free_bytes));
+        }
+    }
+
+    let last_used = bees
+        .iter()
+        .max_by_key(|bee| bee.created_at)
+        .and_then(|last| volumes.iter().position(|volume| is_in_volume(last, volume)));
+
+    pick_volume(
+        config.storage.placement,
+        &candidates,
+        volumes.len(),
+        last_used,
+    )
+    .map(|index| volumes[index].to_owned())
+    .ok_or_else(|| {
+        ApiError::Capacity("No storage volume has room for a new node".to_owned()).into()
+    })
+}
+
+/// Saved nodes, plus the data dirs placed for nodes not saved yet or being moved.
+/// Read under the allocation lock, so they don't change until a placement is recorded.
+pub async fn get_placed_bees(
+    db: Box<dyn BeeDatabase>,
+    reservations: &BeeIdReservations,
+) -> Result<Vec<BeeData>> {
+    let mut bees = db.get_bees().await?;
+    let placed = reservations
+        .get_placed()
+        .into_iter()
+        .filter(|(_, data_dir)| !bees.iter().any(|bee| &bee.data_dir == data_dir))
+        .map(|(id, data_dir)| BeeData {
+            id,
+            data_dir,
+            created_at: now_secs(),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    bees.extend(placed);
+    Ok(bees)
+}
+
+/// Creates the data dir of a reserved id. The allocation lock is held until the dir
+/// is recorded on the reservation, so concurrent creations count each other.
+pub async fn create_node_dir(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    reservations: &BeeIdReservations,
+    reservation: &BeeIdReservation,
+) -> Result<PathBuf> {
+    let _allocation = reservations.lock().await;
+    let bee_id = reservation.id;
+    let node_path = if config.storage.volumes.is_empty()
+        && config.storage.placement == Placement::FillFirst
+    {
+        get_node_path(config, bee_id)?
+    } else {
+        let bees = get_placed_bees(db, reservations).await?;
+        let config = config.to_owned();
+        let volume = tokio::task::spawn_blocking(move || select_volume(&config, &bees)).await??;
+        volume.path.join(get_node_name(bee_id))
+    };
 
     if node_path.exists() {
         return Err(anyhow!(
@@ -49,13 +234,297 @@ pub async fn create_node_dir(config: &Config, bee_id: u8) -> Result<PathBuf> {
     perms.set_mode(0o755);
     fs::set_permissions(&node_path, perms).await?;
 
+    reservation.place(&node_path);
     Ok(node_path)
 }
 
+/// Directories bee creates in its data dir, one of them marks a node data dir.
+pub const BEE_DATA_DIRS: [&str; 3] = ["keys", "localstore", "statestore"];
+
+/// Every path nodes may live under, configured volumes and the parent dir root.
+pub fn get_storage_roots(config: &Config) -> Vec<PathBuf> {
+    let mut roots = config
+        .storage
+        .volumes
+        .iter()
+        .map(|volume| volume.path.to_owned())
+        .collect::<Vec<_>>();
+    if !config.storage.root_path.as_os_str().is_empty() {
+        roots.push(config.storage.root_path.to_owned());
+    }
+    roots
+}
+
+/// Whether the path lies strictly inside a storage root, symlinks of existing paths resolved.
+pub fn is_inside_storage_roots(config: &Config, path: &Path) -> bool {
+    let resolve = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_owned());
+    let path = resolve(path);
+    get_storage_roots(config).iter().any(|root| {
+        let root = resolve(root);
+        path.starts_with(&root) && path != root
+    })
+}
+
+/// Resolves an existing path, refusing one that isn't strictly inside a storage root.
+pub fn check_inside_storage_roots(config: &Config, path: &Path) -> Result<PathBuf> {
+    let path = path.canonicalize()?;
+    if !is_inside_storage_roots(config, &path) {
+        return Err(anyhow!(
+            "Refusing to remove '{}': not inside a configured storage root",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
+/// Guards against removing anything that isn't a node data dir inside the storage roots.
+/// Returns whether the directory exists; a missing one has nothing to remove.
+pub fn check_node_dir(config: &Config, data_dir: &Path) -> Result<bool> {
+    if !data_dir.exists() {
+        return Ok(false);
+    }
+
+    let data_dir = check_inside_storage_roots(config, data_dir)?;
+
+    let mut entries = std::fs::read_dir(&data_dir)?.peekable();
+    let is_empty = entries.peek().is_none();
+    let has_bee_data = BEE_DATA_DIRS.iter().any(|dir| data_dir.join(dir).is_dir());
+    if !is_empty && !has_bee_data {
+        return Err(anyhow!(
+            "Refusing to remove '{}': not a bee data dir",
+            data_dir.display()
+        ));
+    }
+
+    Ok(true)
+}
+
+/// Space actually allocated on disk, like `du`. Missing paths count as empty.
+pub fn get_dir_size(path: &Path) -> Result<u64> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err.into()),
+    };
+    let mut size = metadata.blocks() * 512;
+
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            size += get_dir_size(&entry?.path())?;
+        }
+    }
+    Ok(size)
+}
+
+const COPY_BUFFER_SIZE: usize = 8 * 1024 * 1024;
+
+/// Entries of a directory tree, relative to its root, parents listed before their children.
+fn list_entries(root: &Path) -> Result<Vec<(PathBuf, std::fs::Metadata)>> {
+    let mut entries = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(root.join(&dir))? {
+            let relative = dir.join(entry?.file_name());
+            let metadata = std::fs::symlink_metadata(root.join(&relative))?;
+            if metadata.is_dir() {
+                dirs.push(relative.to_owned());
+            }
+            entries.push((relative, metadata));
+        }
+    }
+    Ok(entries)
+}
+
+/// Total length of the regular files in a directory tree.
+pub fn get_content_size(root: &Path) -> Result<u64> {
+    Ok(list_entries(root)?
+        .iter()
+        .filter(|(_, metadata)| metadata.is_file())
+        .map(|(_, metadata)| metadata.len())
+        .sum())
+}
+
+/// Copies a directory tree with its permissions, reporting the bytes copied so far.
+pub fn copy_dir(source: &Path, destination: &Path, progress: &mut dyn FnMut(u64)) -> Result<()> {
+    let mut copied = 0;
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+
+    std::fs::create_dir_all(destination)?;
+    std::fs::set_permissions(destination, std::fs::metadata(source)?.permissions())?;
+
+    for (relative, metadata) in list_entries(source)? {
+        let from = source.join(&relative);
+        let to = destination.join(&relative);
+
+        if metadata.is_symlink() {
+            std::os::unix::fs::symlink(std::fs::read_link(&from)?, &to)?;
+            continue;
+        }
+        if metadata.is_dir() {
+            std::fs::create_dir_all(&to)?;
+        } else {
+            let mut reader = std::fs::File::open(&from)?;
+            let mut writer = std::fs::File::create(&to)?;
+            loop {
+                let read = reader.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                writer.write_all(&buffer[..read])?;
+                copied += read as u64;
+                progress(copied);
+            }
+            writer.sync_all()?;
+        }
+        std::fs::set_permissions(&to, metadata.permissions())?;
+    }
+    Ok(())
+}
+
+/// Sha256 of a file, read in the same chunks as it is copied.
+fn hash_file(path: &Path, buffer: &mut [u8]) -> Result<Vec<u8>> {
+    let mut reader = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    loop {
+        let read = reader.read(buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Checks both trees hold the same entries, files with the same content and
+/// symlinks with the same target.
+pub fn verify_copy(source: &Path, destination: &Path) -> Result<()> {
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+    let mut summarize = |root: &Path| -> Result<BTreeMap<PathBuf, (bool, Vec<u8>)>> {
+        list_entries(root)?
+            .into_iter()
+            .map(|(relative, metadata)| {
+                let path = root.join(&relative);
+                let content = if metadata.is_symlink() {
+                    std::fs::read_link(&path)?
+                        .into_os_string()
+                        .into_encoded_bytes()
+                } else if metadata.is_file() {
+                    hash_file(&path, &mut buffer)?
+                } else {
+                    Vec::new()
+                };
+                Ok((relative, (metadata.is_dir(), content)))
+            })
+            .collect()
+    };
+
+    let source_entries = summarize(source)?;
+    let destination_entries = summarize(destination)?;
+    if source_entries != destination_entries {
+        let mismatch = source_entries
+            .iter()
+            .find(|(path, summary)| destination_entries.get(*path) != Some(summary))
+            .map(|(path, _)| path.display().to_string())
+            .unwrap_or_else(|| "unexpected entries".to_owned());
+        return Err(anyhow!(
+            "Copy of '{}' doesn't match the source: {}",
+            source.display(),
+            mismatch
+        ));
+    }
+    Ok(())
+}
+
+pub async fn get_node_disk_usage(bee_data: &BeeData) -> Result<NodeDiskUsage> {
+    let bee_data = bee_data.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let data_dir = &bee_data.data_dir;
+        Ok(NodeDiskUsage {
+            id: bee_data.id,
+            name: bee_data.name(),
+            data_dir: data_dir.to_owned(),
+            total_bytes: get_dir_size(data_dir)?,
+            localstore_bytes: get_dir_size(&data_dir.join("localstore"))?,
+            statestore_bytes: get_dir_size(&data_dir.join("statestore"))?,
+            keys_bytes: get_dir_size(&data_dir.join("keys"))?,
+        })
+    })
+    .await?
+}
+
+pub fn get_parent_dir_usage(
+    config: &Config,
+    path: &Path,
+    bee_ids: Vec<u8>,
+) -> Result<ParentDirUsage> {
+    let stat = statvfs(path)?;
+    let fragment_size = stat.fragment_size() as u64;
+    let capacity_bytes = stat.blocks() as u64 * fragment_size;
+    let free_bytes = stat.blocks_free() as u64 * fragment_size;
+    let available_bytes = stat.blocks_available() as u64 * fragment_size;
+    let used_bytes = capacity_bytes.saturating_sub(free_bytes);
+
+    // Same as `df`: blocks reserved for root are not usable by the nodes.
+    let used_percent = match used_bytes + available_bytes {
+        0 => 0.0,
+        usable => used_bytes as f64 / usable as f64 * 100.0,
+    };
+
+    Ok(ParentDirUsage {
+        path: path.to_owned(),
+        bee_ids,
+        capacity_bytes: Some(capacity_bytes),
+        used_bytes: Some(used_bytes),
+        available_bytes: Some(available_bytes),
+        used_percent: Some(used_percent),
+        warning: used_percent >= config.storage.usage_warning_percent as f64,
+        error: None,
+    })
+}
+
+/// Reports the filesystem of every storage root, configured volume and parent directory
+/// holding a node, empty or not. A directory that can't be read only fails its own entry.
+pub fn get_parent_dirs_usage(config: &Config, bees: &[BeeData]) -> Result<Vec<ParentDirUsage>> {
+    let mut parent_dirs = BTreeMap::<PathBuf, Vec<u8>>::new();
+    for root in get_storage_roots(config) {
+        parent_dirs.entry(root).or_default();
+    }
+    // Parent dirs of the layout are only listed once created, most of them never are.
+    if config.storage.volumes.is_empty() && !config.storage.root_path.as_os_str().is_empty() {
+        for volume in get_volumes(config)? {
+            if volume.path.exists() {
+                parent_dirs.entry(volume.path).or_default();
+            }
+        }
+    }
+    for bee in bees {
+        if let Some(parent) = bee.data_dir.parent() {
+            parent_dirs
+                .entry(parent.to_owned())
+                .or_default()
+                .push(bee.id);
+        }
+    }
+
+    Ok(parent_dirs
+        .into_iter()
+        .map(|(path, bee_ids)| {
+            get_parent_dir_usage(config, &path, bee_ids.to_owned()).unwrap_or_else(|err| {
+                ParentDirUsage {
+                    path,
+                    bee_ids,
+                    error: Some(err.to_string()),
+                    ..Default::default()
+                }
+            })
+        })
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::config::Storage;
+    use crate::{core::database::MockDbService, models::config::Storage};
 
     #[tokio::test]
     async fn should_calculate_directory_id_correctly() {
@@ -121,7 +590,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn should_generate_correct_node_path() {
+    async fn should_derive_volumes_from_parent_dir_layout() {
         let temp_dir = tempfile::tempdir().unwrap();
         let root_path = temp_dir.path().to_path_buf();
         let config = Config {
@@ -134,13 +603,16 @@ mod tests {
             ..Default::default()
         };
 
-        let path = get_node_path(&config, 5).unwrap();
+        let volumes = get_volumes(&config).unwrap();
 
-        assert_eq!(path, root_path.join("swarm_data_02").join("node_05"));
+        assert_eq!(volumes.len(), 25);
+        assert_eq!(volumes[1].path, root_path.join("swarm_data_02"));
+        assert_eq!(volumes[1].capacity, 4);
+        assert_eq!(volumes[24].path, root_path.join("swarm_data_25"));
     }
 
     #[tokio::test]
-    async fn should_fail_node_path_with_invalid_parent_format() {
+    async fn should_fail_volumes_with_invalid_parent_format() {
         let temp_dir = tempfile::tempdir().unwrap();
         let root_path = temp_dir.path().to_path_buf();
         let config = Config {
@@ -153,7 +625,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = get_node_path(&config, 1);
+        let result = get_volumes(&config);
 
         assert!(result.is_err());
         assert_eq!(
@@ -162,6 +634,46 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn should_prefer_configured_volumes() {
+        let volumes = vec![Volume {
+            path: PathBuf::from("/mnt/disk1"),
+            capacity: 2,
+            max_size_gb: None,
+        }];
+        let config = Config {
+            storage: Storage {
+                root_path: PathBuf::from("/media"),
+                parent_dir_format: "data_xx".to_string(),
+                parent_dir_capacity: 3,
+                volumes: volumes.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(get_volumes(&config).unwrap(), volumes);
+    }
+
+    #[tokio::test]
+    async fn should_generate_correct_node_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root_path = temp_dir.path().to_path_buf();
+        let config = Config {
+            storage: Storage {
+                root_path: root_path.clone(),
+                parent_dir_format: "swarm_data_xx".to_string(),
+                parent_dir_capacity: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let path = get_node_path(&config, 5).unwrap();
+
+        assert_eq!(path, root_path.join("swarm_data_02").join("node_05"));
+    }
+
     #[tokio::test]
     async fn should_get_correct_path_for_first_id() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -200,6 +712,178 @@ mod tests {
         assert_eq!(path, root_path.join("storage_25").join("node_99"));
     }
 
+    #[test]
+    fn should_count_nodes_in_volume_through_symlinks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let disk = temp_dir.path().join("disk1");
+        let link = temp_dir.path().join("swarm");
+        std::fs::create_dir_all(&disk).unwrap();
+        std::os::unix::fs::symlink(&disk, &link).unwrap();
+        let config = Config {
+            storage: Storage {
+                volumes: vec![Volume {
+                    path: disk,
+                    capacity: 1,
+                    max_size_gb: None,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let bee = BeeData {
+            id: 1,
+            data_dir: link.join("node_01"),
+            ..Default::default()
+        };
+
+        assert!(select_volume(&config, &[bee]).is_err());
+    }
+
+    #[test]
+    fn should_pick_volume_by_policy() {
+        let candidates = [(1, 500), (2, 900), (4, 100)];
+
+        assert_eq!(
+            pick_volume(Placement::FillFirst, &candidates, 5, Some(4)),
+            Some(1)
+        );
+        assert_eq!(
+            pick_volume(Placement::MostFree, &candidates, 5, None),
+            Some(2)
+        );
+        assert_eq!(
+            pick_volume(Placement::RoundRobin, &candidates, 5, None),
+            Some(1)
+        );
+        assert_eq!(
+            pick_volume(Placement::RoundRobin, &candidates, 5, Some(1)),
+            Some(2)
+        );
+        assert_eq!(
+            pick_volume(Placement::RoundRobin, &candidates, 5, Some(2)),
+            Some(4)
+        );
+        assert_eq!(
+            pick_volume(Placement::RoundRobin, &candidates, 5, Some(4)),
+            Some(1)
+        );
+        assert_eq!(pick_volume(Placement::MostFree, &[], 5, None), None);
+    }
+
+    #[test]
+    fn should_skip_full_volumes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let first = temp_dir.path().join("disk1");
+        let second = temp_dir.path().join("disk2");
+        let volume = |path: &Path| Volume {
+            path: path.to_owned(),
+            capacity: 1,
+            max_size_gb: None,
+        };
+        let config = Config {
+            storage: Storage {
+                volumes: vec![volume(&first), volume(&second)],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let bee = |id: u8, parent: &Path| BeeData {
+            id,
+            data_dir: parent.join(get_node_name(id)),
+            created_at: id as u64,
+            ..Default::default()
+        };
+
+        assert_eq!(select_volume(&config, &[]).unwrap().path, first);
+        assert_eq!(
+            select_volume(&config, &[bee(1, &first)]).unwrap().path,
+            second
+        );
+        let result = select_volume(&config, &[bee(1, &first), bee(2, &second)]);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "No storage volume has room for a new node"
+        );
+    }
+
+    #[test]
+    fn should_skip_volume_over_size_limit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let first = temp_dir.path().join("disk1");
+        let second = temp_dir.path().join("disk2");
+        std::fs::create_dir_all(first.join("node_01")).unwrap();
+        std::fs::write(first.join("node_01").join("data"), vec![1; 4096]).unwrap();
+        let config = Config {
+            storage: Storage {
+                volumes: vec![
+                    Volume {
+                        path: first.clone(),
+                        capacity: 4,
+                        max_size_gb: Some(0),
+                    },
+                    Volume {
+                        path: second.clone(),
+                        capacity: 4,
+                        max_size_gb: None,
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let bee = BeeData {
+            id: 1,
+            data_dir: first.join("node_01"),
+            ..Default::default()
+        };
+        assert_eq!(select_volume(&config, &[bee]).unwrap().path, second);
+    }
+
+    async fn create_first_node_dir(config: &Config) -> Result<PathBuf> {
+        let reservations = BeeIdReservations::default();
+        let reservation = reservations.reserve(1);
+        create_node_dir(
+            config,
+            Box::new(MockDbService::default()),
+            &reservations,
+            &reservation,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn should_count_placed_nodes_not_saved_yet() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let volume = |disk: &str| Volume {
+            path: temp_dir.path().join(disk),
+            capacity: 1,
+            max_size_gb: None,
+        };
+        let config = Config {
+            storage: Storage {
+                volumes: vec![volume("disk1"), volume("disk2")],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let db = Box::new(MockDbService::default());
+        let reservations = BeeIdReservations::default();
+        let (first, second, third) = (
+            reservations.reserve(1),
+            reservations.reserve(2),
+            reservations.reserve(3),
+        );
+
+        let first_dir = create_node_dir(&config, db.clone(), &reservations, &first).await;
+        let second_dir = create_node_dir(&config, db.clone(), &reservations, &second).await;
+        let third_dir = create_node_dir(&config, db, &reservations, &third).await;
+
+        assert_eq!(first_dir.unwrap(), temp_dir.path().join("disk1/node_01"));
+        assert_eq!(second_dir.unwrap(), temp_dir.path().join("disk2/node_02"));
+        assert!(third_dir.is_err());
+    }
+
     #[tokio::test]
     async fn should_create_node_dir_successfully() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -214,7 +898,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = create_node_dir(&config, 1).await;
+        let result = create_first_node_dir(&config).await;
 
         assert!(result.is_ok());
         let node_path = result.unwrap();
@@ -241,7 +925,7 @@ mod tests {
         let existing_path = root_path.join("swarm_data_01").join("node_01");
         tokio::fs::create_dir_all(&existing_path).await.unwrap();
 
-        let result = create_node_dir(&config, 1).await;
+        let result = create_first_node_dir(&config).await;
 
         assert!(result.is_err());
         assert_eq!(
@@ -265,7 +949,7 @@ mod tests {
         let existing_path = root_path.join("swarm_data_01").join("node_02");
         tokio::fs::create_dir_all(&existing_path).await.unwrap();
 
-        let result = create_node_dir(&config, 1).await;
+        let result = create_first_node_dir(&config).await;
 
         assert!(result.is_ok());
     }
@@ -284,7 +968,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = create_node_dir(&config, 1).await;
+        let result = create_first_node_dir(&config).await;
 
         assert!(result.is_err());
         assert_eq!(
@@ -292,4 +976,183 @@ mod tests {
             "Invalid parent name format 'swarm_data_x'"
         );
     }
+
+    fn config_with_root(root_path: &Path) -> Config {
+        Config {
+            storage: Storage {
+                root_path: root_path.to_owned(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_accept_node_dir_with_bee_data() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let data_dir = temp_dir.path().join("swarm_data_01").join("node_01");
+        std::fs::create_dir_all(data_dir.join("keys")).unwrap();
+
+        let config = config_with_root(temp_dir.path());
+
+        assert!(check_node_dir(&config, &data_dir).unwrap());
+        assert!(!check_node_dir(&config, &temp_dir.path().join("node_02")).unwrap());
+    }
+
+    #[test]
+    fn should_refuse_node_dir_outside_storage_roots() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let other_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(other_dir.path().join("keys")).unwrap();
+
+        let config = config_with_root(root_dir.path());
+
+        let result = check_node_dir(&config, other_dir.path());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .ends_with("not inside a configured storage root"));
+        let result = check_node_dir(&config, root_dir.path());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_refuse_dir_without_bee_data() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let data_dir = temp_dir.path().join("node_01");
+        std::fs::create_dir_all(data_dir.join("photos")).unwrap();
+
+        let config = config_with_root(temp_dir.path());
+
+        let result = check_node_dir(&config, &data_dir);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .ends_with("not a bee data dir"));
+    }
+
+    #[test]
+    fn should_copy_and_verify_dir_tree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("swarm_data_02").join("node_07");
+        let destination = temp_dir.path().join("swarm_data_05").join("node_07");
+        std::fs::create_dir_all(source.join("localstore").join("sharky")).unwrap();
+        std::fs::create_dir_all(source.join("statestore")).unwrap();
+        std::fs::write(
+            source.join("localstore").join("sharky").join("0"),
+            vec![7; 1000],
+        )
+        .unwrap();
+        std::fs::write(source.join("keys"), "{}").unwrap();
+        std::os::unix::fs::symlink("keys", source.join("keys_link")).unwrap();
+
+        let mut progress = vec![];
+        copy_dir(&source, &destination, &mut |copied| progress.push(copied)).unwrap();
+
+        assert_eq!(progress.last(), Some(&1002));
+        assert_eq!(get_content_size(&source).unwrap(), 1002);
+        assert!(destination.join("statestore").is_dir());
+        assert!(destination.join("keys_link").is_symlink());
+        verify_copy(&source, &destination).unwrap();
+
+        std::fs::write(destination.join("keys"), "[]").unwrap();
+        let result = verify_copy(&source, &destination);
+        assert!(result.unwrap_err().to_string().ends_with(": keys"));
+    }
+
+    #[tokio::test]
+    async fn should_report_node_disk_usage_by_subdirectory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let data_dir = temp_dir.path().join("node_01");
+        std::fs::create_dir_all(data_dir.join("localstore")).unwrap();
+        std::fs::create_dir_all(data_dir.join("keys")).unwrap();
+        std::fs::write(
+            data_dir.join("localstore").join("chunks"),
+            vec![1; 64 * 1024],
+        )
+        .unwrap();
+        std::fs::write(data_dir.join("keys").join("swarm.key"), "{}").unwrap();
+
+        let usage = get_node_disk_usage(&BeeData {
+            id: 1,
+            data_dir: data_dir.clone(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(usage.name, "node_01");
+        assert!(usage.localstore_bytes >= 64 * 1024);
+        assert!(usage.keys_bytes > 0);
+        assert_eq!(usage.statestore_bytes, 0);
+        assert!(usage.total_bytes >= usage.localstore_bytes + usage.keys_bytes);
+    }
+
+    #[tokio::test]
+    async fn should_group_nodes_by_parent_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let first = temp_dir.path().join("swarm_data_01");
+        let second = temp_dir.path().join("swarm_data_02");
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        let bee = |id: u8, parent: &Path| BeeData {
+            id,
+            data_dir: parent.join(get_node_name(id)),
+            ..Default::default()
+        };
+        let bees = vec![bee(1, &first), bee(2, &first), bee(5, &second)];
+
+        let usages = get_parent_dirs_usage(&Config::default(), &bees).unwrap();
+
+        assert_eq!(usages.len(), 2);
+        assert_eq!(usages[0].path, first);
+        assert_eq!(usages[0].bee_ids, vec![1, 2]);
+        assert_eq!(usages[1].bee_ids, vec![5]);
+        assert!(usages[0].capacity_bytes.unwrap() > 0);
+    }
+
+    #[test]
+    fn should_report_empty_volumes_and_errors_per_entry() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let empty = temp_dir.path().join("disk1");
+        let missing = temp_dir.path().join("disk2");
+        std::fs::create_dir_all(&empty).unwrap();
+        let volume = |path: &Path| Volume {
+            path: path.to_owned(),
+            capacity: 4,
+            max_size_gb: None,
+        };
+        let config = Config {
+            storage: Storage {
+                volumes: vec![volume(&empty), volume(&missing)],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let usages = get_parent_dirs_usage(&config, &[]).unwrap();
+
+        assert_eq!(usages.len(), 2);
+        assert_eq!(usages[0].path, empty);
+        assert!(usages[0].bee_ids.is_empty());
+        assert!(usages[0].capacity_bytes.is_some());
+        assert!(usages[0].error.is_none());
+        assert_eq!(usages[1].path, missing);
+        assert!(usages[1].capacity_bytes.is_none());
+        assert!(usages[1].error.is_some());
+    }
+
+    #[test]
+    fn should_flag_parent_dir_above_warning_threshold() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+
+        config.storage.usage_warning_percent = 0;
+        let usage = get_parent_dir_usage(&config, temp_dir.path(), vec![]).unwrap();
+        assert!(usage.warning);
+
+        config.storage.usage_warning_percent = 101;
+        let usage = get_parent_dir_usage(&config, temp_dir.path(), vec![]).unwrap();
+        assert!(!usage.warning);
+    }
 }
@@ -0,0 +1,210 @@
+use anyhow::Result;
+use regex::Regex;
+
+use crate::{
+    core::database::BeeDatabase,
+    models::{
+        bee::{BeeData, TagsChange, UpdateTagsRequest},
+        http_error::ApiError,
+    },
+    utils::regex::TAG_KEY_REGEX,
+};
+
+const MAX_TAGS: usize = 32;
+const MAX_TAG_VALUE_LEN: usize = 256;
+const MAX_DESCRIPTION_LEN: usize = 1024;
+
+/// A `key=value` filter, or `key` alone to match any value.
+#[derive(Debug, PartialEq)]
+pub struct TagFilter {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+fn validate_tag_key(key: &str) -> Result<()> {
+    if !Regex::new(TAG_KEY_REGEX)?.is_match(key) {
+        return Err(ApiError::Validation(format!("Invalid tag key '{}'", key)).into());
+    }
+    Ok(())
+}
+
+pub fn validate_tag_update(request: &UpdateTagsRequest) -> Result<()> {
+    if request.tags.len() > MAX_TAGS {
+        return Err(
+            ApiError::Validation(format!("A node can't have more than {} tags", MAX_TAGS)).into(),
+        );
+    }
+    for (key, value) in &request.tags {
+        validate_tag_key(key)?;
+        if value.len() > MAX_TAG_VALUE_LEN {
+            return Err(ApiError::Validation(format!(
+                "The value of tag '{}' exceeds {} bytes",
+                key, MAX_TAG_VALUE_LEN
+            ))
+            .into());
+        }
+    }
+    if request
+        .description
+        .as_ref()
+        .is_some_and(|description| description.len() > MAX_DESCRIPTION_LEN)
+    {
+        return Err(ApiError::Validation(format!(
+            "The description exceeds {} bytes",
+            MAX_DESCRIPTION_LEN
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+pub fn parse_tag_filters(filters: &str) -> Result<Vec<TagFilter>> {
+    filters
+        .split(',')
+        .map(|filter| {
+            let (key, value) = match filter.split_once('=') {
+                Some((key, value)) => (key, Some(value.to_owned())),
+                None => (filter, None),
+            };
+            validate_tag_key(key)?;
+            Ok(TagFilter {
+                key: key.to_owned(),
+                value,
+            })
+        })
+        .collect()
+}
+
+pub fn matches_tags(bee: &BeeData, filters: &[TagFilter]) -> bool {
+    filters
+        .iter()
+        .all(|filter| match bee.tags.get(&filter.key) {
+            Some(value) => filter
+                .value
+                .as_ref()
+                .is_none_or(|expected| expected == value),
+            None => false,
+        })
+}
+
+pub fn parse_tag_keys(keys: Option<&str>) -> Option<Vec<String>> {
+    keys.map(|keys| keys.split(',').map(str::to_owned).collect())
+}
+
+/// Tags reach the container labels on its next creation or recreate. `None`
+/// once the node is gone.
+pub async fn update_bee_tags(
+    db: Box<dyn BeeDatabase>,
+    bee_id: u8,
+    request: UpdateTagsRequest,
+) -> Result<Option<BeeData>> {
+    db.update_bee_tags(bee_id, TagsChange::Replace(request))
+        .await
+}
+
+/// Removes the given keys, or every tag when `keys` is `None`.
+pub async fn delete_bee_tags(
+    db: Box<dyn BeeDatabase>,
+    bee_id: u8,
+    keys: Option<Vec<String>>,
+) -> Result<Option<BeeData>> {
+    db.update_bee_tags(bee_id, TagsChange::Remove(keys)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::database::MockDbService;
+    use std::collections::BTreeMap;
+
+    fn tagged(tags: &[(&str, &str)]) -> BeeData {
+        BeeData {
+            id: 1,
+            tags: tags
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_validate_tags() {
+        let request = |key: &str, value: &str| UpdateTagsRequest {
+            tags: BTreeMap::from([(key.to_owned(), value.to_owned())]),
+            description: None,
+        };
+
+        assert!(validate_tag_update(&request("owner", "teamA")).is_ok());
+        assert!(validate_tag_update(&request("disk.type", "nvme1")).is_ok());
+        assert!(validate_tag_update(&request("", "x")).is_err());
+        assert!(validate_tag_update(&request("has space", "x")).is_err());
+        assert!(validate_tag_update(&request("owner", &"x".repeat(300))).is_err());
+    }
+
+    #[test]
+    fn should_match_tag_filters() {
+        let bee = tagged(&[("owner", "teamA"), ("purpose", "canary")]);
+        let matches = |filters: &str| matches_tags(&bee, &parse_tag_filters(filters).unwrap());
+
+        assert!(matches("owner=teamA"));
+        assert!(matches("owner=teamA,purpose"));
+        assert!(!matches("owner=teamB"));
+        assert!(!matches("disk"));
+        assert!(parse_tag_filters("owner=a,=b").is_err());
+    }
+
+    #[tokio::test]
+    async fn should_replace_and_delete_tags() {
+        let db = Box::new(MockDbService::default());
+        let bee = BeeData {
+            description: Some("canary".to_owned()),
+            ..tagged(&[("owner", "teamA"), ("disk", "nvme1")])
+        };
+        db.add_bee(bee.clone()).await.unwrap();
+
+        let request = UpdateTagsRequest {
+            tags: BTreeMap::from([
+                ("owner".to_owned(), "teamB".to_owned()),
+                ("disk".to_owned(), "nvme2".to_owned()),
+            ]),
+            description: None,
+        };
+        let bee = update_bee_tags(db.clone(), 1, request)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(bee.tags["owner"], "teamB");
+        assert_eq!(bee.description.as_deref(), Some("canary"));
+
+        let bee = delete_bee_tags(db.clone(), 1, parse_tag_keys(Some("disk")))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(bee.tags, tagged(&[("owner", "teamB")]).tags);
+        assert_eq!(db.get_bees().await.unwrap()[0].tags, bee.tags);
+
+        let bee = delete_bee_tags(db.clone(), 1, None).await.unwrap().unwrap();
+        assert!(bee.tags.is_empty());
+        assert!(delete_bee_tags(db, 2, None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn should_clear_description_when_empty() {
+        let db = Box::new(MockDbService::default());
+        db.add_bee(BeeData {
+            description: Some("canary".to_owned()),
+            ..tagged(&[])
+        })
+        .await
+        .unwrap();
+
+        let request = UpdateTagsRequest {
+            tags: BTreeMap::new(),
+            description: Some(String::new()),
+        };
+        let bee = update_bee_tags(db, 1, request).await.unwrap().unwrap();
+
+        assert_eq!(bee.description, None);
+    }
+}
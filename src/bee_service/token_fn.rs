@@ -0,0 +1,246 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    core::database::BeeDatabase,
+    models::{
+        config::Config,
+        token::{ApiToken, ApiTokenInfo, CreateTokenRequest, NewApiToken, Principal},
+    },
+    utils::{
+        time::now_secs,
+        token::{generate_token, generate_token_id, hash_token, hashes_match},
+    },
+};
+
+pub async fn create_token(
+    db: Box<dyn BeeDatabase>,
+    request: &CreateTokenRequest,
+) -> Result<NewApiToken> {
+    let token = generate_token();
+    let api_token = ApiToken {
+        id: generate_token_id(),
+        name: request.name.to_owned(),
+        hash: hash_token(&token),
+        role: request.role,
+        bee_ids: request.bee_ids.to_owned(),
+        created_at: now_secs(),
+    };
+
+    db.add_token(api_token.clone()).await?;
+
+    Ok(NewApiToken {
+        id: api_token.id,
+        name: api_token.name,
+        token,
+        role: api_token.role,
+        bee_ids: api_token.bee_ids,
+        created_at: api_token.created_at,
+    })
+}
+
+pub async fn get_tokens(db: Box<dyn BeeDatabase>) -> Result<Vec<ApiTokenInfo>> {
+    Ok(db
+        .get_tokens()
+        .await?
+        .iter()
+        .map(ApiTokenInfo::new)
+        .collect())
+}
+
+pub async fn get_token(db: Box<dyn BeeDatabase>, token_id: &str) -> Result<Option<ApiTokenInfo>> {
+    Ok(db
+        .get_token(token_id)
+        .await?
+        .as_ref()
+        .map(ApiTokenInfo::new))
+}
+
+pub async fn revoke_token(db: Box<dyn BeeDatabase>, token_id: &str) -> Result<()> {
+    db.delete_token(token_id).await
+}
+
+/// With authentication on and no token at all, every request would be rejected.
+pub async fn check_auth_tokens(config: &Config, db: Box<dyn BeeDatabase>) -> Result<()> {
+    if config.auth.enabled && config.auth.tokens.is_empty() && db.get_tokens().await?.is_empty() {
+        return Err(anyhow!(
+            "Authentication is enabled but no token is configured. Add [[auth.tokens]] to the config, or set enabled = false under [auth]."
+        ));
+    }
+    Ok(())
+}
+
+/// Tokens from the config file have no id, theirs is derived from the secret
+/// so it doesn't change when the file is reordered.
+fn get_config_token_id(token: &str) -> String {
+    format!("config-{}", &hash_token(token)[..16])
+}
+
+/// Every known token is compared so the lookup time doesn't depend on which one matched.
+pub async fn authenticate(
+    config: &Config,
+    db: Box<dyn BeeDatabase>,
+    token: &str,
+) -> Result<Option<Principal>> {
+    let hash = hash_token(token);
+    let mut principal = None;
+
+    for config_token in &config.auth.tokens {
+        if hashes_match(&hash, &hash_token(&config_token.token)) {
+            principal = Some(Principal {
+                token_id: get_config_token_id(&config_token.token),
+                name: config_token.name.to_owned(),
+                role: config_token.role,
+                bee_ids: config_token.bee_ids.to_owned(),
+            });
+        }
+    }
+
+    for api_token in db.get_tokens().await? {
+        if hashes_match(&hash, &api_token.hash) {
+            principal = Some(Principal {
+                token_id: api_token.id.to_owned(),
+                name: api_token.name.to_owned(),
+                role: api_token.role,
+                bee_ids: api_token.bee_ids.to_owned(),
+            });
+        }
+    }
+
+    Ok(principal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::database::MockDbService,
+        models::{
+            config::{Auth, AuthToken},
+            token::Role,
+        },
+    };
+
+    fn config_with_token(name: &str, token: &str) -> Config {
+        Config {
+            auth: Auth {
+                enabled: true,
+                tokens: vec![AuthToken {
+                    name: name.to_owned(),
+                    token: token.to_owned(),
+                    role: Role::Admin,
+                    bee_ids: None,
+                }],
+            },
+            ..Default::default()
+        }
+    }
+
+    fn monitoring_request() -> CreateTokenRequest {
+        CreateTokenRequest {
+            name: "monitoring".to_owned(),
+            role: Role::Viewer,
+            bee_ids: Some(vec![1, 2]),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_store_hashed_token_on_creation() {
+        let db = Box::new(MockDbService::default());
+
+        let new_token = create_token(db.clone(), &monitoring_request())
+            .await
+            .unwrap();
+
+        let stored = db.get_token(&new_token.id).await.unwrap().unwrap();
+        assert_eq!(stored.name, "monitoring");
+        assert_ne!(stored.hash, new_token.token);
+        assert_eq!(stored.hash, hash_token(&new_token.token));
+    }
+
+    #[tokio::test]
+    async fn should_authenticate_created_token() {
+        let db = Box::new(MockDbService::default());
+        let new_token = create_token(db.clone(), &monitoring_request())
+            .await
+            .unwrap();
+
+        let principal = authenticate(&Config::default(), db, &new_token.token)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(principal.token_id, new_token.id);
+        assert_eq!(principal.name, "monitoring");
+        assert_eq!(principal.role, Role::Viewer);
+        assert_eq!(principal.bee_ids, Some(vec![1, 2]));
+    }
+
+    #[tokio::test]
+    async fn should_authenticate_config_token() {
+        let db = Box::new(MockDbService::default());
+        let config = config_with_token("ci", "some-token");
+
+        let principal = authenticate(&config, db, "some-token")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(principal.token_id, get_config_token_id("some-token"));
+        assert_eq!(principal.name, "ci");
+        assert_eq!(principal.role, Role::Admin);
+        assert!(principal.bee_ids.is_none());
+    }
+
+    #[tokio::test]
+    async fn should_reject_unknown_token() {
+        let db = Box::new(MockDbService::default());
+        let config = config_with_token("ci", "some-token");
+        create_token(db.clone(), &monitoring_request())
+            .await
+            .unwrap();
+
+        let principal = authenticate(&config, db, "unknown-token").await.unwrap();
+
+        assert!(principal.is_none());
+    }
+
+    #[tokio::test]
+    async fn should_refuse_auth_without_any_token() {
+        let db = Box::new(MockDbService::default());
+        let config = Config {
+            auth: Auth {
+                enabled: true,
+                tokens: Vec::new(),
+            },
+            ..Default::default()
+        };
+
+        assert!(check_auth_tokens(&config, db.clone()).await.is_err());
+
+        create_token(db.clone(), &monitoring_request())
+            .await
+            .unwrap();
+        assert!(check_auth_tokens(&config, db.clone()).await.is_ok());
+        assert!(
+            check_auth_tokens(&config_with_token("ci", "some-token"), db)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_reject_revoked_token() {
+        let db = Box::new(MockDbService::default());
+        let new_token = create_token(db.clone(), &monitoring_request())
+            .await
+            .unwrap();
+
+        revoke_token(db.clone(), &new_token.id).await.unwrap();
+
+        let principal = authenticate(&Config::default(), db.clone(), &new_token.token)
+            .await
+            .unwrap();
+        assert!(principal.is_none());
+        assert!(get_tokens(db).await.unwrap().is_empty());
+    }
+}
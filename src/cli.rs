@@ -0,0 +1,182 @@
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+use crate::bee_service::BeeService;
+use crate::core::{database::Database, sqlite::SqliteDatabase};
+use crate::models::config::Config;
+use crate::models::export::ConflictMode;
+
+const USAGE: &str = "Usage: ruche [db rebuild [--dry-run] [--report <file>] | db migrate-sqlite | export | import <file> [--conflict skip|overwrite|fail] [--dry-run]]";
+
+/// What the binary runs, the API server unless a maintenance command is given.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Serve,
+    /// Restores the nodes missing from `ruche.db` from the containers of this instance,
+    /// also writing the consistency report to a file when given one.
+    DbRebuild {
+        dry_run: bool,
+        report: Option<PathBuf>,
+    },
+    /// Copies `ruche.db` into the configured SQLite file, which must still be empty.
+    DbMigrateSqlite,
+    /// Prints the whole database as versioned JSON.
+    Export,
+    /// Loads a file written by `export`.
+    Import {
+        path: PathBuf,
+        conflict: ConflictMode,
+        dry_run: bool,
+    },
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        match args.as_slice() {
+            [] => Ok(Command::Serve),
+            ["db", "rebuild", options @ ..] => Command::parse_rebuild(options),
+            ["db", "migrate-sqlite"] => Ok(Command::DbMigrateSqlite),
+            ["export"] => Ok(Command::Export),
+            ["import", path, options @ ..] => Command::parse_import(path, options),
+            _ => Err(anyhow!(USAGE)),
+        }
+    }
+
+    fn parse_rebuild(options: &[&str]) -> Result<Self> {
+        let mut dry_run = false;
+        let mut report = None;
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            match *option {
+                "--dry-run" => dry_run = true,
+                "--report" => {
+                    let path = options.next().ok_or_else(|| anyhow!(USAGE))?;
+                    report = Some(PathBuf::from(path));
+                }
+                _ => return Err(anyhow!(USAGE)),
+            }
+        }
+        Ok(Command::DbRebuild { dry_run, report })
+    }
+
+    fn parse_import(path: &str, options: &[&str]) -> Result<Self> {
+        let mut conflict = ConflictMode::default();
+        let mut dry_run = false;
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            match *option {
+                "--dry-run" => dry_run = true,
+                "--conflict" => {
+                    let mode = options.next().ok_or_else(|| anyhow!(USAGE))?;
+                    conflict = serde_json::from_value(serde_json::Value::from(*mode))
+                        .map_err(|_| anyhow!(USAGE))?;
+                }
+                _ => return Err(anyhow!(USAGE)),
+            }
+        }
+        Ok(Command::Import {
+            path: PathBuf::from(path),
+            conflict,
+            dry_run,
+        })
+    }
+}
+
+/// Runs a maintenance command and prints its report as JSON.
+pub async fn run(command: Command, bee_service: &BeeService) -> Result<()> {
+    let report = match command {
+        Command::Serve => return Ok(()),
+        Command::DbRebuild { dry_run, report } => {
+            let content = serde_json::to_string_pretty(&bee_service.rebuild_bees(dry_run).await?)?;
+            if let Some(path) = report {
+                tokio::fs::write(&path, &content).await?;
+            }
+            content
+        }
+        Command::DbMigrateSqlite => return Ok(()),
+        Command::Export => serde_json::to_string_pretty(&bee_service.export_database().await?)?,
+        Command::Import {
+            path,
+            conflict,
+            dry_run,
+        } => {
+            let content = tokio::fs::read_to_string(&path).await?;
+            let export = serde_json::from_str(&content)?;
+            let report = bee_service
+                .import_database(export, conflict, dry_run)
+                .await?;
+            serde_json::to_string_pretty(&report)?
+        }
+    };
+    println!("{}", report);
+    Ok(())
+}
+
+/// Runs before any backend is opened, as `ruche.db` only accepts one handle.
+pub async fn migrate_sqlite(config: &Config) -> Result<()> {
+    let source = Database::open()?;
+    let target = SqliteDatabase::open(&config.database.sqlite_path)?;
+    let counts = target.import_from(&source).await?;
+    println!("{}", serde_json::to_string_pretty(&counts)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command> {
+        Command::parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn should_parse_commands() {
+        assert_eq!(parse(&[]).unwrap(), Command::Serve);
+        assert_eq!(
+            parse(&["db", "rebuild"]).unwrap(),
+            Command::DbRebuild {
+                dry_run: false,
+                report: None,
+            }
+        );
+        assert_eq!(
+            parse(&["db", "rebuild", "--dry-run"]).unwrap(),
+            Command::DbRebuild {
+                dry_run: true,
+                report: None,
+            }
+        );
+        assert_eq!(
+            parse(&["db", "rebuild", "--report", "rebuild.json", "--dry-run"]).unwrap(),
+            Command::DbRebuild {
+                dry_run: true,
+                report: Some(PathBuf::from("rebuild.json")),
+            }
+        );
+        assert!(parse(&["db", "rebuild", "--report"]).is_err());
+        assert_eq!(
+            parse(&["db", "migrate-sqlite"]).unwrap(),
+            Command::DbMigrateSqlite
+        );
+        assert_eq!(parse(&["export"]).unwrap(), Command::Export);
+        assert_eq!(
+            parse(&["import", "dump.json", "--conflict", "skip", "--dry-run"]).unwrap(),
+            Command::Import {
+                path: PathBuf::from("dump.json"),
+                conflict: ConflictMode::Skip,
+                dry_run: true,
+            }
+        );
+        assert_eq!(
+            parse(&["import", "dump.json"]).unwrap(),
+            Command::Import {
+                path: PathBuf::from("dump.json"),
+                conflict: ConflictMode::Fail,
+                dry_run: false,
+            }
+        );
+        assert!(parse(&["import", "dump.json", "--conflict", "merge"]).is_err());
+        assert!(parse(&["db"]).is_err());
+    }
+}
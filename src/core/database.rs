@@ -2,11 +2,24 @@ use anyhow::Error;
 use anyhow::Result;
 use async_trait::async_trait;
 use dyn_clone::DynClone;
-use polodb_core::bson::doc;
+use polodb_core::bson::{doc, to_bson, to_document};
 use polodb_core::Database as PoloDb;
 use polodb_core::{Collection, CollectionT};
+use std::cmp::Reverse;
 use std::collections::VecDeque;
+use std::path::Path;
 use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::migrations;
+use crate::models::{
+    audit::{AuditEntry, AuditQuery},
+    bee::{BeeData, DeletionRequest, TagsChange},
+    event::{BeeEvent, BeeEventQuery},
+    export::RecordsImport,
+    token::ApiToken,
+};
+use crate::utils::time::now_secs;
 
 dyn_clone::clone_trait_object!(BeeDatabase);
 
@@ -14,15 +27,55 @@ dyn_clone::clone_trait_object!(BeeDatabase);
 pub trait BeeDatabase: DynClone + Send + Sync {
     async fn add_bee(&self, bee: BeeData) -> Result<()>;
     async fn add_bees(&self, bees: Vec<BeeData>) -> Result<()>;
+    /// Inserts the node unless `capacity` nodes are already stored, atomically.
+    async fn add_bee_within_capacity(&self, bee: BeeData, capacity: u64) -> Result<bool>;
     async fn count_bees(&self) -> Result<u64>;
     async fn get_bee(&self, bee_id: u8) -> Result<Option<BeeData>>;
     async fn get_bees(&self) -> Result<Vec<BeeData>>;
+    async fn update_bee(&self, bee: BeeData) -> Result<()>;
     async fn delete_bee(&self, bee_id: u8) -> Result<()>;
+    async fn add_token(&self, token: ApiToken) -> Result<()>;
+    async fn get_token(&self, token_id: &str) -> Result<Option<ApiToken>>;
+    async fn get_tokens(&self) -> Result<Vec<ApiToken>>;
+    async fn delete_token(&self, token_id: &str) -> Result<()>;
+    async fn add_audit_entry(&self, entry: AuditEntry) -> Result<()>;
+    async fn get_audit_entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>>;
+    async fn add_bee_event(&self, event: BeeEvent) -> Result<()>;
+    async fn get_bee_events(&self, bee_id: u8, query: &BeeEventQuery) -> Result<Vec<BeeEvent>>;
+    /// Events of every node id oldest first, deleted nodes included.
+    async fn get_all_bee_events(&self) -> Result<Vec<BeeEvent>>;
+    /// Replaces the pending request of the same token for the node, and purges expired ones.
+    async fn put_deletion_request(&self, request: DeletionRequest) -> Result<()>;
+    /// Request matching the token hash, left in place.
+    async fn get_deletion_request(&self, bee_id: u8, hash: &str)
+        -> Result<Option<DeletionRequest>>;
+    /// Removes and returns the unexpired request of the API token matching the token hash,
+    /// so it is only used once. A request not matching them all is left in place.
+    async fn take_deletion_request(
+        &self,
+        bee_id: u8,
+        token_id: &str,
+        hash: &str,
+    ) -> Result<Option<DeletionRequest>>;
+    async fn delete_deletion_requests(&self, bee_id: u8) -> Result<()>;
+    /// Changes the tags and description of the stored node at once, `None` if it's gone.
+    async fn update_bee_tags(&self, bee_id: u8, change: TagsChange) -> Result<Option<BeeData>>;
+    /// Writes every record in one transaction, nothing is kept if one fails.
+    async fn import_records(&self, import: RecordsImport) -> Result<()>;
 }
 
-use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+/// Oldest first, the order they were recorded in.
+pub async fn get_all_audit_entries(db: &dyn BeeDatabase) -> Result<Vec<AuditEntry>> {
+    let query = AuditQuery {
+        limit: Some(usize::MAX),
+        ..Default::default()
+    };
+    let mut entries = db.get_audit_entries(&query).await?;
+    entries.reverse();
+    Ok(entries)
+}
 
-use crate::models::bee::BeeData;
+const DB_PATH: &str = "ruche.db";
 
 #[derive(Clone)]
 pub struct Database {
@@ -30,11 +83,22 @@ pub struct Database {
 }
 
 impl Database {
-    pub fn new() -> Self {
-        let db = PoloDb::open_path("ruche.db").expect("Failed to open database");
-        Database {
+    /// Opens the database and runs pending schema migrations, failing on a schema
+    /// newer than the binary. It's closed while backed up before migrating.
+    pub fn open() -> Result<Self> {
+        let db = PoloDb::open_path(DB_PATH)?;
+        let db = match migrations::get_backup_version(&db)? {
+            Some(version) => {
+                drop(db);
+                migrations::back_up(Path::new(DB_PATH), version)?;
+                PoloDb::open_path(DB_PATH)?
+            }
+            None => db,
+        };
+        migrations::migrate(&db)?;
+        Ok(Database {
             db: Arc::new(RwLock::new(db)),
-        }
+        })
     }
 
     async fn get_bees_col_write(&self) -> Collection<BeeData> {
@@ -44,6 +108,44 @@ impl Database {
     async fn get_bees_col_read(&self) -> Collection<BeeData> {
         self.db.read().await.collection::<BeeData>("bees")
     }
+
+    async fn get_tokens_col_write(&self) -> Collection<ApiToken> {
+        self.db.write().await.collection::<ApiToken>("tokens")
+    }
+
+    async fn get_tokens_col_read(&self) -> Collection<ApiToken> {
+        self.db.read().await.collection::<ApiToken>("tokens")
+    }
+
+    async fn get_audit_col_write(&self) -> Collection<AuditEntry> {
+        self.db.write().await.collection::<AuditEntry>("audit")
+    }
+
+    async fn get_audit_col_read(&self) -> Collection<AuditEntry> {
+        self.db.read().await.collection::<AuditEntry>("audit")
+    }
+
+    async fn get_events_col_write(&self) -> Collection<BeeEvent> {
+        self.db.write().await.collection::<BeeEvent>("events")
+    }
+
+    async fn get_events_col_read(&self) -> Collection<BeeEvent> {
+        self.db.read().await.collection::<BeeEvent>("events")
+    }
+
+    async fn get_deletion_requests_col_read(&self) -> Collection<DeletionRequest> {
+        self.db
+            .read()
+            .await
+            .collection::<DeletionRequest>("deletion_requests")
+    }
+
+    async fn get_deletion_requests_col_write(&self) -> Collection<DeletionRequest> {
+        self.db
+            .write()
+            .await
+            .collection::<DeletionRequest>("deletion_requests")
+    }
 }
 
 #[async_trait]
@@ -60,6 +162,19 @@ impl BeeDatabase for Database {
         Ok(())
     }
 
+    async fn add_bee_within_capacity(&self, bee: BeeData, capacity: u64) -> Result<bool> {
+        let db = self.db.write().await;
+        let transaction = db.start_transaction()?;
+        let collection = transaction.collection::<BeeData>("bees");
+        if collection.count_documents()? >= capacity {
+            transaction.rollback()?;
+            return Ok(false);
+        }
+        collection.insert_one(bee)?;
+        transaction.commit()?;
+        Ok(true)
+    }
+
     async fn count_bees(&self) -> Result<u64> {
         let collection = self.get_bees_col_read().await;
         collection.count_documents().map_err(Error::from)
@@ -88,16 +203,247 @@ impl BeeDatabase for Database {
         Ok(bees)
     }
 
+    async fn update_bee(&self, bee: BeeData) -> Result<()> {
+        let collection = self.get_bees_col_write().await;
+        collection.update_one(
+            doc! {"id": bee.id as i32},
+            doc! {"$set": to_document(&bee)?},
+        )?;
+        Ok(())
+    }
+
     async fn delete_bee(&self, bee_id: u8) -> Result<()> {
         let collection = self.get_bees_col_write().await;
         collection.delete_one(doc! {"id": bee_id as i32})?;
         Ok(())
     }
+
+    async fn add_token(&self, token: ApiToken) -> Result<()> {
+        let collection = self.get_tokens_col_write().await;
+        collection.insert_one(token)?;
+        Ok(())
+    }
+
+    async fn get_token(&self, token_id: &str) -> Result<Option<ApiToken>> {
+        let collection = self.get_tokens_col_read().await;
+        let result = collection.find_one(doc! {"id": token_id})?;
+        Ok(result)
+    }
+
+    async fn get_tokens(&self) -> Result<Vec<ApiToken>> {
+        let collection = self.get_tokens_col_read().await;
+        let cursor = collection
+            .find(doc! {})
+            .sort(doc! {
+                "created_at": 1
+            })
+            .run()
+            .map_err(Error::from)?;
+        let mut tokens = Vec::new();
+        for result in cursor {
+            let token = result.map_err(Error::from)?;
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+
+    async fn delete_token(&self, token_id: &str) -> Result<()> {
+        let collection = self.get_tokens_col_write().await;
+        collection.delete_one(doc! {"id": token_id})?;
+        Ok(())
+    }
+
+    async fn add_audit_entry(&self, entry: AuditEntry) -> Result<()> {
+        let collection = self.get_audit_col_write().await;
+        collection.insert_one(entry)?;
+        Ok(())
+    }
+
+    async fn get_audit_entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let collection = self.get_audit_col_read().await;
+        let mut filter = doc! {};
+        if let Some(action) = &query.action {
+            filter.insert("action", action);
+        }
+        let cursor = collection
+            .find(filter)
+            .sort(doc! {
+                "timestamp": -1
+            })
+            .run()
+            .map_err(Error::from)?;
+        let mut entries = Vec::new();
+        for result in cursor {
+            let entry = result.map_err(Error::from)?;
+            if query.matches(&entry) {
+                entries.push(entry);
+            }
+            if entries.len() >= query.limit() {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn add_bee_event(&self, event: BeeEvent) -> Result<()> {
+        let collection = self.get_events_col_write().await;
+        collection.insert_one(event)?;
+        Ok(())
+    }
+
+    async fn get_bee_events(&self, bee_id: u8, query: &BeeEventQuery) -> Result<Vec<BeeEvent>> {
+        let collection = self.get_events_col_read().await;
+        let cursor = collection
+            .find(doc! {"bee_id": bee_id as i32})
+            .sort(doc! {
+                "timestamp": -1
+            })
+            .run()
+            .map_err(Error::from)?;
+        let mut events = Vec::new();
+        for result in cursor {
+            let event = result.map_err(Error::from)?;
+            if query.matches(&event) {
+                events.push(event);
+            }
+            if events.len() >= query.limit() {
+                break;
+            }
+        }
+        Ok(events)
+    }
+
+    async fn get_all_bee_events(&self) -> Result<Vec<BeeEvent>> {
+        let collection = self.get_events_col_read().await;
+        let cursor = collection
+            .find(doc! {})
+            .sort(doc! {
+                "timestamp": 1
+            })
+            .run()
+            .map_err(Error::from)?;
+        cursor.map(|result| result.map_err(Error::from)).collect()
+    }
+
+    async fn put_deletion_request(&self, request: DeletionRequest) -> Result<()> {
+        let db = self.db.write().await;
+        let transaction = db.start_transaction()?;
+        let collection = transaction.collection::<DeletionRequest>("deletion_requests");
+        collection.delete_many(doc! {"expires_at": {"$lt": now_secs() as i64}})?;
+        collection.delete_many(doc! {
+            "bee_id": request.bee_id as i32,
+            "token_id": &request.token_id,
+        })?;
+        collection.insert_one(request)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    async fn get_deletion_request(
+        &self,
+        bee_id: u8,
+        hash: &str,
+    ) -> Result<Option<DeletionRequest>> {
+        let collection = self.get_deletion_requests_col_read().await;
+        Ok(collection.find_one(doc! {"bee_id": bee_id as i32, "hash": hash})?)
+    }
+
+    async fn take_deletion_request(
+        &self,
+        bee_id: u8,
+        token_id: &str,
+        hash: &str,
+    ) -> Result<Option<DeletionRequest>> {
+        let db = self.db.write().await;
+        let transaction = db.start_transaction()?;
+        let collection = transaction.collection::<DeletionRequest>("deletion_requests");
+        let filter = doc! {
+            "bee_id": bee_id as i32,
+            "token_id": token_id,
+            "hash": hash,
+            "expires_at": {"$gte": now_secs() as i64},
+        };
+        let request = collection.find_one(filter.clone())?;
+        if request.is_some() {
+            collection.delete_one(filter)?;
+        }
+        transaction.commit()?;
+        Ok(request)
+    }
+
+    async fn delete_deletion_requests(&self, bee_id: u8) -> Result<()> {
+        let collection = self.get_deletion_requests_col_write().await;
+        collection.delete_many(doc! {"bee_id": bee_id as i32})?;
+        Ok(())
+    }
+
+    async fn update_bee_tags(&self, bee_id: u8, change: TagsChange) -> Result<Option<BeeData>> {
+        let db = self.db.write().await;
+        let transaction = db.start_transaction()?;
+        let collection = transaction.collection::<BeeData>("bees");
+        let filter = doc! {"id": bee_id as i32};
+        let Some(mut bee) = collection.find_one(filter.clone())? else {
+            transaction.rollback()?;
+            return Ok(None);
+        };
+        change.apply(&mut bee);
+        collection.update_one(
+            filter,
+            doc! {"$set": {
+                "tags": to_bson(&bee.tags)?,
+                "description": to_bson(&bee.description)?,
+            }},
+        )?;
+        transaction.commit()?;
+        Ok(Some(bee))
+    }
+
+    async fn import_records(&self, import: RecordsImport) -> Result<()> {
+        let db = self.db.write().await;
+        let transaction = db.start_transaction()?;
+        let result = (|| -> Result<()> {
+            let bees = transaction.collection::<BeeData>("bees");
+            if !import.added_bees.is_empty() {
+                bees.insert_many(import.added_bees)?;
+            }
+            for bee in import.overwritten_bees {
+                bees.update_one(
+                    doc! {"id": bee.id as i32},
+                    doc! {"$set": to_document(&bee)?},
+                )?;
+            }
+            let tokens = transaction.collection::<ApiToken>("tokens");
+            for token in import.tokens {
+                tokens.delete_one(doc! {"id": &token.id})?;
+                tokens.insert_one(token)?;
+            }
+            if !import.audit.is_empty() {
+                transaction
+                    .collection::<AuditEntry>("audit")
+                    .insert_many(import.audit)?;
+            }
+            if !import.events.is_empty() {
+                transaction
+                    .collection::<BeeEvent>("events")
+                    .insert_many(import.events)?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => transaction.commit()?,
+            Err(_) => transaction.rollback()?,
+        }
+        result
+    }
 }
 
 #[derive(Default, Clone)]
 pub struct MockDbService {
     db: Arc<RwLock<VecDeque<BeeData>>>,
+    tokens: Arc<RwLock<Vec<ApiToken>>>,
+    audit: Arc<RwLock<Vec<AuditEntry>>>,
+    events: Arc<RwLock<Vec<BeeEvent>>>,
+    deletion_requests: Arc<RwLock<Vec<DeletionRequest>>>,
 }
 
 impl MockDbService {
@@ -108,22 +454,47 @@ impl MockDbService {
     async fn get_bees_col_read(&self) -> RwLockReadGuard<'_, VecDeque<BeeData>> {
         self.db.read().await
     }
+
+    /// Mirrors the unique index on `id` of the real backends.
+    fn ensure_unique_id(queue: &VecDeque<BeeData>, bee_id: u8) -> Result<()> {
+        if queue.iter().any(|bee| bee.id == bee_id) {
+            return Err(anyhow::anyhow!("Duplicate bee id {}", bee_id));
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl BeeDatabase for MockDbService {
     async fn add_bee(&self, bee: BeeData) -> Result<()> {
         let mut queue = self.get_bees_col_write().await;
+        MockDbService::ensure_unique_id(&queue, bee.id)?;
         queue.push_back(bee);
         Ok(())
     }
 
     async fn add_bees(&self, bees: Vec<BeeData>) -> Result<()> {
         let mut queue = self.get_bees_col_write().await;
+        for (index, bee) in bees.iter().enumerate() {
+            MockDbService::ensure_unique_id(&queue, bee.id)?;
+            if bees[..index].iter().any(|other| other.id == bee.id) {
+                return Err(anyhow::anyhow!("Duplicate bee id {}", bee.id));
+            }
+        }
         queue.extend(bees);
         Ok(())
     }
 
+    async fn add_bee_within_capacity(&self, bee: BeeData, capacity: u64) -> Result<bool> {
+        let mut queue = self.get_bees_col_write().await;
+        if queue.len() as u64 >= capacity {
+            return Ok(false);
+        }
+        MockDbService::ensure_unique_id(&queue, bee.id)?;
+        queue.push_back(bee);
+        Ok(true)
+    }
+
     async fn count_bees(&self) -> Result<u64> {
         let queue = self.get_bees_col_read().await;
         Ok(queue.len() as u64)
@@ -139,9 +510,163 @@ impl BeeDatabase for MockDbService {
         Ok(queue.clone().make_contiguous().to_vec())
     }
 
+    async fn update_bee(&self, bee: BeeData) -> Result<()> {
+        let mut queue = self.get_bees_col_write().await;
+        if let Some(existing) = queue.iter_mut().find(|existing| existing.id == bee.id) {
+            *existing = bee;
+        }
+        Ok(())
+    }
+
     async fn delete_bee(&self, bee_id: u8) -> Result<()> {
         let mut queue = self.get_bees_col_write().await;
         queue.retain(|bee| bee.id != bee_id);
         Ok(())
     }
+
+    async fn add_token(&self, token: ApiToken) -> Result<()> {
+        self.tokens.write().await.push(token);
+        Ok(())
+    }
+
+    async fn get_token(&self, token_id: &str) -> Result<Option<ApiToken>> {
+        let tokens = self.tokens.read().await;
+        Ok(tokens.iter().find(|token| token.id == token_id).cloned())
+    }
+
+    async fn get_tokens(&self) -> Result<Vec<ApiToken>> {
+        Ok(self.tokens.read().await.clone())
+    }
+
+    async fn delete_token(&self, token_id: &str) -> Result<()> {
+        self.tokens
+            .write()
+            .await
+            .retain(|token| token.id != token_id);
+        Ok(())
+    }
+
+    async fn add_audit_entry(&self, entry: AuditEntry) -> Result<()> {
+        self.audit.write().await.push(entry);
+        Ok(())
+    }
+
+    async fn get_audit_entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let audit = self.audit.read().await;
+        let mut entries = audit
+            .iter()
+            .filter(|entry| query.matches(entry))
+            .cloned()
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| Reverse(entry.timestamp));
+        entries.truncate(query.limit());
+        Ok(entries)
+    }
+
+    async fn add_bee_event(&self, event: BeeEvent) -> Result<()> {
+        self.events.write().await.push(event);
+        Ok(())
+    }
+
+    async fn get_bee_events(&self, bee_id: u8, query: &BeeEventQuery) -> Result<Vec<BeeEvent>> {
+        let events = self.events.read().await;
+        let mut events = events
+            .iter()
+            .filter(|event| event.bee_id == bee_id && query.matches(event))
+            .cloned()
+            .collect::<Vec<_>>();
+        events.sort_by_key(|event| Reverse(event.timestamp));
+        events.truncate(query.limit());
+        Ok(events)
+    }
+
+    async fn get_all_bee_events(&self) -> Result<Vec<BeeEvent>> {
+        let mut events = self.events.read().await.clone();
+        events.sort_by_key(|event| event.timestamp);
+        Ok(events)
+    }
+
+    async fn put_deletion_request(&self, request: DeletionRequest) -> Result<()> {
+        let now = now_secs();
+        let mut requests = self.deletion_requests.write().await;
+        requests.retain(|existing| {
+            existing.expires_at >= now
+                && (existing.bee_id != request.bee_id || existing.token_id != request.token_id)
+        });
+        requests.push(request);
+        Ok(())
+    }
+
+    async fn get_deletion_request(
+        &self,
+        bee_id: u8,
+        hash: &str,
+    ) -> Result<Option<DeletionRequest>> {
+        let requests = self.deletion_requests.read().await;
+        Ok(requests
+            .iter()
+            .find(|request| request.bee_id == bee_id && request.hash == hash)
+            .cloned())
+    }
+
+    async fn take_deletion_request(
+        &self,
+        bee_id: u8,
+        token_id: &str,
+        hash: &str,
+    ) -> Result<Option<DeletionRequest>> {
+        let now = now_secs();
+        let mut requests = self.deletion_requests.write().await;
+        let index = requests.iter().position(|request| {
+            request.bee_id == bee_id
+                && request.token_id == token_id
+                && request.hash == hash
+                && request.expires_at >= now
+        });
+        Ok(index.map(|index| requests.remove(index)))
+    }
+
+    async fn delete_deletion_requests(&self, bee_id: u8) -> Result<()> {
+        self.deletion_requests
+            .write()
+            .await
+            .retain(|request| request.bee_id != bee_id);
+        Ok(())
+    }
+
+    async fn update_bee_tags(&self, bee_id: u8, change: TagsChange) -> Result<Option<BeeData>> {
+        let mut queue = self.get_bees_col_write().await;
+        let bee = queue.iter_mut().find(|bee| bee.id == bee_id);
+        Ok(bee.map(|bee| {
+            change.apply(bee);
+            bee.clone()
+        }))
+    }
+
+    async fn import_records(&self, import: RecordsImport) -> Result<()> {
+        let mut queue = self.get_bees_col_write().await;
+        let mut tokens = self.tokens.write().await;
+        for (index, bee) in import.added_bees.iter().enumerate() {
+            MockDbService::ensure_unique_id(&queue, bee.id)?;
+            if import.added_bees[..index]
+                .iter()
+                .any(|other| other.id == bee.id)
+            {
+                return Err(anyhow::anyhow!("Duplicate bee id {}", bee.id));
+            }
+        }
+        queue.extend(import.added_bees);
+        for bee in import.overwritten_bees {
+            if let Some(existing) = queue.iter_mut().find(|existing| existing.id == bee.id) {
+                *existing = bee;
+            }
+        }
+        for token in import.tokens {
+            tokens.retain(|existing| existing.id != token.id);
+            tokens.push(token);
+        }
+        self.audit.write().await.extend(import.audit);
+        self.events.write().await.extend(import.events);
+        Ok(())
+    }
 }
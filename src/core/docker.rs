@@ -1,24 +1,50 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use bollard::{
     container::{
-        Config as ContainerConfig, CreateContainerOptions, LogsOptions, RemoveContainerOptions,
-        StartContainerOptions, StopContainerOptions,
+        Config as ContainerConfig, CreateContainerOptions, InspectContainerOptions,
+        ListContainersOptions, LogsOptions, MemoryStatsStats, RemoveContainerOptions,
+        RenameContainerOptions, StartContainerOptions, Stats, StatsOptions, StopContainerOptions,
     },
     image::CreateImageOptions,
-    secret::{HostConfig, PortBinding, RestartPolicy, RestartPolicyNameEnum},
+    secret::{
+        ContainerInspectResponse, ContainerSummary, EventMessage, HostConfig, PortBinding,
+        RestartPolicy, RestartPolicyNameEnum,
+    },
+    system::EventsOptions,
     Docker as BollarDocker,
 };
 use dyn_clone::DynClone;
-use futures_util::TryStreamExt;
+use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
 use nix::unistd::{getgid, getuid};
+use sha2::{Digest, Sha256};
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::Mutex;
 
-use crate::models::{bee::BeeInfo, config::Config};
+use crate::bee_service::BeeService;
+use crate::core::metrics::Metrics;
+use crate::models::{
+    bee::{get_node_mode, BeeInfo},
+    config::Config,
+    event::ContainerEvent,
+    import::BeeContainer,
+    stats::ContainerStats,
+};
 
 dyn_clone::clone_trait_object!(BeeDocker);
 
+pub const LABEL_MANAGED_BY: &str = "ruche.managed-by";
+pub const MANAGED_BY: &str = "ruche";
+pub const LABEL_INSTANCE: &str = "ruche.instance";
+pub const LABEL_NODE_ID: &str = "ruche.node.id";
+pub const LABEL_NEIGHBORHOOD: &str = "ruche.node.neighborhood";
+pub const LABEL_MODE: &str = "ruche.node.mode";
+/// Hash of everything else in the container config, to spot containers created from an older config.
+pub const LABEL_CONFIG_HASH: &str = "ruche.config-hash";
+/// Prefix of the labels carrying the node tags, e.g. `ruche.tag.owner`.
+pub const LABEL_TAG_PREFIX: &str = "ruche.tag.";
+
+/// Containers are addressed by node name, `node_xx`, whatever name prefix is configured.
 #[async_trait]
 pub trait BeeDocker: DynClone + Send + Sync {
     async fn create_bee_container(&self, bee: &BeeInfo, config: &Config) -> Result<()>;
@@ -27,22 +53,134 @@ pub trait BeeDocker: DynClone + Send + Sync {
     async fn remove_bee_container(&self, name: &str) -> Result<()>;
     async fn recreate_container(&self, bee: &BeeInfo, config: &Config) -> Result<()>;
     async fn get_bee_container_logs(&self, name: &str) -> Result<Vec<String>>;
+    /// Events of the containers labeled as managed by this instance.
+    async fn get_bee_container_events(&self) -> Result<BoxStream<'static, Result<ContainerEvent>>>;
+    /// States of the containers labeled as managed by this instance.
+    async fn get_bee_container_states(&self) -> Result<HashMap<String, String>>;
+    async fn get_bee_container_stats(&self, name: &str) -> Result<ContainerStats>;
+    /// Every container named like a node, labeled or not, for adopting existing nodes.
+    async fn get_bee_containers(&self) -> Result<Vec<BeeContainer>>;
+    /// Renames the containers labeled as managed by this instance under another name
+    /// prefix, returns the names of their nodes.
+    async fn rename_prefixed_bee_containers(&self) -> Result<Vec<String>>;
 }
 
 #[derive(Clone)]
 pub struct Docker {
     docker: Arc<Mutex<BollarDocker>>,
+    metrics: Metrics,
+    instance_id: String,
+    name_prefix: String,
 }
 
 impl Docker {
-    pub fn new() -> Self {
+    pub fn new(metrics: Metrics, config: &Config) -> Self {
         let docker =
             BollarDocker::connect_with_socket_defaults().expect("Failed to connect to docker");
         Docker {
             docker: Arc::new(Mutex::new(docker)),
+            metrics,
+            instance_id: config.instance_id.to_owned(),
+            name_prefix: config.name_prefix.to_owned(),
+        }
+    }
+
+    /// Client of a daemon that never answers, for tests that don't reach Docker.
+    #[cfg(test)]
+    pub fn unreachable(metrics: Metrics, config: &Config) -> Self {
+        let docker =
+            BollarDocker::connect_with_http("http://127.0.0.1:9", 1, bollard::API_DEFAULT_VERSION)
+                .unwrap();
+        Docker {
+            docker: Arc::new(Mutex::new(docker)),
+            metrics,
+            instance_id: config.instance_id.to_owned(),
+            name_prefix: config.name_prefix.to_owned(),
         }
     }
 
+    /// Containers labeled as managed by this instance, running or not.
+    async fn list_labeled_containers(&self) -> Result<Vec<ContainerSummary>> {
+        let docker = self.docker.lock().await;
+        let mut filters = HashMap::new();
+        filters.insert(
+            "label".to_owned(),
+            Docker::get_label_filters(&self.instance_id),
+        );
+
+        self.metrics
+            .observe_docker_call("list_containers", async {
+                docker
+                    .list_containers(Some(ListContainersOptions::<String> {
+                        all: true,
+                        filters,
+                        ..Default::default()
+                    }))
+                    .await
+                    .map_err(Into::into)
+            })
+            .await
+    }
+
+    fn get_container_name(&self, name: &str) -> String {
+        format!("{}{}", self.name_prefix, name)
+    }
+
+    fn get_node_name(container_name: &str, name_prefix: &str) -> Option<String> {
+        let name = container_name.strip_prefix(name_prefix)?;
+        let id = name.strip_prefix("node_")?.parse().ok()?;
+        (BeeService::get_node_name(id) == name).then(|| name.to_owned())
+    }
+
+    fn get_label_filters(instance_id: &str) -> Vec<String> {
+        vec![
+            format!("{}={}", LABEL_MANAGED_BY, MANAGED_BY),
+            format!("{}={}", LABEL_INSTANCE, instance_id),
+        ]
+    }
+
+    /// Node name of a container labeled as managed by the given instance.
+    fn get_labeled_node_name(
+        labels: &HashMap<String, String>,
+        instance_id: &str,
+    ) -> Option<String> {
+        if labels.get(LABEL_MANAGED_BY).map(String::as_str) != Some(MANAGED_BY)
+            || labels.get(LABEL_INSTANCE).map(String::as_str) != Some(instance_id)
+        {
+            return None;
+        }
+        let id = labels.get(LABEL_NODE_ID)?.parse().ok()?;
+        Some(BeeService::get_node_name(id))
+    }
+
+    /// JSON with object keys sorted, so that map ordering doesn't change the hash.
+    fn to_canonical_json(value: &serde_json::Value) -> String {
+        match value {
+            serde_json::Value::Object(map) => {
+                let mut entries = map
+                    .iter()
+                    .map(|(key, value)| format!("{:?}:{}", key, Docker::to_canonical_json(value)))
+                    .collect::<Vec<_>>();
+                entries.sort();
+                format!("{{{}}}", entries.join(","))
+            }
+            serde_json::Value::Array(values) => {
+                let values = values
+                    .iter()
+                    .map(Docker::to_canonical_json)
+                    .collect::<Vec<_>>();
+                format!("[{}]", values.join(","))
+            }
+            value => value.to_string(),
+        }
+    }
+
+    fn get_config_hash(container_config: &ContainerConfig<String>) -> String {
+        let value = serde_json::to_value(container_config).unwrap_or_default();
+        let digest = Sha256::digest(Docker::to_canonical_json(&value).as_bytes());
+        hex::encode(&digest[..8])
+    }
+
     fn get_container_config(bee: &BeeInfo, config: &Config) -> ContainerConfig<String> {
         let bee_data_dir = "/home/bee/.bee";
         let data_dir_mount = format!("{}:{}", bee.data_dir.to_string_lossy(), bee_data_dir);
@@ -72,7 +210,18 @@ impl Docker {
             true => Some(vec!["host.docker.internal:host-gateway".to_owned()]),
         };
 
-        ContainerConfig {
+        let mut labels = HashMap::from([
+            (LABEL_MANAGED_BY.to_owned(), MANAGED_BY.to_owned()),
+            (LABEL_INSTANCE.to_owned(), config.instance_id.to_owned()),
+            (LABEL_NODE_ID.to_owned(), bee.id.to_string()),
+            (LABEL_NEIGHBORHOOD.to_owned(), bee.neighborhood.to_owned()),
+            (
+                LABEL_MODE.to_owned(),
+                get_node_mode(bee.full_node, bee.swap_enable).to_owned(),
+            ),
+        ]);
+
+        let mut container_config = ContainerConfig {
             image: Some(bee.image.clone()),
             cmd: Some(vec!["start".to_owned()]),
             host_config: Some(HostConfig {
@@ -102,6 +251,132 @@ impl Docker {
                 format!("BEE_WELCOME_MESSAGE={}", config.bee.welcome_msg),
             ]),
             ..Default::default()
+        };
+
+        labels.insert(
+            LABEL_CONFIG_HASH.to_owned(),
+            Docker::get_config_hash(&container_config),
+        );
+        labels.extend(
+            bee.tags
+                .iter()
+                .map(|(key, value)| (format!("{}{}", LABEL_TAG_PREFIX, key), value.to_owned())),
+        );
+        container_config.labels = Some(labels);
+        container_config
+    }
+
+    /// Event attributes carry the container labels, which name the node.
+    fn to_container_event(message: EventMessage, instance_id: &str) -> Option<ContainerEvent> {
+        let attributes = message.actor?.attributes?;
+        let name = Docker::get_labeled_node_name(&attributes, instance_id)?;
+
+        Some(ContainerEvent {
+            name,
+            action: message.action?,
+            timestamp: message.time.unwrap_or_default().max(0) as u64,
+            image: attributes.get("image").cloned(),
+            exit_code: attributes
+                .get("exitCode")
+                .and_then(|code| code.parse().ok()),
+        })
+    }
+
+    fn to_bee_container(container: ContainerInspectResponse, name: &str) -> BeeContainer {
+        let config = container.config.unwrap_or_default();
+        BeeContainer {
+            name: name.to_owned(),
+            image: config.image.unwrap_or_default(),
+            labels: config.labels.unwrap_or_default(),
+            env: config
+                .env
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|var| var.split_once('='))
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+            mounts: container
+                .mounts
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|mount| Some((mount.destination?, mount.source?.into())))
+                .collect(),
+        }
+    }
+
+    /// Computes usage the same way `docker stats` does, page cache excluded from memory.
+    fn to_container_stats(name: &str, stats: Stats) -> ContainerStats {
+        let cpu_delta = stats
+            .cpu_stats
+            .cpu_usage
+            .total_usage
+            .saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
+        let system_delta = stats
+            .cpu_stats
+            .system_cpu_usage
+            .unwrap_or_default()
+            .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or_default());
+        let online_cpus = stats.cpu_stats.online_cpus.unwrap_or_else(|| {
+            stats
+                .cpu_stats
+                .cpu_usage
+                .percpu_usage
+                .as_ref()
+                .map_or(1, |percpu| percpu.len() as u64)
+        });
+        let cpu_percent = match system_delta {
+            0 => 0.0,
+            _ => cpu_delta as f64 / system_delta as f64 * online_cpus as f64 * 100.0,
+        };
+
+        let inactive_file = match &stats.memory_stats.stats {
+            Some(MemoryStatsStats::V1(v1)) => v1.total_inactive_file,
+            Some(MemoryStatsStats::V2(v2)) => v2.inactive_file,
+            None => 0,
+        };
+        let memory_usage = stats
+            .memory_stats
+            .usage
+            .unwrap_or_default()
+            .saturating_sub(inactive_file);
+        let memory_limit = stats.memory_stats.limit.unwrap_or_default();
+        let memory_percent = match memory_limit {
+            0 => 0.0,
+            _ => memory_usage as f64 / memory_limit as f64 * 100.0,
+        };
+
+        let (net_rx_bytes, net_tx_bytes) = stats
+            .networks
+            .unwrap_or_default()
+            .values()
+            .fold((0, 0), |(rx, tx), network| {
+                (rx + network.rx_bytes, tx + network.tx_bytes)
+            });
+
+        let (block_read_bytes, block_write_bytes) = stats
+            .blkio_stats
+            .io_service_bytes_recursive
+            .unwrap_or_default()
+            .iter()
+            .fold((0, 0), |(read, write), entry| {
+                match entry.op.to_lowercase().as_str() {
+                    "read" => (read + entry.value, write),
+                    "write" => (read, write + entry.value),
+                    _ => (read, write),
+                }
+            });
+
+        ContainerStats {
+            name: name.to_owned(),
+            cpu_percent,
+            memory_usage,
+            memory_limit,
+            memory_percent,
+            net_rx_bytes,
+            net_tx_bytes,
+            block_read_bytes,
+            block_write_bytes,
+            pids: stats.pids_stats.current.unwrap_or_default(),
         }
     }
 }
@@ -113,26 +388,36 @@ impl BeeDocker for Docker {
 
         let container_config = Docker::get_container_config(bee, config);
 
-        docker
-            .create_image(
-                Some(CreateImageOptions {
-                    from_image: config.bee.image.to_owned(),
-                    ..Default::default()
-                }),
-                None,
-                None,
-            )
-            .try_collect::<Vec<_>>()
+        self.metrics
+            .observe_docker_call("create_image", async {
+                docker
+                    .create_image(
+                        Some(CreateImageOptions {
+                            from_image: config.bee.image.to_owned(),
+                            ..Default::default()
+                        }),
+                        None,
+                        None,
+                    )
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .map_err(Into::into)
+            })
             .await?;
 
-        docker
-            .create_container(
-                Some(CreateContainerOptions {
-                    name: bee.name.clone(),
-                    platform: None,
-                }),
-                container_config,
-            )
+        self.metrics
+            .observe_docker_call("create_container", async {
+                docker
+                    .create_container(
+                        Some(CreateContainerOptions {
+                            name: self.get_container_name(&bee.name),
+                            platform: None,
+                        }),
+                        container_config,
+                    )
+                    .await
+                    .map_err(Into::into)
+            })
             .await?;
 
         Ok(())
@@ -140,26 +425,44 @@ impl BeeDocker for Docker {
 
     async fn start_bee_container(&self, name: &str) -> Result<()> {
         let docker = self.docker.lock().await;
-        docker
-            .start_container(name, None::<StartContainerOptions<String>>)
+        self.metrics
+            .observe_docker_call("start_container", async {
+                docker
+                    .start_container(
+                        &self.get_container_name(name),
+                        None::<StartContainerOptions<String>>,
+                    )
+                    .await
+                    .map_err(Into::into)
+            })
             .await
-            .map_err(Into::into)
     }
 
     async fn stop_bee_container(&self, name: &str) -> Result<()> {
         let docker = self.docker.lock().await;
-        docker
-            .stop_container(name, None::<StopContainerOptions>)
+        self.metrics
+            .observe_docker_call("stop_container", async {
+                docker
+                    .stop_container(&self.get_container_name(name), None::<StopContainerOptions>)
+                    .await
+                    .map_err(Into::into)
+            })
             .await
-            .map_err(Into::into)
     }
 
     async fn remove_bee_container(&self, name: &str) -> Result<()> {
         let docker = self.docker.lock().await;
-        docker
-            .remove_container(name, None::<RemoveContainerOptions>)
+        self.metrics
+            .observe_docker_call("remove_container", async {
+                docker
+                    .remove_container(
+                        &self.get_container_name(name),
+                        None::<RemoveContainerOptions>,
+                    )
+                    .await
+                    .map_err(Into::into)
+            })
             .await
-            .map_err(Into::into)
     }
 
     async fn recreate_container(&self, bee: &BeeInfo, config: &Config) -> Result<()> {
@@ -173,16 +476,22 @@ impl BeeDocker for Docker {
 
     async fn get_bee_container_logs(&self, name: &str) -> Result<Vec<String>> {
         let docker = self.docker.lock().await;
-        let logs = docker
-            .logs(
-                name,
-                Some(LogsOptions::<String> {
-                    stdout: true,
-                    stderr: true,
-                    ..Default::default()
-                }),
-            )
-            .try_collect::<Vec<_>>()
+        let logs = self
+            .metrics
+            .observe_docker_call("logs", async {
+                docker
+                    .logs(
+                        &self.get_container_name(name),
+                        Some(LogsOptions::<String> {
+                            stdout: true,
+                            stderr: true,
+                            ..Default::default()
+                        }),
+                    )
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .map_err(Into::into)
+            })
             .await?;
 
         Ok(logs
@@ -190,6 +499,155 @@ impl BeeDocker for Docker {
             .map(|log| String::from_utf8_lossy(&log.into_bytes()).into_owned())
             .collect())
     }
+
+    async fn get_bee_container_events(&self) -> Result<BoxStream<'static, Result<ContainerEvent>>> {
+        let docker = self.docker.lock().await;
+        let mut filters = HashMap::new();
+        filters.insert("type".to_owned(), vec!["container".to_owned()]);
+        filters.insert(
+            "label".to_owned(),
+            Docker::get_label_filters(&self.instance_id),
+        );
+
+        let instance_id = self.instance_id.to_owned();
+        let events = docker
+            .events(Some(EventsOptions::<String> {
+                filters,
+                ..Default::default()
+            }))
+            .filter_map(move |message| {
+                let instance_id = instance_id.to_owned();
+                async move {
+                    match message {
+                        Ok(message) => Docker::to_container_event(message, &instance_id).map(Ok),
+                        Err(err) => Some(Err(err.into())),
+                    }
+                }
+            });
+
+        Ok(events.boxed())
+    }
+
+    async fn get_bee_container_states(&self) -> Result<HashMap<String, String>> {
+        let containers = self.list_labeled_containers().await?;
+
+        Ok(containers
+            .into_iter()
+            .filter_map(|container| {
+                let labels = container.labels.unwrap_or_default();
+                let name = Docker::get_labeled_node_name(&labels, &self.instance_id)?;
+                Some((name, container.state.unwrap_or_default()))
+            })
+            .collect())
+    }
+
+    async fn get_bee_container_stats(&self, name: &str) -> Result<ContainerStats> {
+        // Docker samples twice to compute CPU usage, so the lock isn't held while waiting.
+        let docker = self.docker.lock().await.clone();
+        let stats = self
+            .metrics
+            .observe_docker_call("stats", async {
+                docker
+                    .stats(
+                        &self.get_container_name(name),
+                        Some(StatsOptions {
+                            stream: false,
+                            one_shot: false,
+                        }),
+                    )
+                    .try_next()
+                    .await?
+                    .ok_or_else(|| anyhow!("No stats returned for {}", name))
+            })
+            .await?;
+
+        Ok(Docker::to_container_stats(name, stats))
+    }
+
+    async fn get_bee_containers(&self) -> Result<Vec<BeeContainer>> {
+        let docker = self.docker.lock().await;
+        let mut filters = HashMap::new();
+        filters.insert("name".to_owned(), vec![self.get_container_name("node_")]);
+
+        let summaries = self
+            .metrics
+            .observe_docker_call("list_containers", async {
+                docker
+                    .list_containers(Some(ListContainersOptions::<String> {
+                        all: true,
+                        filters,
+                        ..Default::default()
+                    }))
+                    .await
+                    .map_err(Into::into)
+            })
+            .await?;
+        // The name filter matches substrings, only exact `<prefix>node_xx` names are kept.
+        let names = summaries
+            .into_iter()
+            .flat_map(|container| container.names.unwrap_or_default())
+            .filter_map(|name| {
+                Docker::get_node_name(name.trim_start_matches('/'), &self.name_prefix)
+            })
+            .collect::<Vec<_>>();
+
+        let mut containers = Vec::new();
+        for name in names {
+            let container = self
+                .metrics
+                .observe_docker_call("inspect_container", async {
+                    docker
+                        .inspect_container(
+                            &self.get_container_name(&name),
+                            None::<InspectContainerOptions>,
+                        )
+                        .await
+                        .map_err(Into::into)
+                })
+                .await?;
+            containers.push(Docker::to_bee_container(container, &name));
+        }
+        containers.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(containers)
+    }
+
+    async fn rename_prefixed_bee_containers(&self) -> Result<Vec<String>> {
+        let containers = self.list_labeled_containers().await?;
+        let docker = self.docker.lock().await;
+
+        let mut renamed = Vec::new();
+        for container in containers {
+            let labels = container.labels.unwrap_or_default();
+            let Some(name) = Docker::get_labeled_node_name(&labels, &self.instance_id) else {
+                continue;
+            };
+            let expected = self.get_container_name(&name);
+            let Some(current) = container
+                .names
+                .unwrap_or_default()
+                .first()
+                .map(|current| current.trim_start_matches('/').to_owned())
+            else {
+                continue;
+            };
+            if current == expected {
+                continue;
+            }
+            self.metrics
+                .observe_docker_call("rename_container", async {
+                    docker
+                        .rename_container(&current, RenameContainerOptions { name: expected })
+                        .await
+                        .map_err(Into::into)
+                })
+                .await
+                .map_err(|err: anyhow::Error| {
+                    anyhow!("Failed to rename container {}: {}", current, err)
+                })?;
+            renamed.push(name);
+        }
+        Ok(renamed)
+    }
 }
 
 #[cfg(test)]
@@ -197,7 +655,7 @@ mod tests {
     use crate::models::config::{Bee, Chains, Network, Storage};
 
     use super::*;
-    use std::path::PathBuf;
+    use std::{collections::BTreeMap, path::PathBuf};
 
     // Helper function to create test data
     fn create_test_data() -> (BeeInfo, Config) {
@@ -212,6 +670,8 @@ mod tests {
             data_dir: PathBuf::from("/home/lowkey/swarm_test/swarm_data_01/node_01"),
             api_port: "1701".to_string(),
             p2p_port: "1801".to_string(),
+            tags: BTreeMap::from([("owner".to_owned(), "teamA".to_owned())]),
+            description: None,
         };
 
         let config = Config {
@@ -223,6 +683,7 @@ mod tests {
                 full_node: false,
                 swap_enable: false,
                 reserve_doubling: true,
+                protect_funded: false,
             },
             network: Network {
                 nat_addr: "1.1.1.1".to_string(),
@@ -238,6 +699,7 @@ mod tests {
                 root_path: PathBuf::from("/media"),
                 parent_dir_format: "swarm_data_xx".to_string(),
                 parent_dir_capacity: 4,
+                ..Default::default()
             },
             ..Default::default()
         };
@@ -413,6 +875,201 @@ mod tests {
         assert!(exposed_ports.contains_key("1801"));
     }
 
+    fn labels(instance_id: &str, node_id: &str) -> HashMap<String, String> {
+        HashMap::from([
+            (LABEL_MANAGED_BY.to_owned(), MANAGED_BY.to_owned()),
+            (LABEL_INSTANCE.to_owned(), instance_id.to_owned()),
+            (LABEL_NODE_ID.to_owned(), node_id.to_owned()),
+        ])
+    }
+
+    #[test]
+    fn test_node_name_from_container_name() {
+        assert_eq!(
+            Docker::get_node_name("staging_node_07", "staging_"),
+            Some("node_07".to_owned())
+        );
+        assert_eq!(Docker::get_node_name("node_07", "staging_"), None);
+        assert_eq!(Docker::get_node_name("staging_node_7", "staging_"), None);
+        assert_eq!(
+            Docker::get_node_name("node_07", ""),
+            Some("node_07".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_container_event_from_die_message() {
+        let mut attributes = labels("ruche", "12");
+        attributes.extend([
+            ("name".to_owned(), "other_node_12".to_owned()),
+            ("image".to_owned(), "ethersphere/bee:2.5.0".to_owned()),
+            ("exitCode".to_owned(), "137".to_owned()),
+        ]);
+        let message = EventMessage {
+            action: Some("die".to_owned()),
+            actor: Some(bollard::secret::EventActor {
+                id: Some("abc".to_owned()),
+                attributes: Some(attributes),
+            }),
+            time: Some(1700000000),
+            ..Default::default()
+        };
+
+        let event = Docker::to_container_event(message, "ruche").unwrap();
+
+        assert_eq!(event.name, "node_12");
+        assert_eq!(event.action, "die");
+        assert_eq!(event.timestamp, 1700000000);
+        assert_eq!(event.image, Some("ethersphere/bee:2.5.0".to_owned()));
+        assert_eq!(event.exit_code, Some(137));
+    }
+
+    #[test]
+    fn test_container_event_ignores_other_containers() {
+        let message = |attributes| EventMessage {
+            action: Some("start".to_owned()),
+            actor: Some(bollard::secret::EventActor {
+                id: Some("abc".to_owned()),
+                attributes: Some(attributes),
+            }),
+            ..Default::default()
+        };
+        let unlabeled = HashMap::from([("name".to_owned(), "node_01".to_owned())]);
+
+        assert!(Docker::to_container_event(message(unlabeled), "ruche").is_none());
+        assert!(Docker::to_container_event(message(labels("other", "1")), "ruche").is_none());
+    }
+
+    #[test]
+    fn test_container_stats_from_docker_stats() {
+        let stats: Stats = serde_json::from_value(serde_json::json!({
+            "read": "2025-01-01T00:00:01Z",
+            "preread": "2025-01-01T00:00:00Z",
+            "num_procs": 0,
+            "pids_stats": { "current": 24 },
+            "networks": {
+                "eth0": { "rx_bytes": 1000, "tx_bytes": 500, "rx_packets": 0, "tx_packets": 0,
+                    "rx_errors": 0, "tx_errors": 0, "rx_dropped": 0, "tx_dropped": 0 }
+            },
+            "memory_stats": {
+                "usage": 600,
+                "limit": 2000,
+                "stats": { "inactive_file": 100, "active_anon": 0, "active_file": 0, "anon": 0,
+                    "anon_thp": 0, "file": 0, "file_dirty": 0, "file_mapped": 0,
+                    "file_writeback": 0, "inactive_anon": 0, "kernel_stack": 0, "pgactivate": 0,
+                    "pgdeactivate": 0, "pgfault": 0, "pglazyfree": 0, "pglazyfreed": 0,
+                    "pgmajfault": 0, "pgrefill": 0, "pgscan": 0, "pgsteal": 0, "shmem": 0,
+                    "slab": 0, "slab_reclaimable": 0, "slab_unreclaimable": 0, "sock": 0,
+                    "thp_collapse_alloc": 0, "thp_fault_alloc": 0, "unevictable": 0,
+                    "workingset_activate": 0, "workingset_nodereclaim": 0,
+                    "workingset_refault": 0 }
+            },
+            "blkio_stats": {
+                "io_service_bytes_recursive": [
+                    { "major": 8, "minor": 0, "op": "read", "value": 4096 },
+                    { "major": 8, "minor": 0, "op": "write", "value": 8192 }
+                ]
+            },
+            "cpu_stats": {
+                "cpu_usage": { "total_usage": 300, "usage_in_usermode": 0, "usage_in_kernelmode": 0 },
+                "system_cpu_usage": 2000,
+                "online_cpus": 4,
+                "throttling_data": { "periods": 0, "throttled_periods": 0, "throttled_time": 0 }
+            },
+            "precpu_stats": {
+                "cpu_usage": { "total_usage": 100, "usage_in_usermode": 0, "usage_in_kernelmode": 0 },
+                "system_cpu_usage": 1000,
+                "online_cpus": 4,
+                "throttling_data": { "periods": 0, "throttled_periods": 0, "throttled_time": 0 }
+            },
+            "storage_stats": {}
+        }))
+        .unwrap();
+
+        let stats = Docker::to_container_stats("node_01", stats);
+
+        assert_eq!(stats.name, "node_01");
+        assert_eq!(stats.cpu_percent, 80.0);
+        assert_eq!(stats.memory_usage, 500);
+        assert_eq!(stats.memory_limit, 2000);
+        assert_eq!(stats.memory_percent, 25.0);
+        assert_eq!(stats.net_rx_bytes, 1000);
+        assert_eq!(stats.net_tx_bytes, 500);
+        assert_eq!(stats.block_read_bytes, 4096);
+        assert_eq!(stats.block_write_bytes, 8192);
+        assert_eq!(stats.pids, 24);
+    }
+
+    #[test]
+    fn test_bee_container_from_inspect() {
+        let container: ContainerInspectResponse = serde_json::from_value(serde_json::json!({
+            "Name": "/node_03",
+            "Config": {
+                "Image": "ethersphere/bee:2.5.0",
+                "Env": ["BEE_FULL_NODE=true", "BEE_NAT_ADDR=1.1.1.1:1803"],
+                "Labels": { "ruche.node.id": "3" }
+            },
+            "Mounts": [{
+                "Type": "bind",
+                "Source": "/media/swarm_data_01/node_03",
+                "Destination": "/home/bee/.bee"
+            }]
+        }))
+        .unwrap();
+
+        let container = Docker::to_bee_container(container, "node_03");
+
+        assert_eq!(container.name, "node_03");
+        assert_eq!(container.image, "ethersphere/bee:2.5.0");
+        assert_eq!(container.env["BEE_NAT_ADDR"], "1.1.1.1:1803");
+        assert_eq!(container.labels[LABEL_NODE_ID], "3");
+        assert_eq!(
+            container.mounts["/home/bee/.bee"],
+            PathBuf::from("/media/swarm_data_01/node_03")
+        );
+    }
+
+    #[test]
+    fn test_container_labels() {
+        let (bee_info, mut config) = create_test_data();
+        config.instance_id = "host-a".to_string();
+
+        let container_config = Docker::get_container_config(&bee_info, &config);
+
+        let labels = container_config.labels.unwrap();
+        assert_eq!(labels[LABEL_MANAGED_BY], "ruche");
+        assert_eq!(labels[LABEL_INSTANCE], "host-a");
+        assert_eq!(labels[LABEL_NODE_ID], "1");
+        assert_eq!(labels[LABEL_NEIGHBORHOOD], "1111101010");
+        assert_eq!(labels[LABEL_MODE], "full");
+        assert_eq!(labels[LABEL_CONFIG_HASH].len(), 16);
+        assert_eq!(labels["ruche.tag.owner"], "teamA");
+    }
+
+    #[test]
+    fn test_config_hash_follows_config() {
+        let (bee_info, config) = create_test_data();
+        let hash = |bee_info: &BeeInfo| {
+            Docker::get_container_config(bee_info, &config)
+                .labels
+                .unwrap()[LABEL_CONFIG_HASH]
+                .to_owned()
+        };
+
+        let upgraded = BeeInfo {
+            image: "ethersphere/bee:2.6.0".to_string(),
+            ..bee_info.clone()
+        };
+        let retagged = BeeInfo {
+            tags: BTreeMap::new(),
+            ..bee_info.clone()
+        };
+
+        assert_eq!(hash(&bee_info), hash(&bee_info));
+        assert_ne!(hash(&bee_info), hash(&upgraded));
+        assert_eq!(hash(&bee_info), hash(&retagged));
+    }
+
     #[test]
     fn test_user() {
         let (bee_info, config) = create_test_data();
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::models::job::{Job, JobKind};
+use crate::utils::{time::now_secs, token::generate_token_id};
+
+/// In-memory registry of jobs. Jobs don't survive a restart, their outcome is
+/// still visible in the node events and the audit log.
+#[derive(Clone, Default)]
+pub struct Jobs {
+    jobs: Arc<RwLock<HashMap<String, Job>>>,
+}
+
+impl Jobs {
+    /// Registers a new running job unless one is already running for the node.
+    pub fn start(&self, kind: JobKind, bee_id: u8) -> Option<Job> {
+        let mut jobs = self.jobs.write().unwrap();
+        if jobs
+            .values()
+            .any(|job| job.bee_id == bee_id && job.is_running())
+        {
+            return None;
+        }
+
+        let job = Job {
+            id: generate_token_id(),
+            kind,
+            bee_id,
+            created_at: now_secs(),
+            ..Default::default()
+        };
+        jobs.insert(job.id.to_owned(), job.clone());
+        Some(job)
+    }
+
+    /// Whether a job still works on the node, whose data dir may be in use.
+    pub fn is_busy(&self, bee_id: u8) -> bool {
+        self.jobs
+            .read()
+            .unwrap()
+            .values()
+            .any(|job| job.bee_id == bee_id && job.is_running())
+    }
+
+    pub fn update(&self, job_id: &str, update: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.write().unwrap().get_mut(job_id) {
+            update(job);
+        }
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<Job> {
+        self.jobs.read().unwrap().get(job_id).cloned()
+    }
+
+    pub fn get_all(&self) -> Vec<Job> {
+        let mut jobs = self
+            .jobs
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        jobs.sort_by_key(|job| job.created_at);
+        jobs
+    }
+
+    pub fn get_bee_jobs(&self, bee_id: u8) -> Vec<Job> {
+        let mut jobs = self
+            .jobs
+            .read()
+            .unwrap()
+            .values()
+            .filter(|job| job.bee_id == bee_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        jobs.sort_by_key(|job| job.created_at);
+        jobs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::job::JobStatus;
+
+    #[test]
+    fn should_refuse_concurrent_jobs_on_same_bee() {
+        let jobs = Jobs::default();
+
+        let job = jobs.start(JobKind::Move, 7).unwrap();
+        assert!(jobs.start(JobKind::Move, 7).is_none());
+        assert!(jobs.start(JobKind::Move, 8).is_some());
+
+        assert!(jobs.is_busy(7));
+        jobs.update(&job.id, |job| job.status = JobStatus::Completed);
+        assert!(!jobs.is_busy(7));
+        assert!(jobs.start(JobKind::Move, 7).is_some());
+        assert_eq!(jobs.get_bee_jobs(7).len(), 2);
+    }
+}
@@ -0,0 +1,306 @@
+use anyhow::Result;
+use prometheus_client::encoding::text::encode;
+use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::registry::Registry;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct HttpLabels {
+    pub method: String,
+    pub route: String,
+    pub status: u16,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct RouteLabels {
+    pub method: String,
+    pub route: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct DockerLabels {
+    pub operation: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct StateLabels {
+    pub state: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct NodeLabels {
+    pub node: String,
+}
+
+/// Values scraped from a bee node API. Amounts are in base units (PLUR and wei)
+/// and missing on nodes that don't expose them, such as ultra-light nodes.
+#[derive(Default, Clone, Debug)]
+pub struct BeeMetrics {
+    pub peers: f64,
+    pub storage_radius: f64,
+    pub reserve_size: f64,
+    pub stake: Option<f64>,
+    pub bzz_balance: Option<f64>,
+    pub native_balance: Option<f64>,
+}
+
+type FloatGauge = Gauge<f64, AtomicU64>;
+
+fn histogram() -> Histogram {
+    Histogram::new(exponential_buckets(0.005, 2.0, 14))
+}
+
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Arc<Registry>,
+    http_requests: Family<HttpLabels, Counter>,
+    http_request_duration: Family<RouteLabels, Histogram>,
+    docker_call_duration: Family<DockerLabels, Histogram>,
+    docker_call_errors: Family<DockerLabels, Counter>,
+    bees: Family<StateLabels, Gauge>,
+    bee_up: Family<NodeLabels, Gauge>,
+    bee_peers: Family<NodeLabels, FloatGauge>,
+    bee_storage_radius: Family<NodeLabels, FloatGauge>,
+    bee_reserve_size: Family<NodeLabels, FloatGauge>,
+    bee_stake: Family<NodeLabels, FloatGauge>,
+    bee_bzz_balance: Family<NodeLabels, FloatGauge>,
+    bee_native_balance: Family<NodeLabels, FloatGauge>,
+    scrape_lock: Arc<Mutex<()>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let mut registry = Registry::with_prefix("ruche");
+
+        let http_requests = Family::<HttpLabels, Counter>::default();
+        registry.register(
+            "http_requests",
+            "HTTP requests handled by ruche",
+            http_requests.clone(),
+        );
+        let http_request_duration =
+            Family::<RouteLabels, Histogram>::new_with_constructor(histogram as fn() -> Histogram);
+        registry.register(
+            "http_request_duration_seconds",
+            "HTTP request latency",
+            http_request_duration.clone(),
+        );
+        let docker_call_duration =
+            Family::<DockerLabels, Histogram>::new_with_constructor(histogram as fn() -> Histogram);
+        registry.register(
+            "docker_call_duration_seconds",
+            "Duration of calls to the Docker API",
+            docker_call_duration.clone(),
+        );
+        let docker_call_errors = Family::<DockerLabels, Counter>::default();
+        registry.register(
+            "docker_call_errors",
+            "Failed calls to the Docker API",
+            docker_call_errors.clone(),
+        );
+        let bees = Family::<StateLabels, Gauge>::default();
+        registry.register(
+            "bees",
+            "Registered bee nodes by container state",
+            bees.clone(),
+        );
+
+        let bee_up = Family::<NodeLabels, Gauge>::default();
+        registry.register(
+            "bee_up",
+            "Whether the bee node API answered the last scrape",
+            bee_up.clone(),
+        );
+        let mut register_node_gauge = |name: &str, help: &str| {
+            let family = Family::<NodeLabels, FloatGauge>::default();
+            registry.register(name, help, family.clone());
+            family
+        };
+        let bee_peers = register_node_gauge("bee_peers", "Connected peers");
+        let bee_storage_radius = register_node_gauge("bee_storage_radius", "Storage radius");
+        let bee_reserve_size = register_node_gauge("bee_reserve_size", "Reserve size in chunks");
+        let bee_stake = register_node_gauge("bee_stake_plur", "Staked amount in PLUR");
+        let bee_bzz_balance =
+            register_node_gauge("bee_wallet_bzz_plur", "Wallet xBZZ balance in PLUR");
+        let bee_native_balance =
+            register_node_gauge("bee_wallet_native_wei", "Wallet xDAI balance in wei");
+
+        Metrics {
+            registry: Arc::new(registry),
+            http_requests,
+            http_request_duration,
+            docker_call_duration,
+            docker_call_errors,
+            bees,
+            bee_up,
+            bee_peers,
+            bee_storage_radius,
+            bee_reserve_size,
+            bee_stake,
+            bee_bzz_balance,
+            bee_native_balance,
+            scrape_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn observe_http_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        self.http_requests
+            .get_or_create(&HttpLabels {
+                method: method.to_owned(),
+                route: route.to_owned(),
+                status,
+            })
+            .inc();
+        self.http_request_duration
+            .get_or_create(&RouteLabels {
+                method: method.to_owned(),
+                route: route.to_owned(),
+            })
+            .observe(duration.as_secs_f64());
+    }
+
+    pub async fn observe_docker_call<T, F>(&self, operation: &str, call: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let labels = DockerLabels {
+            operation: operation.to_owned(),
+        };
+        let start = Instant::now();
+        let result = call.await;
+
+        self.docker_call_duration
+            .get_or_create(&labels)
+            .observe(start.elapsed().as_secs_f64());
+        if result.is_err() {
+            self.docker_call_errors.get_or_create(&labels).inc();
+        }
+        result
+    }
+
+    /// Replaces the fleet gauges and per-node metrics with the given scrape and
+    /// returns the whole registry in Prometheus text format.
+    pub async fn encode_scrape(
+        &self,
+        states: &HashMap<String, i64>,
+        bees: &[(String, Option<BeeMetrics>)],
+    ) -> Result<String> {
+        let _lock = self.scrape_lock.lock().await;
+
+        self.bees.clear();
+        for (state, count) in states {
+            self.bees
+                .get_or_create(&StateLabels {
+                    state: state.to_owned(),
+                })
+                .set(*count);
+        }
+
+        for family in self.node_gauges() {
+            family.clear();
+        }
+        self.bee_up.clear();
+        for (node, metrics) in bees {
+            let labels = NodeLabels {
+                node: node.to_owned(),
+            };
+            self.bee_up
+                .get_or_create(&labels)
+                .set(metrics.is_some() as i64);
+            let Some(metrics) = metrics else {
+                continue;
+            };
+            let values = [
+                Some(metrics.peers),
+                Some(metrics.storage_radius),
+                Some(metrics.reserve_size),
+                metrics.stake,
+                metrics.bzz_balance,
+                metrics.native_balance,
+            ];
+            for (family, value) in self.node_gauges().into_iter().zip(values) {
+                if let Some(value) = value {
+                    family.get_or_create(&labels).set(value);
+                }
+            }
+        }
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &self.registry)?;
+        Ok(buffer)
+    }
+
+    fn node_gauges(&self) -> [&Family<NodeLabels, FloatGauge>; 6] {
+        [
+            &self.bee_peers,
+            &self.bee_storage_radius,
+            &self.bee_reserve_size,
+            &self.bee_stake,
+            &self.bee_bzz_balance,
+            &self.bee_native_balance,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[tokio::test]
+    async fn should_encode_http_and_docker_metrics() {
+        let metrics = Metrics::new();
+        metrics.observe_http_request("GET", "/bee/{bee_id}", 200, Duration::from_millis(20));
+        let _ = metrics
+            .observe_docker_call("start_container", async { Err::<(), _>(anyhow!("boom")) })
+            .await;
+
+        let text = metrics.encode_scrape(&HashMap::new(), &[]).await.unwrap();
+
+        assert!(text.contains(
+            "ruche_http_requests_total{method=\"GET\",route=\"/bee/{bee_id}\",status=\"200\"} 1"
+        ));
+        assert!(text.contains("ruche_docker_call_errors_total{operation=\"start_container\"} 1"));
+        assert!(text
+            .contains("ruche_docker_call_duration_seconds_count{operation=\"start_container\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn should_replace_node_metrics_on_each_scrape() {
+        let metrics = Metrics::new();
+        let states = HashMap::from([("running".to_owned(), 1)]);
+        let bee_metrics = BeeMetrics {
+            peers: 150.0,
+            storage_radius: 11.0,
+            ..Default::default()
+        };
+
+        metrics
+            .encode_scrape(
+                &states,
+                &[
+                    ("node_01".to_owned(), Some(bee_metrics)),
+                    ("node_02".to_owned(), None),
+                ],
+            )
+            .await
+            .unwrap();
+        let text = metrics
+            .encode_scrape(&states, &[("node_02".to_owned(), None)])
+            .await
+            .unwrap();
+
+        assert!(text.contains("ruche_bees{state=\"running\"} 1"));
+        assert!(text.contains("ruche_bee_up{node=\"node_02\"} 0"));
+        assert!(!text.contains("node_01"));
+    }
+}
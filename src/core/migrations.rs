@@ -0,0 +1,273 @@
+use anyhow::{anyhow, Result};
+use polodb_core::bson::{doc, Document};
+use polodb_core::Database as PoloDb;
+use polodb_core::{CollectionT, IndexModel, IndexOptions};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::utils::time::now_secs;
+
+/// Schema version this binary reads and writes.
+pub const SCHEMA_VERSION: u32 = 2;
+
+const META_COLLECTION: &str = "meta";
+const SCHEMA_DOC_NAME: &str = "schema";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaVersion {
+    name: String,
+    version: u32,
+}
+
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub run: fn(&PoloDb) -> Result<()>,
+}
+
+/// Migrations in ascending version order, each one moves the schema to its `version`.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema with versioning",
+        run: |_| Ok(()),
+    },
+    Migration {
+        version: 2,
+        description: "unique index on node id",
+        run: |db| {
+            db.collection::<Document>("bees").create_index(IndexModel {
+                keys: doc! {"id": 1},
+                options: Some(IndexOptions {
+                    name: Some("bees_id".to_owned()),
+                    unique: Some(true),
+                }),
+            })?;
+            Ok(())
+        },
+    },
+];
+
+fn get_schema_version(db: &PoloDb) -> Result<Option<u32>> {
+    let collection = db.collection::<SchemaVersion>(META_COLLECTION);
+    let schema = collection.find_one(doc! {"name": SCHEMA_DOC_NAME})?;
+    Ok(schema.map(|schema| schema.version))
+}
+
+fn set_schema_version(db: &PoloDb, version: u32) -> Result<()> {
+    let collection = db.collection::<SchemaVersion>(META_COLLECTION);
+    collection.delete_many(doc! {"name": SCHEMA_DOC_NAME})?;
+    collection.insert_one(SchemaVersion {
+        name: SCHEMA_DOC_NAME.to_string(),
+        version,
+    })?;
+    Ok(())
+}
+
+fn has_data(db: &PoloDb) -> Result<bool> {
+    Ok(db
+        .list_collection_names()?
+        .iter()
+        .any(|name| name != META_COLLECTION))
+}
+
+/// Migrations to run from `current`, refusing schemas newer than the binary.
+pub fn get_pending_migrations(
+    migrations: &'static [Migration],
+    current: u32,
+    latest: u32,
+) -> Result<Vec<&'static Migration>> {
+    if current > latest {
+        return Err(anyhow!(
+            "Database schema version {} is newer than the supported version {}, upgrade ruche",
+            current,
+            latest
+        ));
+    }
+    Ok(migrations
+        .iter()
+        .filter(|migration| migration.version > current && migration.version <= latest)
+        .collect())
+}
+
+pub fn get_backup_path(db_path: &Path, version: u32, timestamp: u64) -> PathBuf {
+    let name = db_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    db_path.with_file_name(format!("{}.v{}-{}.bak", name, version, timestamp))
+}
+
+pub fn copy_path(source: &Path, destination: &Path) -> Result<()> {
+    if !source.is_dir() {
+        std::fs::copy(source, destination)?;
+        return Ok(());
+    }
+    std::fs::create_dir_all(destination)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        copy_path(&entry.path(), &destination.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Schema version to back up before migrating, `None` for a new or up to date database.
+pub fn get_backup_version(db: &PoloDb) -> Result<Option<u32>> {
+    let version = get_schema_version(db)?;
+    let is_new = version.is_none() && !has_data(db)?;
+    let current = version.unwrap_or_default();
+    let pending = get_pending_migrations(MIGRATIONS, current, SCHEMA_VERSION)?;
+    Ok((!is_new && !pending.is_empty()).then_some(current))
+}
+
+/// Copies the database files, which must not be open: RocksDB keeps writing to them.
+pub fn back_up(db_path: &Path, version: u32) -> Result<()> {
+    let backup_path = get_backup_path(db_path, version, now_secs());
+    copy_path(db_path, &backup_path)
+        .map_err(|err| anyhow!("Failed to back up database before migrating: {}", err))?;
+    tracing::info!("Backed up database to {}", backup_path.display());
+    Ok(())
+}
+
+/// Brings the database to `SCHEMA_VERSION`, once backed up by `back_up` if needed.
+pub fn migrate(db: &PoloDb) -> Result<()> {
+    let current = get_schema_version(db)?.unwrap_or_default();
+    for migration in get_pending_migrations(MIGRATIONS, current, SCHEMA_VERSION)? {
+        tracing::info!(
+            "Migrating database to schema version {}: {}",
+            migration.version,
+            migration.description
+        );
+        (migration.run)(db).map_err(|err| {
+            anyhow!(
+                "Migration to schema version {} failed: {}",
+                migration.version,
+                err
+            )
+        })?;
+        set_schema_version(db, migration.version)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: 1,
+            description: "first",
+            run: |_| Ok(()),
+        },
+        Migration {
+            version: 2,
+            description: "second",
+            run: |_| Ok(()),
+        },
+        Migration {
+            version: 3,
+            description: "third",
+            run: |_| Ok(()),
+        },
+    ];
+
+    #[test]
+    fn should_select_pending_migrations_in_order() {
+        let versions = |current, latest| {
+            get_pending_migrations(TEST_MIGRATIONS, current, latest)
+                .unwrap()
+                .iter()
+                .map(|migration| migration.version)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(versions(0, 3), vec![1, 2, 3]);
+        assert_eq!(versions(1, 3), vec![2, 3]);
+        assert_eq!(versions(1, 2), vec![2]);
+        assert!(versions(3, 3).is_empty());
+    }
+
+    #[test]
+    fn should_refuse_newer_schema() {
+        assert!(get_pending_migrations(TEST_MIGRATIONS, 4, 3).is_err());
+    }
+
+    #[test]
+    fn should_keep_migrations_ordered_up_to_schema_version() {
+        let versions = MIGRATIONS
+            .iter()
+            .map(|migration| migration.version)
+            .collect::<Vec<_>>();
+
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(versions.last(), Some(&SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn should_name_backup_after_version() {
+        assert_eq!(
+            get_backup_path(Path::new("ruche.db"), 1, 1700000000),
+            PathBuf::from("ruche.db.v1-1700000000.bak")
+        );
+    }
+
+    #[test]
+    fn should_back_up_and_stamp_unversioned_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("ruche.db");
+        let db = PoloDb::open_path(&db_path).unwrap();
+        db.collection::<SchemaVersion>("bees")
+            .insert_one(SchemaVersion {
+                name: "legacy".to_string(),
+                version: 0,
+            })
+            .unwrap();
+
+        let version = get_backup_version(&db).unwrap();
+        assert_eq!(version, Some(0));
+        drop(db);
+        back_up(&db_path, 0).unwrap();
+        let db = PoloDb::open_path(&db_path).unwrap();
+        migrate(&db).unwrap();
+
+        assert_eq!(get_backup_version(&db).unwrap(), None);
+        assert_eq!(get_schema_version(&db).unwrap(), Some(SCHEMA_VERSION));
+        let backups = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("ruche.db.v0-")
+            })
+            .count();
+        assert_eq!(backups, 1);
+    }
+
+    #[test]
+    fn should_reject_duplicate_bee_id_once_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("ruche.db");
+        let db = PoloDb::open_path(&db_path).unwrap();
+
+        assert_eq!(get_backup_version(&db).unwrap(), None);
+        migrate(&db).unwrap();
+
+        let bees = db.collection::<Document>("bees");
+        bees.insert_one(doc! {"id": 1}).unwrap();
+        assert!(bees.insert_one(doc! {"id": 1}).is_err());
+    }
+
+    #[test]
+    fn should_refuse_to_open_newer_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("ruche.db");
+        let db = PoloDb::open_path(&db_path).unwrap();
+        set_schema_version(&db, SCHEMA_VERSION + 1).unwrap();
+
+        assert!(get_backup_version(&db).is_err());
+        assert!(migrate(&db).is_err());
+    }
+}
@@ -1,2 +1,8 @@
 pub mod database;
 pub mod docker;
+pub mod jobs;
+pub mod metrics;
+pub mod migrations;
+pub mod reservations;
+pub mod server;
+pub mod sqlite;
@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Reserved ids, with the data dir created for them once placed.
+type Reserved = HashMap<u8, Option<PathBuf>>;
+
+/// Node ids handed out but not saved yet, so concurrent creations never share one,
+/// and the destinations of moves, so creations and moves never share the last slot
+/// of a volume.
+#[derive(Clone, Default)]
+pub struct BeeIdReservations {
+    allocation: Arc<tokio::sync::Mutex<()>>,
+    reserved: Arc<Mutex<Reserved>>,
+    moves: Arc<Mutex<HashMap<u8, PathBuf>>>,
+}
+
+/// Keeps its id reserved until dropped, once the node is saved or given up.
+#[derive(Debug)]
+pub struct BeeIdReservation {
+    pub id: u8,
+    reserved: Arc<Mutex<Reserved>>,
+}
+
+/// Keeps the destination of a node move counted until dropped, once the move is over.
+#[derive(Debug)]
+pub struct MoveReservation {
+    bee_id: u8,
+    moves: Arc<Mutex<HashMap<u8, PathBuf>>>,
+}
+
+/// The maps stay consistent whatever panicked while holding them, so poisoning is ignored.
+fn lock_reserved<T>(reserved: &Mutex<T>) -> MutexGuard<'_, T> {
+    reserved.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl BeeIdReservations {
+    /// Serializes allocations, the guard must be held while reading the saved nodes.
+    pub async fn lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.allocation.lock().await
+    }
+
+    pub fn get_reserved(&self) -> HashSet<u8> {
+        lock_reserved(&self.reserved).keys().copied().collect()
+    }
+
+    /// Data dirs of the reserved ids already placed in a volume, and destinations of moves.
+    pub fn get_placed(&self) -> Vec<(u8, PathBuf)> {
+        let mut placed = lock_reserved(&self.reserved)
+            .iter()
+            .filter_map(|(id, data_dir)| Some((*id, data_dir.to_owned()?)))
+            .collect::<Vec<_>>();
+        placed.extend(
+            lock_reserved(&self.moves)
+                .iter()
+                .map(|(id, destination)| (*id, destination.to_owned())),
+        );
+        placed
+    }
+
+    /// Records the destination of a move, under the allocation lock.
+    pub fn reserve_move(&self, bee_id: u8, destination: &Path) -> MoveReservation {
+        lock_reserved(&self.moves).insert(bee_id, destination.to_owned());
+        MoveReservation {
+            bee_id,
+            moves: self.moves.clone(),
+        }
+    }
+
+    pub fn reserve(&self, id: u8) -> BeeIdReservation {
+        lock_reserved(&self.reserved).insert(id, None);
+        BeeIdReservation {
+            id,
+            reserved: self.reserved.clone(),
+        }
+    }
+}
+
+impl BeeIdReservation {
+    /// Records the data dir created for the id, under the allocation lock.
+    pub fn place(&self, data_dir: &Path) {
+        lock_reserved(&self.reserved).insert(self.id, Some(data_dir.to_owned()));
+    }
+}
+
+impl Drop for BeeIdReservation {
+    fn drop(&mut self) {
+        lock_reserved(&self.reserved).remove(&self.id);
+    }
+}
+
+impl Drop for MoveReservation {
+    fn drop(&mut self) {
+        lock_reserved(&self.moves).remove(&self.bee_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_release_id_on_drop() {
+        let reservations = BeeIdReservations::default();
+
+        let reservation = reservations.reserve(4);
+        assert!(reservations.get_reserved().contains(&4));
+
+        drop(reservation);
+        assert!(reservations.get_reserved().is_empty());
+    }
+
+    #[test]
+    fn should_list_placed_ids_until_dropped() {
+        let reservations = BeeIdReservations::default();
+        let placed = reservations.reserve(4);
+        let _unplaced = reservations.reserve(5);
+
+        placed.place(Path::new("/mnt/disk1/node_04"));
+        assert_eq!(
+            reservations.get_placed(),
+            vec![(4, PathBuf::from("/mnt/disk1/node_04"))]
+        );
+
+        drop(placed);
+        assert!(reservations.get_placed().is_empty());
+
+        let moving = reservations.reserve_move(7, Path::new("/mnt/disk2/node_07"));
+        assert_eq!(
+            reservations.get_placed(),
+            vec![(7, PathBuf::from("/mnt/disk2/node_07"))]
+        );
+        assert!(!reservations.get_reserved().contains(&7));
+
+        drop(moving);
+        assert!(reservations.get_placed().is_empty());
+    }
+
+    #[test]
+    fn should_keep_reserving_after_a_panic() {
+        let reservations = BeeIdReservations::default();
+        let reserved = reservations.reserved.clone();
+        std::thread::spawn(move || {
+            let _guard = reserved.lock().unwrap();
+            panic!("poisoned");
+        })
+        .join()
+        .unwrap_err();
+
+        let reservation = reservations.reserve(5);
+        assert!(reservations.get_reserved().contains(&5));
+
+        drop(reservation);
+        assert!(reservations.get_reserved().is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+use anyhow::{anyhow, Context, Result};
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::crypto::ring::default_provider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::net::{TcpListener, UnixListener, UnixStream};
+use tokio::task::JoinHandle;
+
+use crate::models::config::{Config, Tls};
+
+/// Serves the app on the configured TCP address and, when set, on the Unix socket.
+pub async fn serve(config: &Config, app: Router) -> Result<()> {
+    let addr = SocketAddr::new(config.server.host, config.port);
+
+    let tcp = {
+        let app = app.clone();
+        let tls = config.server.tls.clone();
+        tokio::spawn(async move {
+            match tls {
+                Some(tls) => serve_tls(addr, &tls, app).await,
+                None => serve_tcp(addr, app).await,
+            }
+        })
+    };
+
+    let unix = config.server.unix_socket.clone().map(|path| {
+        let app = app.clone();
+        tokio::spawn(async move { serve_unix(&path, app).await })
+    });
+
+    // Fails as soon as either listener does, the other one would otherwise hide it.
+    match unix {
+        Some(unix) => tokio::try_join!(join_server(tcp), join_server(unix)).map(|_| ()),
+        None => join_server(tcp).await,
+    }
+}
+
+async fn join_server(server: JoinHandle<Result<()>>) -> Result<()> {
+    server.await?
+}
+
+async fn serve_tcp(addr: SocketAddr, app: Router) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!("Listening on http://{}", addr);
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .map_err(Into::into)
+}
+
+async fn serve_unix(path: &Path, app: Router) -> Result<()> {
+    remove_stale_socket(path).await?;
+    let listener = UnixListener::bind(path)?;
+    tracing::info!("Listening on unix:{}", path.display());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .map_err(Into::into)
+}
+
+/// Removes a socket left by a previous run, refusing to touch anything else.
+async fn remove_stale_socket(path: &Path) -> Result<()> {
+    let metadata = match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(anyhow!("'{}' exists and isn't a socket", path.display()));
+    }
+    if UnixStream::connect(path).await.is_ok() {
+        return Err(anyhow!("'{}' is in use by another server", path.display()));
+    }
+    tokio::fs::remove_file(path).await?;
+    Ok(())
+}
+
+async fn serve_tls(addr: SocketAddr, tls: &Tls, app: Router) -> Result<()> {
+    let rustls_config = RustlsConfig::from_config(Arc::new(load_tls_config(tls)?));
+    tokio::spawn(watch_tls_files(tls.clone(), rustls_config.clone()));
+
+    tracing::info!("Listening on https://{}", addr);
+    axum_server::bind_rustls(addr, rustls_config)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .map_err(Into::into)
+}
+
+fn load_tls_config(tls: &Tls) -> Result<ServerConfig> {
+    let certs = load_certs(&tls.cert_path)?;
+    let key = load_key(&tls.key_path)?;
+    let provider = Arc::new(default_provider());
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    let builder = match &tls.client_ca_path {
+        Some(client_ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(client_ca_path)? {
+                roots.add(cert)?;
+            }
+            let verifier =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider).build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder.with_single_cert(certs, key)?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Failed to read certificate '{}'", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice()).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(anyhow!("No certificate found in '{}'", path.display()));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Failed to read private key '{}'", path.display()))?;
+    rustls_pemfile::private_key(&mut pem.as_slice())?
+        .ok_or_else(|| anyhow!("No private key found in '{}'", path.display()))
+}
+
+fn tls_files(tls: &Tls) -> Vec<PathBuf> {
+    let mut files = vec![tls.cert_path.clone(), tls.key_path.clone()];
+    files.extend(tls.client_ca_path.clone());
+    files
+}
+
+fn modified_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| std::fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Polls the certificate files and swaps the TLS config in place when one of them changes.
+async fn watch_tls_files(tls: Tls, rustls_config: RustlsConfig) {
+    let files = tls_files(&tls);
+    let mut last_modified = modified_times(&files);
+    let mut interval = tokio::time::interval(Duration::from_secs(tls.reload_interval_secs.max(1)));
+
+    loop {
+        interval.tick().await;
+
+        let modified = modified_times(&files);
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        match load_tls_config(&tls) {
+            Ok(server_config) => {
+                rustls_config.reload_from_config(Arc::new(server_config));
+                tracing::info!("Reloaded TLS certificates");
+            }
+            Err(err) => tracing::error!("Failed to reload TLS certificates: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_only_remove_stale_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ruche.sock");
+
+        std::fs::write(&path, "not a socket").unwrap();
+        assert!(remove_stale_socket(&path).await.is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        let listener = UnixListener::bind(&path).unwrap();
+        assert!(remove_stale_socket(&path).await.is_err());
+        drop(listener);
+
+        remove_stale_socket(&path).await.unwrap();
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_a_listener_fails() {
+        let failing = tokio::spawn(async { Err(anyhow!("Address in use")) });
+        let running = tokio::spawn(std::future::pending::<Result<()>>());
+
+        let result = tokio::try_join!(join_server(failing), join_server(running));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_fail_loading_missing_certificate() {
+        let tls = Tls {
+            cert_path: PathBuf::from("/nonexistent/cert.pem"),
+            key_path: PathBuf::from("/nonexistent/key.pem"),
+            ..Default::default()
+        };
+
+        let result = load_tls_config(&tls);
+
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Failed to read certificate '/nonexistent/cert.pem'"
+        );
+    }
+
+    #[test]
+    fn should_fail_loading_file_without_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert_path = dir.path().join("cert.pem");
+        std::fs::write(&cert_path, "not a certificate").unwrap();
+
+        let result = load_certs(&cert_path);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_watch_client_ca_when_configured() {
+        let tls = Tls {
+            cert_path: PathBuf::from("cert.pem"),
+            key_path: PathBuf::from("key.pem"),
+            client_ca_path: Some(PathBuf::from("ca.pem")),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            tls_files(&tls),
+            vec![
+                PathBuf::from("cert.pem"),
+                PathBuf::from("key.pem"),
+                PathBuf::from("ca.pem")
+            ]
+        );
+    }
+}
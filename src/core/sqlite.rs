@@ -0,0 +1,990 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension, Row, ToSql, TransactionBehavior};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::database::{get_all_audit_entries, BeeDatabase};
+use super::migrations::get_backup_path;
+use crate::models::{
+    audit::{AuditEntry, AuditQuery},
+    bee::{BeeData, DeletionRequest, TagsChange},
+    event::{BeeEvent, BeeEventQuery},
+    export::RecordsImport,
+    token::ApiToken,
+};
+use crate::utils::time::now_secs;
+
+/// Schema statements, the one at index `n` moves `user_version` from `n` to `n + 1`.
+const SCHEMA_MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE bees (
+        id INTEGER PRIMARY KEY NOT NULL,
+        neighborhood TEXT NOT NULL,
+        full_node INTEGER NOT NULL,
+        swap_enable INTEGER NOT NULL,
+        reserve_doubling INTEGER NOT NULL,
+        data_dir TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE tokens (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        hash TEXT NOT NULL UNIQUE,
+        role TEXT NOT NULL,
+        bee_ids TEXT,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE audit (
+        timestamp INTEGER NOT NULL,
+        principal TEXT NOT NULL,
+        source_ip TEXT,
+        action TEXT NOT NULL,
+        bee_ids TEXT NOT NULL,
+        parameters TEXT NOT NULL,
+        status INTEGER NOT NULL,
+        success INTEGER NOT NULL
+    );
+    CREATE INDEX audit_timestamp ON audit (timestamp);
+    CREATE TABLE events (
+        bee_id INTEGER NOT NULL,
+        timestamp INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        exit_code INTEGER,
+        image TEXT,
+        previous_image TEXT,
+        health TEXT
+    );
+    CREATE INDEX events_bee_timestamp ON events (bee_id, timestamp);
+",
+    "
+    ALTER TABLE bees ADD COLUMN tags TEXT NOT NULL DEFAULT '{}';
+    ALTER TABLE bees ADD COLUMN description TEXT;
+",
+    "
+    CREATE TABLE deletion_requests (
+        bee_id INTEGER NOT NULL,
+        token_id TEXT NOT NULL,
+        hash TEXT NOT NULL UNIQUE,
+        expires_at INTEGER NOT NULL,
+        funds TEXT NOT NULL,
+        PRIMARY KEY (bee_id, token_id)
+    );
+",
+];
+
+/// SQLite backend, writes spanning several rows run in a transaction.
+#[derive(Clone)]
+pub struct SqliteDatabase {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteDatabase {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut conn = Connection::open(path)?;
+        migrate(&mut conn, path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Ok(SqliteDatabase {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Copies every record of `source`, once, into this still empty database.
+    pub async fn import_from(&self, source: &dyn BeeDatabase) -> Result<ImportCounts> {
+        let bees = source.get_bees().await?;
+        let tokens = source.get_tokens().await?;
+        let audit = get_all_audit_entries(source).await?;
+        let events = source.get_all_bee_events().await?;
+
+        self.run(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            for table in ["bees", "tokens", "audit", "events"] {
+                let count: u64 =
+                    tx.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                        row.get(0)
+                    })?;
+                if count > 0 {
+                    return Err(anyhow!("The SQLite database already holds {}", table));
+                }
+            }
+            for bee in &bees {
+                insert_bee(&tx, bee)?;
+            }
+            for token in &tokens {
+                insert_token(&tx, token)?;
+            }
+            for entry in &audit {
+                insert_audit_entry(&tx, entry)?;
+            }
+            for event in &events {
+                insert_bee_event(&tx, event)?;
+            }
+            tx.commit()?;
+
+            Ok(ImportCounts {
+                bees: bees.len(),
+                tokens: tokens.len(),
+                audit: audit.len(),
+                events: events.len(),
+            })
+        })
+        .await
+    }
+
+    /// Runs blocking rusqlite calls off the async workers, holding the connection.
+    async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let mut conn = self.conn.clone().lock_owned().await;
+        tokio::task::spawn_blocking(move || f(&mut conn)).await?
+    }
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct ImportCounts {
+    pub bees: usize,
+    pub tokens: usize,
+    pub audit: usize,
+    pub events: usize,
+}
+
+fn migrate(conn: &mut Connection, path: &Path) -> Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > SCHEMA_MIGRATIONS.len() {
+        return Err(anyhow!(
+            "SQLite schema version {} is newer than the supported version {}, upgrade ruche",
+            version,
+            SCHEMA_MIGRATIONS.len()
+        ));
+    }
+    if version == SCHEMA_MIGRATIONS.len() {
+        return Ok(());
+    }
+    if version > 0 {
+        // A file copy would miss the pages still in the WAL file.
+        let backup_path = get_backup_path(path, version as u32, now_secs());
+        conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy()])
+            .map_err(|err| anyhow!("Failed to back up database before migrating: {}", err))?;
+        tracing::info!("Backed up database to {}", backup_path.display());
+    }
+
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    for (index, statements) in SCHEMA_MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(statements)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+/// Reads a JSON column back, wrapping parse errors so rusqlite reports the column.
+fn from_json<T: serde::de::DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    serde_json::from_str(&value).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, err.into())
+    })
+}
+
+/// Enums are stored as their bare serde name, e.g. `admin` rather than `"admin"`.
+fn to_name<T: serde::Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_value(value)?
+        .as_str()
+        .unwrap_or_default()
+        .to_owned())
+}
+
+fn from_name<T: serde::de::DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    serde_json::from_value(serde_json::Value::String(value)).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, err.into())
+    })
+}
+
+const BEE_COLUMNS: &str =
+    "id, neighborhood, full_node, swap_enable, reserve_doubling, data_dir, created_at, tags, description";
+
+fn insert_bee(conn: &Connection, bee: &BeeData) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO bees ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            BEE_COLUMNS
+        ),
+        params![
+            bee.id,
+            bee.neighborhood,
+            bee.full_node,
+            bee.swap_enable,
+            bee.reserve_doubling,
+            bee.data_dir.to_string_lossy(),
+            bee.created_at,
+            to_json(&bee.tags)?,
+            bee.description,
+        ],
+    )?;
+    Ok(())
+}
+
+fn update_bee_row(conn: &Connection, bee: &BeeData) -> Result<()> {
+    conn.execute(
+        "UPDATE bees SET neighborhood = ?2, full_node = ?3, swap_enable = ?4, reserve_doubling = ?5, data_dir = ?6, created_at = ?7, tags = ?8, description = ?9 WHERE id = ?1",
+        params![
+            bee.id,
+            bee.neighborhood,
+            bee.full_node,
+            bee.swap_enable,
+            bee.reserve_doubling,
+            bee.data_dir.to_string_lossy(),
+            bee.created_at,
+            to_json(&bee.tags)?,
+            bee.description,
+        ],
+    )?;
+    Ok(())
+}
+
+fn read_bee(row: &Row) -> rusqlite::Result<BeeData> {
+    Ok(BeeData {
+        id: row.get(0)?,
+        neighborhood: row.get(1)?,
+        full_node: row.get(2)?,
+        swap_enable: row.get(3)?,
+        reserve_doubling: row.get(4)?,
+        data_dir: PathBuf::from(row.get::<_, String>(5)?),
+        created_at: row.get(6)?,
+        tags: from_json(row, 7)?,
+        description: row.get(8)?,
+    })
+}
+
+fn insert_token(conn: &Connection, token: &ApiToken) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tokens (id, name, hash, role, bee_ids, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            token.id,
+            token.name,
+            token.hash,
+            to_name(&token.role)?,
+            token.bee_ids.as_ref().map(to_json).transpose()?,
+            token.created_at,
+        ],
+    )?;
+    Ok(())
+}
+
+fn read_token(row: &Row) -> rusqlite::Result<ApiToken> {
+    let bee_ids: Option<String> = row.get(4)?;
+    Ok(ApiToken {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        hash: row.get(2)?,
+        role: from_name(row, 3)?,
+        bee_ids: match bee_ids {
+            Some(_) => from_json(row, 4)?,
+            None => None,
+        },
+        created_at: row.get(5)?,
+    })
+}
+
+fn insert_audit_entry(conn: &Connection, entry: &AuditEntry) -> Result<()> {
+    conn.execute(
+        "INSERT INTO audit (timestamp, principal, source_ip, action, bee_ids, parameters, status, success) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            entry.timestamp,
+            entry.principal,
+            entry.source_ip,
+            entry.action,
+            to_json(&entry.bee_ids)?,
+            to_json(&entry.parameters)?,
+            entry.status,
+            entry.success,
+        ],
+    )?;
+    Ok(())
+}
+
+fn read_audit_entry(row: &Row) -> rusqlite::Result<AuditEntry> {
+    Ok(AuditEntry {
+        timestamp: row.get(0)?,
+        principal: row.get(1)?,
+        source_ip: row.get(2)?,
+        action: row.get(3)?,
+        bee_ids: from_json(row, 4)?,
+        parameters: from_json(row, 5)?,
+        status: row.get(6)?,
+        success: row.get(7)?,
+    })
+}
+
+fn insert_bee_event(conn: &Connection, event: &BeeEvent) -> Result<()> {
+    conn.execute(
+        "INSERT INTO events (bee_id, timestamp, kind, exit_code, image, previous_image, health) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            event.bee_id,
+            event.timestamp,
+            to_name(&event.kind)?,
+            event.exit_code,
+            event.image,
+            event.previous_image,
+            event.health,
+        ],
+    )?;
+    Ok(())
+}
+
+fn read_bee_event(row: &Row) -> rusqlite::Result<BeeEvent> {
+    Ok(BeeEvent {
+        bee_id: row.get(0)?,
+        timestamp: row.get(1)?,
+        kind: from_name(row, 2)?,
+        exit_code: row.get(3)?,
+        image: row.get(4)?,
+        previous_image: row.get(5)?,
+        health: row.get(6)?,
+    })
+}
+
+/// Request matching `condition`, a filter on the deletion_requests columns.
+fn find_deletion_request(
+    conn: &Connection,
+    condition: &str,
+    params: &[&dyn ToSql],
+) -> Result<Option<DeletionRequest>> {
+    let query = format!(
+        "SELECT bee_id, token_id, hash, expires_at, funds FROM deletion_requests WHERE {}",
+        condition
+    );
+    let request = conn
+        .query_row(&query, params, |row| {
+            Ok(DeletionRequest {
+                bee_id: row.get(0)?,
+                token_id: row.get(1)?,
+                hash: row.get(2)?,
+                expires_at: row.get(3)?,
+                funds: from_json(row, 4)?,
+            })
+        })
+        .optional()?;
+    Ok(request)
+}
+
+#[async_trait]
+impl BeeDatabase for SqliteDatabase {
+    async fn add_bee(&self, bee: BeeData) -> Result<()> {
+        self.run(move |conn| insert_bee(conn, &bee)).await
+    }
+
+    async fn add_bees(&self, bees: Vec<BeeData>) -> Result<()> {
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            for bee in &bees {
+                insert_bee(&tx, bee)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn add_bee_within_capacity(&self, bee: BeeData, capacity: u64) -> Result<bool> {
+        self.run(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let count: u64 = tx.query_row("SELECT COUNT(*) FROM bees", [], |row| row.get(0))?;
+            if count >= capacity {
+                return Ok(false);
+            }
+            insert_bee(&tx, &bee)?;
+            tx.commit()?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn count_bees(&self) -> Result<u64> {
+        self.run(|conn| Ok(conn.query_row("SELECT COUNT(*) FROM bees", [], |row| row.get(0))?))
+            .await
+    }
+
+    async fn get_bee(&self, bee_id: u8) -> Result<Option<BeeData>> {
+        self.run(move |conn| {
+            let bee = conn
+                .query_row(
+                    &format!("SELECT {} FROM bees WHERE id = ?1", BEE_COLUMNS),
+                    [bee_id],
+                    read_bee,
+                )
+                .optional()?;
+            Ok(bee)
+        })
+        .await
+    }
+
+    async fn get_bees(&self) -> Result<Vec<BeeData>> {
+        self.run(|conn| {
+            let mut statement =
+                conn.prepare(&format!("SELECT {} FROM bees ORDER BY id", BEE_COLUMNS))?;
+            let bees = statement
+                .query_map([], read_bee)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(bees)
+        })
+        .await
+    }
+
+    async fn update_bee(&self, bee: BeeData) -> Result<()> {
+        self.run(move |conn| update_bee_row(conn, &bee)).await
+    }
+
+    async fn delete_bee(&self, bee_id: u8) -> Result<()> {
+        self.run(move |conn| {
+            conn.execute("DELETE FROM bees WHERE id = ?1", [bee_id])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn add_token(&self, token: ApiToken) -> Result<()> {
+        self.run(move |conn| insert_token(conn, &token)).await
+    }
+
+    async fn get_token(&self, token_id: &str) -> Result<Option<ApiToken>> {
+        let token_id = token_id.to_owned();
+        self.run(move |conn| {
+            let token = conn
+                .query_row(
+                    "SELECT id, name, hash, role, bee_ids, created_at FROM tokens WHERE id = ?1",
+                    [token_id],
+                    read_token,
+                )
+                .optional()?;
+            Ok(token)
+        })
+        .await
+    }
+
+    async fn get_tokens(&self) -> Result<Vec<ApiToken>> {
+        self.run(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT id, name, hash, role, bee_ids, created_at FROM tokens ORDER BY created_at",
+            )?;
+            let tokens = statement
+                .query_map([], read_token)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(tokens)
+        })
+        .await
+    }
+
+    async fn delete_token(&self, token_id: &str) -> Result<()> {
+        let token_id = token_id.to_owned();
+        self.run(move |conn| {
+            conn.execute("DELETE FROM tokens WHERE id = ?1", [token_id])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn add_audit_entry(&self, entry: AuditEntry) -> Result<()> {
+        self.run(move |conn| insert_audit_entry(conn, &entry)).await
+    }
+
+    async fn get_audit_entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let query = query.clone();
+        self.run(move |conn| {
+            let mut statement = conn.prepare(
+                "SELECT timestamp, principal, source_ip, action, bee_ids, parameters, status, success
+                 FROM audit WHERE (?1 IS NULL OR action = ?1) ORDER BY timestamp DESC, rowid DESC",
+            )?;
+            let mut entries = Vec::new();
+            for entry in statement.query_map([&query.action], read_audit_entry)? {
+                let entry = entry?;
+                if query.matches(&entry) {
+                    entries.push(entry);
+                }
+                if entries.len() >= query.limit() {
+                    break;
+                }
+            }
+            Ok(entries)
+        })
+        .await
+    }
+
+    async fn add_bee_event(&self, event: BeeEvent) -> Result<()> {
+        self.run(move |conn| insert_bee_event(conn, &event)).await
+    }
+
+    async fn get_bee_events(&self, bee_id: u8, query: &BeeEventQuery) -> Result<Vec<BeeEvent>> {
+        let query = query.clone();
+        self.run(move |conn| {
+            let mut statement = conn.prepare(
+                "SELECT bee_id, timestamp, kind, exit_code, image, previous_image, health
+                 FROM events WHERE bee_id = ?1 ORDER BY timestamp DESC, rowid DESC",
+            )?;
+            let mut events = Vec::new();
+            for event in statement.query_map([bee_id], read_bee_event)? {
+                let event = event?;
+                if query.matches(&event) {
+                    events.push(event);
+                }
+                if events.len() >= query.limit() {
+                    break;
+                }
+            }
+            Ok(events)
+        })
+        .await
+    }
+
+    async fn get_all_bee_events(&self) -> Result<Vec<BeeEvent>> {
+        self.run(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT bee_id, timestamp, kind, exit_code, image, previous_image, health
+                 FROM events ORDER BY timestamp, rowid",
+            )?;
+            let events = statement
+                .query_map([], read_bee_event)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(events)
+        })
+        .await
+    }
+
+    async fn put_deletion_request(&self, request: DeletionRequest) -> Result<()> {
+        let funds = serde_json::to_string(&request.funds)?;
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "DELETE FROM deletion_requests WHERE expires_at < ?1",
+                [now_secs()],
+            )?;
+            tx.execute(
+                "INSERT OR REPLACE INTO deletion_requests (bee_id, token_id, hash, expires_at, funds)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    request.bee_id,
+                    request.token_id,
+                    request.hash,
+                    request.expires_at,
+                    funds,
+                ],
+            )?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_deletion_request(
+        &self,
+        bee_id: u8,
+        hash: &str,
+    ) -> Result<Option<DeletionRequest>> {
+        let hash = hash.to_owned();
+        self.run(move |conn| {
+            find_deletion_request(conn, "bee_id = ?1 AND hash = ?2", params![bee_id, hash])
+        })
+        .await
+    }
+
+    async fn take_deletion_request(
+        &self,
+        bee_id: u8,
+        token_id: &str,
+        hash: &str,
+    ) -> Result<Option<DeletionRequest>> {
+        let (token_id, hash) = (token_id.to_owned(), hash.to_owned());
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            let request = find_deletion_request(
+                &tx,
+                "bee_id = ?1 AND token_id = ?2 AND hash = ?3 AND expires_at >= ?4",
+                params![bee_id, token_id, hash, now_secs()],
+            )?;
+            if request.is_some() {
+                tx.execute(
+                    "DELETE FROM deletion_requests WHERE bee_id = ?1 AND hash = ?2",
+                    params![bee_id, hash],
+                )?;
+            }
+            tx.commit()?;
+            Ok(request)
+        })
+        .await
+    }
+
+    async fn delete_deletion_requests(&self, bee_id: u8) -> Result<()> {
+        self.run(move |conn| {
+            conn.execute("DELETE FROM deletion_requests WHERE bee_id = ?1", [bee_id])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn update_bee_tags(&self, bee_id: u8, change: TagsChange) -> Result<Option<BeeData>> {
+        self.run(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let bee = tx
+                .query_row(
+                    &format!("SELECT {} FROM bees WHERE id = ?1", BEE_COLUMNS),
+                    [bee_id],
+                    read_bee,
+                )
+                .optional()?;
+            let Some(mut bee) = bee else {
+                return Ok(None);
+            };
+            change.apply(&mut bee);
+            tx.execute(
+                "UPDATE bees SET tags = ?2, description = ?3 WHERE id = ?1",
+                params![bee_id, to_json(&bee.tags)?, bee.description],
+            )?;
+            tx.commit()?;
+            Ok(Some(bee))
+        })
+        .await
+    }
+
+    async fn import_records(&self, import: RecordsImport) -> Result<()> {
+        self.run(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            for bee in &import.added_bees {
+                insert_bee(&tx, bee)?;
+            }
+            for bee in &import.overwritten_bees {
+                update_bee_row(&tx, bee)?;
+            }
+            for token in &import.tokens {
+                tx.execute("DELETE FROM tokens WHERE id = ?1", [&token.id])?;
+                insert_token(&tx, token)?;
+            }
+            for entry in &import.audit {
+                insert_audit_entry(&tx, entry)?;
+            }
+            for event in &import.events {
+                insert_bee_event(&tx, event)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::database::MockDbService;
+    use crate::models::{bee_api::BeeFunds, event::BeeEventKind, token::Role};
+    use std::collections::BTreeMap;
+
+    fn open() -> (tempfile::TempDir, SqliteDatabase) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = SqliteDatabase::open(&dir.path().join("ruche.sqlite")).unwrap();
+        (dir, db)
+    }
+
+    #[tokio::test]
+    async fn should_reject_duplicate_bee_id() {
+        let (_dir, db) = open();
+        let bee = BeeData {
+            id: 1,
+            ..Default::default()
+        };
+
+        db.add_bee(bee.clone()).await.unwrap();
+
+        assert!(db.add_bee(bee.clone()).await.is_err());
+        assert!(db.add_bees(vec![bee.clone()]).await.is_err());
+        assert_eq!(db.count_bees().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_roll_back_partial_batch() {
+        let (_dir, db) = open();
+        let bees = [1, 2, 1]
+            .into_iter()
+            .map(|id| BeeData {
+                id,
+                ..Default::default()
+            })
+            .collect();
+
+        assert!(db.add_bees(bees).await.is_err());
+        assert_eq!(db.count_bees().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn should_only_insert_within_capacity() {
+        let (_dir, db) = open();
+        let bee = |id| BeeData {
+            id,
+            ..Default::default()
+        };
+
+        assert!(db.add_bee_within_capacity(bee(1), 2).await.unwrap());
+        assert!(db.add_bee_within_capacity(bee(2), 2).await.unwrap());
+        assert!(!db.add_bee_within_capacity(bee(3), 2).await.unwrap());
+        assert_eq!(db.count_bees().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_list_events_of_every_node_oldest_first() {
+        let (_dir, db) = open();
+        for (bee_id, timestamp) in [(2, 20), (1, 30), (200, 10)] {
+            db.add_bee_event(BeeEvent {
+                bee_id,
+                timestamp,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let events = db.get_all_bee_events().await.unwrap();
+
+        let ids = events.iter().map(|event| event.bee_id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![200, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn should_round_trip_records() {
+        let (_dir, db) = open();
+        let bee = BeeData {
+            id: 3,
+            neighborhood: "0101".to_owned(),
+            full_node: true,
+            data_dir: PathBuf::from("/media/swarm_data_01/node_03"),
+            created_at: 42,
+            tags: BTreeMap::from([("owner".to_owned(), "teamA".to_owned())]),
+            ..Default::default()
+        };
+        db.add_bee(bee).await.unwrap();
+        db.add_token(ApiToken {
+            id: "t1".to_owned(),
+            role: Role::Operator,
+            bee_ids: Some(vec![3]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        db.add_bee_event(BeeEvent {
+            bee_id: 3,
+            timestamp: 10,
+            kind: BeeEventKind::OomKilled,
+            exit_code: Some(137),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let bee = db.get_bee(3).await.unwrap().unwrap();
+        assert_eq!(bee.neighborhood, "0101");
+        assert!(bee.full_node);
+        assert_eq!(bee.created_at, 42);
+        assert_eq!(bee.tags["owner"], "teamA");
+        let token = db.get_token("t1").await.unwrap().unwrap();
+        assert_eq!(token.role, Role::Operator);
+        assert_eq!(token.bee_ids, Some(vec![3]));
+        let events = db
+            .get_bee_events(3, &BeeEventQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(events[0].kind, BeeEventKind::OomKilled);
+        assert_eq!(events[0].exit_code, Some(137));
+    }
+
+    #[tokio::test]
+    async fn should_import_once_from_another_backend() {
+        let (_dir, db) = open();
+        let source = MockDbService::default();
+        source
+            .add_bees(vec![
+                BeeData {
+                    id: 1,
+                    ..Default::default()
+                },
+                BeeData {
+                    id: 2,
+                    ..Default::default()
+                },
+            ])
+            .await
+            .unwrap();
+        source
+            .add_audit_entry(AuditEntry {
+                action: "bee.create".to_owned(),
+                bee_ids: vec![1],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let counts = db.import_from(&source).await.unwrap();
+
+        assert_eq!(
+            counts,
+            ImportCounts {
+                bees: 2,
+                tokens: 0,
+                audit: 1,
+                events: 0,
+            }
+        );
+        assert_eq!(db.get_bees().await.unwrap().len(), 2);
+        assert!(db.import_from(&source).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_roll_back_failed_import() {
+        let (_dir, db) = open();
+        let token = ApiToken {
+            id: "t1".to_owned(),
+            hash: "h1".to_owned(),
+            ..Default::default()
+        };
+        db.add_token(token.clone()).await.unwrap();
+
+        let result = db
+            .import_records(RecordsImport {
+                added_bees: vec![BeeData {
+                    id: 1,
+                    ..Default::default()
+                }],
+                tokens: vec![ApiToken {
+                    id: "t2".to_owned(),
+                    ..token
+                }],
+                ..Default::default()
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(db.count_bees().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn should_take_deletion_request_once() {
+        let (_dir, db) = open();
+        let request = DeletionRequest {
+            bee_id: 3,
+            token_id: "t1".to_owned(),
+            hash: "h1".to_owned(),
+            expires_at: now_secs() + 60,
+            funds: BeeFunds {
+                bzz_balance: Some("0".to_owned()),
+                ..Default::default()
+            },
+        };
+        db.put_deletion_request(request.clone()).await.unwrap();
+        db.put_deletion_request(DeletionRequest {
+            hash: "h2".to_owned(),
+            ..request.clone()
+        })
+        .await
+        .unwrap();
+
+        assert!(db
+            .take_deletion_request(3, "t1", "h1")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .take_deletion_request(3, "t2", "h2")
+            .await
+            .unwrap()
+            .is_none());
+        let found = db.get_deletion_request(3, "h2").await.unwrap().unwrap();
+        assert_eq!(found.funds, request.funds);
+        let taken = db
+            .take_deletion_request(3, "t1", "h2")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(taken.token_id, "t1");
+        assert_eq!(taken.funds, request.funds);
+        assert!(db
+            .take_deletion_request(3, "t1", "h2")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn should_purge_expired_deletion_requests() {
+        let (_dir, db) = open();
+        let expired = DeletionRequest {
+            bee_id: 3,
+            token_id: "t1".to_owned(),
+            hash: "h1".to_owned(),
+            expires_at: now_secs() - 1,
+            ..Default::default()
+        };
+        db.put_deletion_request(expired.clone()).await.unwrap();
+        db.put_deletion_request(DeletionRequest {
+            bee_id: 4,
+            hash: "h2".to_owned(),
+            expires_at: now_secs() + 60,
+            ..expired
+        })
+        .await
+        .unwrap();
+
+        assert!(db.get_deletion_request(3, "h1").await.unwrap().is_none());
+        assert!(db
+            .take_deletion_request(4, "t1", "h2")
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn should_back_up_committed_wal_pages_before_migrating() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ruche.sqlite");
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+        conn.execute_batch(SCHEMA_MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        conn.execute(
+            "INSERT INTO bees (id, neighborhood, full_node, swap_enable, reserve_doubling, data_dir, created_at)
+             VALUES (1, '', 0, 0, 0, '', 0)",
+            [],
+        )
+        .unwrap();
+
+        let mut migrated = Connection::open(&path).unwrap();
+        migrate(&mut migrated, &path).unwrap();
+
+        let backup = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .find(|entry| entry.file_name().to_string_lossy().ends_with(".bak"))
+            .unwrap();
+        let backup = Connection::open(backup.path()).unwrap();
+        let count: u64 = backup
+            .query_row("SELECT COUNT(*) FROM bees", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        drop(conn);
+    }
+
+    #[test]
+    fn should_refuse_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ruche.sqlite");
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_MIGRATIONS.len() + 1)
+            .unwrap();
+        drop(conn);
+
+        assert!(SqliteDatabase::open(&path).is_err());
+    }
+}
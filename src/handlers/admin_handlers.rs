@@ -0,0 +1,129 @@
+use crate::middlewares::audit::audit;
+use crate::middlewares::auth::{ensure_unscoped, require_role};
+use crate::models::audit::AuditTargets;
+use crate::models::export::{DatabaseExport, ExportImportQuery, ExportImportReport};
+use crate::models::http_error::HttpError;
+use crate::models::import::{RebuildQuery, RebuildReport};
+use crate::models::token::{Principal, Role};
+use crate::AppState;
+use axum::extract::{DefaultBodyLimit, Query, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+/// Dumps carry the whole audit log, well past the default body limit.
+const IMPORT_BODY_LIMIT: usize = 256 * 1024 * 1024;
+
+pub fn init_admin_handlers(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/db/rebuild",
+            post(rebuild_db)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "db.rebuild")),
+        )
+        .route(
+            "/export",
+            get(export_db)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "db.export")),
+        )
+        .route(
+            "/import",
+            post(import_db)
+                .layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT))
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "db.import")),
+        )
+        .with_state(app_state)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(rebuild_db, export_db, import_db))]
+pub struct AdminApi;
+
+/// Restores nodes missing from the database from the containers of this instance.
+#[utoipa::path(
+    post,
+    path = "/db/rebuild",
+    tag = "admin",
+    params(RebuildQuery),
+    responses(
+        (status = 200, description = "Rebuilt nodes", body = RebuildReport),
+        (status = 403, description = "Scoped token", body = HttpError),
+    )
+)]
+async fn rebuild_db(
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<RebuildQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<(Extension<AuditTargets>, Json<RebuildReport>), HttpError> {
+    ensure_unscoped(&principal)?;
+
+    let report = state.bee_service.rebuild_bees(query.dry_run).await?;
+    let targets = AuditTargets(report.restored.to_owned());
+    Ok((Extension(targets), Json(report)))
+}
+
+/// Dumps the database as versioned JSON, served as a download.
+#[utoipa::path(
+    get,
+    path = "/export",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Database dump", body = DatabaseExport),
+        (status = 403, description = "Scoped token", body = HttpError),
+    )
+)]
+async fn export_db(
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    ensure_unscoped(&principal)?;
+
+    let export = state.bee_service.export_database().await?;
+    let disposition = format!(
+        "attachment; filename=\"ruche-export-{}.json\"",
+        export.exported_at
+    );
+    Ok(([(header::CONTENT_DISPOSITION, disposition)], Json(export)))
+}
+
+/// Loads a dump produced by the export, see `ConflictMode` for existing records.
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "admin",
+    request_body = DatabaseExport,
+    params(ExportImportQuery),
+    responses(
+        (status = 200, description = "Loaded records", body = ExportImportReport),
+        (status = 400, description = "Invalid dump", body = HttpError),
+        (status = 409, description = "Conflict or max capacity reached", body = HttpError),
+        (status = 403, description = "Scoped token", body = HttpError),
+    )
+)]
+async fn import_db(
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<ExportImportQuery>,
+    State(state): State<Arc<AppState>>,
+    Json(export): Json<DatabaseExport>,
+) -> Result<(Extension<AuditTargets>, Json<ExportImportReport>), HttpError> {
+    ensure_unscoped(&principal)?;
+
+    let report = state
+        .bee_service
+        .import_database(export, query.conflict, query.dry_run)
+        .await?;
+    let targets = report
+        .bees
+        .added
+        .iter()
+        .chain(&report.bees.overwritten)
+        .copied()
+        .collect();
+    Ok((Extension(AuditTargets(targets)), Json(report)))
+}
@@ -0,0 +1,48 @@
+use crate::middlewares::auth::{ensure_unscoped, require_role};
+use crate::models::audit::{AuditEntry, AuditQuery};
+use crate::models::http_error::HttpError;
+use crate::models::token::{Principal, Role};
+use crate::AppState;
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+pub fn init_audit_handlers(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(get_audit_entries).route_layer(require_role(Role::Admin)),
+        )
+        .with_state(app_state)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_audit_entries))]
+pub struct AuditApi;
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "audit",
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "Entries, newest first", body = Vec<AuditEntry>),
+        (status = 403, description = "Scoped token", body = HttpError),
+    )
+)]
+async fn get_audit_entries(
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<AuditQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<AuditEntry>>, HttpError> {
+    ensure_unscoped(&principal)?;
+
+    state
+        .bee_service
+        .get_audit_entries(&query)
+        .await
+        .map(Json)
+        .map_err(Into::into)
+}
@@ -1,43 +1,227 @@
 use crate::bee_service::BeeService;
-use crate::models::bee::{BeeData, BeeInfo};
-use crate::models::http_error::HttpError;
+use crate::middlewares::audit::audit;
+use crate::middlewares::auth::{ensure_unscoped, require_role};
+use crate::models::audit::AuditTargets;
+use crate::models::backup::{BackupInfo, CreateBackupQuery, RestoreBeeRequest};
+use crate::models::bee::{
+    BeeData, BeeDeletion, BeeInfo, DeleteBeeQuery, DeleteTagsQuery, MoveBeeRequest,
+    PendingBeeDeletion, TaggedBee, UpdateTagsRequest,
+};
+use crate::models::disk::NodeDiskUsage;
+use crate::models::event::{BeeEvent, BeeEventQuery};
+use crate::models::http_error::{ApiError, HttpError};
+use crate::models::job::Job;
+use crate::models::stats::{ContainerStats, StatsQuery};
+use crate::models::token::{Principal, Role};
+use crate::utils::sse::to_json_sse;
 use crate::AppState;
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
-use axum::routing::{delete, get, post};
-use axum::{Json, Router};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
+use axum::{Extension, Json, Router};
+use futures_util::StreamExt;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+use utoipa::OpenApi;
+
+/// Carries the token returned by a deletion request.
+const DELETION_TOKEN_HEADER: &str = "x-deletion-token";
 
 pub fn init_bee_handlers(app_state: Arc<AppState>) -> Router {
     Router::new()
-        .route("/", post(create_bee))
-        .route("/{bee_id}", get(get_bee))
-        .route("/{bee_id}/start", get(start_bee))
-        .route("/{bee_id}/stop", get(stop_bee))
-        .route("/{bee_id}/recreate", get(recreate_bee))
-        .route("/{bee_id}/logs", get(get_bee_logs))
-        .route("/{bee_id}", delete(delete_bee))
-        .route("/{bee_id}/req", delete(request_bee_deletion))
+        .route(
+            "/",
+            post(create_bee)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "bee.create")),
+        )
+        .route(
+            "/restore",
+            post(restore_bee)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "bee.restore")),
+        )
+        .route(
+            "/{bee_id}",
+            get(get_bee).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/{bee_id}/start",
+            post(start_bee)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bee.start")),
+        )
+        .route(
+            "/{bee_id}/stop",
+            post(stop_bee)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bee.stop")),
+        )
+        .route(
+            "/{bee_id}/recreate",
+            post(recreate_bee)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bee.recreate")),
+        )
+        .route(
+            "/{bee_id}/move",
+            post(move_bee)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "bee.move")),
+        )
+        .route(
+            "/{bee_id}/backup",
+            post(backup_bee)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bee.backup")),
+        )
+        .route(
+            "/{bee_id}/backups",
+            get(get_bee_backups).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/{bee_id}/backups/{backup}",
+            get(download_bee_backup)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "bee.download_backup")),
+        )
+        .route(
+            "/{bee_id}/jobs",
+            get(get_bee_jobs).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/{bee_id}/jobs/{job_id}",
+            get(get_bee_job).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/{bee_id}/logs",
+            get(get_bee_logs).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/{bee_id}/stats",
+            get(get_bee_stats).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/{bee_id}/disk",
+            get(get_bee_disk_usage).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/{bee_id}/events",
+            get(get_bee_events).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/{bee_id}/tags",
+            put(update_bee_tags)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bee.tags.update")),
+        )
+        .route(
+            "/{bee_id}/tags",
+            delete(delete_bee_tags)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bee.tags.delete")),
+        )
+        .route(
+            "/{bee_id}",
+            delete(delete_bee)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "bee.delete")),
+        )
+        .route(
+            "/{bee_id}/req",
+            delete(request_bee_deletion)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "bee.request_deletion")),
+        )
         .with_state(app_state)
 }
 
-async fn create_bee(State(state): State<Arc<AppState>>) -> Result<Json<BeeInfo>, HttpError> {
-    if !state.bee_service.ensure_capacity().await? {
-        return Err(HttpError::new(
-            StatusCode::BAD_REQUEST,
-            &format!(
-                "Max capacity reached. {} bee nodes already registered.",
-                state.bee_service.count_bees().await?
-            ),
-        ));
-    }
+/// `GET` actions of the unversioned API, `POST` only on `/v1`.
+pub fn init_legacy_bee_handlers(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/{bee_id}/start",
+            get(start_bee)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bee.start")),
+        )
+        .route(
+            "/{bee_id}/stop",
+            get(stop_bee)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bee.stop")),
+        )
+        .route(
+            "/{bee_id}/recreate",
+            get(recreate_bee)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bee.recreate")),
+        )
+        .with_state(app_state)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_bee,
+    restore_bee,
+    get_bee,
+    start_bee,
+    stop_bee,
+    recreate_bee,
+    move_bee,
+    backup_bee,
+    get_bee_backups,
+    download_bee_backup,
+    get_bee_jobs,
+    get_bee_job,
+    get_bee_logs,
+    get_bee_stats,
+    get_bee_disk_usage,
+    get_bee_events,
+    update_bee_tags,
+    delete_bee_tags,
+    delete_bee,
+    request_bee_deletion,
+))]
+pub struct BeeApi;
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "bee",
+    responses(
+        (
+            status = 201,
+            description = "Created node",
+            body = BeeInfo,
+            headers(("location" = String, description = "URL of the node"))
+        ),
+        (status = 403, description = "Scoped token", body = HttpError),
+        (status = 409, description = "Max capacity reached", body = HttpError),
+    )
+)]
+async fn create_bee(
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+) -> Result<(StatusCode, Location, Extension<AuditTargets>, Json<BeeInfo>), HttpError> {
+    ensure_unscoped(&principal)?;
 
-    let new_bee_id = state.bee_service.get_new_bee_id().await?;
+    // Held until the node is saved, so concurrent creations get distinct ids.
+    let Some(reservation) = state.bee_service.reserve_bee_id().await? else {
+        return Err(ApiError::Capacity(format!(
+            "Max capacity reached. {} bee nodes already registered.",
+            state.bee_service.count_bees().await?
+        ))
+        .into());
+    };
+    let new_bee_id = reservation.id;
 
     let neighborhood = BeeService::get_neighborhood().await?;
 
-    let data_dir = state.bee_service.create_node_dir(new_bee_id).await?;
+    let data_dir = state.bee_service.create_node_dir(&reservation).await?;
 
     let bee_data = state
         .bee_service
@@ -49,9 +233,24 @@ async fn create_bee(State(state): State<Arc<AppState>>) -> Result<Json<BeeInfo>,
 
     state.bee_service.save_bee(&bee_data).await?;
 
-    Ok(Json(bee))
+    Ok((
+        StatusCode::CREATED,
+        location(format!("/v1/bee/{}", bee.id)),
+        Extension(AuditTargets(vec![bee.id])),
+        Json(bee),
+    ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{bee_id}",
+    tag = "bee",
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 200, description = "Node", body = BeeInfo),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
 async fn get_bee(
     Path(bee_id): Path<u8>,
     State(state): State<Arc<AppState>>,
@@ -68,40 +267,382 @@ async fn get_bee(
         .map_err(Into::into)
 }
 
+#[utoipa::path(
+    post,
+    path = "/{bee_id}/start",
+    tag = "bee",
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 204, description = "Container started"),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
 async fn start_bee(
     Path(bee_id): Path<u8>,
     State(state): State<Arc<AppState>>,
-) -> Result<(), HttpError> {
+) -> Result<StatusCode, HttpError> {
     let bee_data = find_bee_data(bee_id, &state).await?;
     state
         .bee_service
         .start_bee_container(&bee_data.name())
         .await?;
-    Ok(())
+    Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/{bee_id}/stop",
+    tag = "bee",
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 204, description = "Container stopped"),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
 async fn stop_bee(
     Path(bee_id): Path<u8>,
     State(state): State<Arc<AppState>>,
-) -> Result<(), HttpError> {
+) -> Result<StatusCode, HttpError> {
     let bee_data = find_bee_data(bee_id, &state).await?;
     state
         .bee_service
         .stop_bee_container(&bee_data.name())
         .await?;
-    Ok(())
+    Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/{bee_id}/recreate",
+    tag = "bee",
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 204, description = "Container recreated"),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
 async fn recreate_bee(
     Path(bee_id): Path<u8>,
     State(state): State<Arc<AppState>>,
-) -> Result<(), HttpError> {
+) -> Result<StatusCode, HttpError> {
     let bee_data = find_bee_data(bee_id, &state).await?;
     let bee = state.bee_service.bee_data_to_info(&bee_data)?;
     state.bee_service.recreate_bee_container(&bee).await?;
-    Ok(())
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Starts moving the node data dir to another volume, progress is polled through its job.
+#[utoipa::path(
+    post,
+    path = "/{bee_id}/move",
+    tag = "bee",
+    request_body = MoveBeeRequest,
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 202, description = "Move job", body = Job),
+        (status = 400, description = "Invalid volume", body = HttpError),
+        (status = 404, description = "Unknown node", body = HttpError),
+        (status = 409, description = "Job running, volume full or destination taken", body = HttpError),
+    )
+)]
+async fn move_bee(
+    Path(bee_id): Path<u8>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<MoveBeeRequest>,
+) -> Result<JobAccepted, HttpError> {
+    let bee_data = find_bee_data(bee_id, &state).await?;
+    let (destination, reservation) = state
+        .bee_service
+        .resolve_move_destination(&bee_data, &request.volume)
+        .await?;
+
+    match state
+        .bee_service
+        .start_bee_move(&bee_data, &destination, reservation)
+    {
+        Some(job) => Ok(job_accepted(job)),
+        None => Err(job_conflict(bee_id)),
+    }
+}
+
+/// Replaces the tags, applied to the container labels on its next recreate.
+///
+/// The running container keeps its previous labels, `recreate_required` tells when
+/// `POST /{bee_id}/recreate` is needed to apply the tags to them.
+#[utoipa::path(
+    put,
+    path = "/{bee_id}/tags",
+    tag = "bee",
+    request_body = UpdateTagsRequest,
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 200, description = "Updated node", body = TaggedBee),
+        (status = 400, description = "Invalid tags", body = HttpError),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
+async fn update_bee_tags(
+    Path(bee_id): Path<u8>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<UpdateTagsRequest>,
+) -> Result<Json<TaggedBee>, HttpError> {
+    BeeService::validate_tag_update(&request)?;
+    let bee_data = find_bee_data(bee_id, &state).await?;
+
+    let updated = state.bee_service.update_bee_tags(bee_id, request).await?;
+    tagged_bee(bee_id, &bee_data, updated, &state)
+}
+
+/// Removes the given tags, or all of them, applied to the container labels on its next recreate.
+///
+/// The running container keeps its previous labels, `recreate_required` tells when
+/// `POST /{bee_id}/recreate` is needed to remove the tags from them.
+#[utoipa::path(
+    delete,
+    path = "/{bee_id}/tags",
+    tag = "bee",
+    params(
+        ("bee_id" = u8, Path, description = "Node id"),
+        DeleteTagsQuery,
+    ),
+    responses(
+        (status = 200, description = "Updated node", body = TaggedBee),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
+async fn delete_bee_tags(
+    Path(bee_id): Path<u8>,
+    Query(query): Query<DeleteTagsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<TaggedBee>, HttpError> {
+    let bee_data = find_bee_data(bee_id, &state).await?;
+
+    let updated = state
+        .bee_service
+        .delete_bee_tags(bee_id, query.keys.as_deref())
+        .await?;
+    tagged_bee(bee_id, &bee_data, updated, &state)
+}
+
+/// Flags the labels as outdated when the tags differ from the ones read before the change.
+fn tagged_bee(
+    bee_id: u8,
+    previous: &BeeData,
+    updated: Option<BeeData>,
+    state: &Arc<AppState>,
+) -> Result<Json<TaggedBee>, HttpError> {
+    let Some(updated) = updated else {
+        return Err(
+            ApiError::NotFound(format!("Unable to find bee node with id {}.", bee_id)).into(),
+        );
+    };
+    Ok(Json(TaggedBee {
+        bee: state.bee_service.bee_data_to_info(&updated)?,
+        recreate_required: updated.tags != previous.tags,
+    }))
+}
+
+type Location = [(HeaderName, String); 1];
+
+fn location(url: String) -> Location {
+    [(header::LOCATION, url)]
+}
+
+type JobAccepted = (StatusCode, Location, Json<Job>);
+
+/// Points to the job, which clients poll until it finishes.
+fn job_accepted(job: Job) -> JobAccepted {
+    let url = format!("/v1/bee/{}/jobs/{}", job.bee_id, job.id);
+    (StatusCode::ACCEPTED, location(url), Json(job))
+}
+
+fn job_conflict(bee_id: u8) -> HttpError {
+    ApiError::Conflict(format!(
+        "A job is already running on bee node with id {}.",
+        bee_id
+    ))
+    .into()
+}
+
+#[utoipa::path(
+    post,
+    path = "/{bee_id}/backup",
+    tag = "bee",
+    params(
+        ("bee_id" = u8, Path, description = "Node id"),
+        CreateBackupQuery,
+    ),
+    responses(
+        (status = 202, description = "Backup job", body = Job),
+        (status = 400, description = "Backups aren't configured", body = HttpError),
+        (status = 404, description = "Unknown node", body = HttpError),
+        (status = 409, description = "A job is already running on the node", body = HttpError),
+    )
+)]
+async fn backup_bee(
+    Path(bee_id): Path<u8>,
+    Query(query): Query<CreateBackupQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JobAccepted, HttpError> {
+    let bee_data = find_bee_data(bee_id, &state).await?;
+    let job = state
+        .bee_service
+        .start_bee_backup(&bee_data, query.include_localstore)?;
+
+    match job {
+        Some(job) => Ok(job_accepted(job)),
+        None => Err(job_conflict(bee_id)),
+    }
+}
+
+/// Backups outlive their node, so they stay listed after it's deleted.
+#[utoipa::path(
+    get,
+    path = "/{bee_id}/backups",
+    tag = "bee",
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 200, description = "Backups of the node", body = Vec<BackupInfo>),
+        (status = 400, description = "Backups aren't configured", body = HttpError),
+    )
+)]
+async fn get_bee_backups(
+    Path(bee_id): Path<u8>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<BackupInfo>>, HttpError> {
+    state
+        .bee_service
+        .get_backups(bee_id)
+        .map(Json)
+        .map_err(HttpError::from)
+}
+
+/// Backups hold the node keys, hence admin only.
+#[utoipa::path(
+    get,
+    path = "/{bee_id}/backups/{backup}",
+    tag = "bee",
+    params(
+        ("bee_id" = u8, Path, description = "Node id"),
+        ("backup" = String, Path, description = "Backup name"),
+    ),
+    responses(
+        (status = 200, description = "Archive", content_type = "application/gzip", body = Vec<u8>),
+        (status = 400, description = "Invalid backup name", body = HttpError),
+    )
+)]
+async fn download_bee_backup(
+    Path((bee_id, backup)): Path<(u8, String)>,
+    State(state): State<Arc<AppState>>,
+    request: Request,
+) -> Result<Response, HttpError> {
+    let path = state.bee_service.get_backup_path(bee_id, &backup)?;
+
+    let mut response = ServeFile::new(path)
+        .oneshot(request)
+        .await
+        .map_err(anyhow::Error::from)?
+        .into_response();
+    if let Ok(disposition) = HeaderValue::from_str(&format!("attachment; filename=\"{}\"", backup))
+    {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_DISPOSITION, disposition);
+    }
+    Ok(response)
+}
+
+/// Creates a node from a backup in the backup dir, keeping its original identity.
+#[utoipa::path(
+    post,
+    path = "/restore",
+    tag = "bee",
+    request_body = RestoreBeeRequest,
+    responses(
+        (status = 202, description = "Restore job", body = Job),
+        (status = 400, description = "Invalid backup", body = HttpError),
+        (status = 403, description = "Scoped token", body = HttpError),
+        (status = 404, description = "Unknown backup", body = HttpError),
+        (status = 409, description = "Job running, identity in use or max capacity", body = HttpError),
+    )
+)]
+async fn restore_bee(
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RestoreBeeRequest>,
+) -> Result<(Extension<AuditTargets>, JobAccepted), HttpError> {
+    ensure_unscoped(&principal)?;
+
+    let (path, bee_data, reservation) = state
+        .bee_service
+        .prepare_bee_restore(&request.backup)
+        .await?;
+
+    match state
+        .bee_service
+        .start_bee_restore(&path, &bee_data, reservation)
+    {
+        Some(job) => Ok((
+            Extension(AuditTargets(vec![bee_data.id])),
+            job_accepted(job),
+        )),
+        None => Err(job_conflict(bee_data.id)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/{bee_id}/jobs",
+    tag = "bee",
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 200, description = "Jobs of the node", body = Vec<Job>),
+    )
+)]
+async fn get_bee_jobs(
+    Path(bee_id): Path<u8>,
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<Job>> {
+    Json(state.bee_service.get_bee_jobs(bee_id))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{bee_id}/jobs/{job_id}",
+    tag = "bee",
+    params(
+        ("bee_id" = u8, Path, description = "Node id"),
+        ("job_id" = String, Path, description = "Job id"),
+    ),
+    responses(
+        (status = 200, description = "Job", body = Job),
+        (status = 404, description = "Unknown job", body = HttpError),
+    )
+)]
+async fn get_bee_job(
+    Path((bee_id, job_id)): Path<(u8, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Job>, HttpError> {
+    match state.bee_service.get_job(&job_id) {
+        Some(job) if job.bee_id == bee_id => Ok(Json(job)),
+        _ => Err(ApiError::NotFound(format!(
+            "Unable to find job {} of bee node with id {}.",
+            job_id, bee_id
+        ))
+        .into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/{bee_id}/logs",
+    tag = "bee",
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 200, description = "Container log lines", body = Vec<String>),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
 async fn get_bee_logs(
     Path(bee_id): Path<u8>,
     State(state): State<Arc<AppState>>,
@@ -115,58 +656,167 @@ async fn get_bee_logs(
         .map_err(Into::into)
 }
 
-async fn request_bee_deletion(
+#[utoipa::path(
+    get,
+    path = "/{bee_id}/stats",
+    tag = "bee",
+    params(
+        ("bee_id" = u8, Path, description = "Node id"),
+        StatsQuery,
+    ),
+    responses(
+        (status = 200, description = "Stats, or server-sent events", body = ContainerStats),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
+async fn get_bee_stats(
     Path(bee_id): Path<u8>,
+    Query(query): Query<StatsQuery>,
     State(state): State<Arc<AppState>>,
-) -> Result<(), HttpError> {
-    find_bee_data(bee_id, &state).await?;
+) -> Result<Response, HttpError> {
+    let bee = find_bee_data(bee_id, &state).await?;
+
+    if query.stream {
+        let stats = state
+            .bee_service
+            .stream_bees_stats(vec![bee.name()], Duration::from_secs(query.interval()))
+            .filter_map(|stats| async move { stats.into_iter().next() })
+            .boxed();
+        return Ok(to_json_sse(stats).into_response());
+    }
 
-    let mut last_bee_deletion_req = state.last_bee_deletion_req.lock().await;
-    last_bee_deletion_req.insert(bee_id, SystemTime::now());
-    Ok(())
+    let stats = state.bee_service.get_bee_stats(&bee.name()).await?;
+    Ok(Json(stats).into_response())
 }
 
-async fn delete_bee(
+#[utoipa::path(
+    get,
+    path = "/{bee_id}/disk",
+    tag = "bee",
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 200, description = "Data dir usage", body = NodeDiskUsage),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
+async fn get_bee_disk_usage(
     Path(bee_id): Path<u8>,
     State(state): State<Arc<AppState>>,
-) -> Result<(), HttpError> {
+) -> Result<Json<NodeDiskUsage>, HttpError> {
     let bee = find_bee_data(bee_id, &state).await?;
+    state
+        .bee_service
+        .get_node_disk_usage(&bee)
+        .await
+        .map(Json)
+        .map_err(Into::into)
+}
 
-    let mut last_bee_deletion_req = state.last_bee_deletion_req.lock().await;
-
-    let has_made_request = match last_bee_deletion_req.get(&bee_id) {
-        Some(last_deletion_req) => match last_deletion_req.elapsed() {
-            Ok(duration) => duration < Duration::from_secs(30),
-            Err(_) => false,
-        },
-        None => false,
-    };
+/// Events are kept after a node is deleted, so its history stays readable.
+#[utoipa::path(
+    get,
+    path = "/{bee_id}/events",
+    tag = "bee",
+    params(
+        ("bee_id" = u8, Path, description = "Node id"),
+        BeeEventQuery,
+    ),
+    responses(
+        (status = 200, description = "Lifecycle events, newest first", body = Vec<BeeEvent>),
+    )
+)]
+async fn get_bee_events(
+    Path(bee_id): Path<u8>,
+    Query(query): Query<BeeEventQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<BeeEvent>>, HttpError> {
+    state
+        .bee_service
+        .get_bee_events(bee_id, &query)
+        .await
+        .map(Json)
+        .map_err(Into::into)
+}
 
-    if !has_made_request {
-        return Err(HttpError::new(
-            StatusCode::BAD_REQUEST,
-            &format!(
-                "Unable to confirm deletion of bee node with id {}. No request made in last 30sec.",
-                bee_id
-            ),
-        ));
-    }
+/// The token is bound to the node and the API token, and confirms a single deletion.
+#[utoipa::path(
+    delete,
+    path = "/{bee_id}/req",
+    tag = "bee",
+    params(("bee_id" = u8, Path, description = "Node id")),
+    responses(
+        (status = 200, description = "Token and what it would delete", body = PendingBeeDeletion),
+        (status = 404, description = "Unknown node", body = HttpError),
+    )
+)]
+async fn request_bee_deletion(
+    Path(bee_id): Path<u8>,
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PendingBeeDeletion>, HttpError> {
+    let bee = find_bee_data(bee_id, &state).await?;
 
-    last_bee_deletion_req.remove(&bee_id);
+    state
+        .bee_service
+        .request_bee_deletion(&bee, &principal)
+        .await
+        .map(Json)
+        .map_err(Into::into)
+}
 
-    state.bee_service.remove_bee_container(&bee.name()).await?;
+#[utoipa::path(
+    delete,
+    path = "/{bee_id}",
+    tag = "bee",
+    params(
+        ("bee_id" = u8, Path, description = "Node id"),
+        ("x-deletion-token" = Option<String>, Header, description = "Token of the deletion request"),
+        DeleteBeeQuery,
+    ),
+    responses(
+        (status = 200, description = "What is, or would be, deleted, the data dir is removed in the background", body = BeeDeletion),
+        (status = 400, description = "Missing deletion token", body = HttpError),
+        (status = 403, description = "Invalid, expired or used token", body = HttpError),
+        (status = 404, description = "Unknown node", body = HttpError),
+        (status = 409, description = "Node holds or didn't report funds or stake, or a job is running on it", body = HttpError),
+    )
+)]
+async fn delete_bee(
+    Path(bee_id): Path<u8>,
+    Query(query): Query<DeleteBeeQuery>,
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<BeeDeletion>, HttpError> {
+    let bee = find_bee_data(bee_id, &state).await?;
 
-    state.bee_service.delete_bee(bee_id).await?;
+    if query.dry_run {
+        return Ok(Json(state.bee_service.get_bee_deletion(&bee).await?));
+    }
 
-    Ok(())
+    // A header rather than a query parameter, which the audit log records.
+    let token = headers
+        .get(DELETION_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| {
+            ApiError::Validation(format!(
+                "Missing {} header, request one with DELETE /v1/bee/{}/req.",
+                DELETION_TOKEN_HEADER, bee_id
+            ))
+        })?;
+    state
+        .bee_service
+        .delete_bee(&bee, token, &principal, query.force)
+        .await
+        .map(Json)
+        .map_err(Into::into)
 }
 
 async fn find_bee_data(bee_id: u8, state: &Arc<AppState>) -> Result<BeeData, HttpError> {
     match state.bee_service.get_bee(bee_id).await? {
         Some(data) => Ok(data),
-        None => Err(HttpError::new(
-            StatusCode::NOT_FOUND,
-            &format!("Unable to find bee node with id {}.", bee_id),
-        )),
+        None => {
+            Err(ApiError::NotFound(format!("Unable to find bee node with id {}.", bee_id)).into())
+        }
     }
 }
@@ -1,73 +1,317 @@
-use crate::models::bee::BeeData;
+use crate::bee_service::BeeService;
+use crate::middlewares::audit::audit;
+use crate::middlewares::auth::{ensure_unscoped, require_role};
+use crate::models::audit::AuditTargets;
+use crate::models::bee::{BeeData, BeesQuery, TagQuery};
+use crate::models::disk::NodeDiskUsage;
 use crate::models::http_error::HttpError;
+use crate::models::import::{ImportQuery, ImportReport};
+use crate::models::stats::{ContainerStats, StatsQuery};
+use crate::models::token::{Principal, Role};
+use crate::utils::sse::to_json_sse;
 use crate::AppState;
-use axum::extract::State;
-use axum::routing::get;
-use axum::{Json, Router};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use futures_util::future::try_join_all;
 use std::sync::Arc;
+use std::time::Duration;
+use utoipa::OpenApi;
+
+const TOTAL_COUNT_HEADER: &str = "x-total-count";
 
 pub fn init_bees_handlers(app_state: Arc<AppState>) -> Router {
     Router::new()
-        .route("/", get(get_bees))
-        .route("/start", get(start_bees))
-        .route("/stop", get(stop_bees))
-        .route("/recreate", get(recreate_bees))
+        .route("/", get(get_bees).route_layer(require_role(Role::Viewer)))
+        .route(
+            "/stats",
+            get(get_bees_stats).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/disk",
+            get(get_bees_disk_usage).route_layer(require_role(Role::Viewer)),
+        )
+        .route(
+            "/import",
+            post(import_bees)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "bees.import")),
+        )
+        .route(
+            "/start",
+            post(start_bees)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bees.start")),
+        )
+        .route(
+            "/stop",
+            post(stop_bees)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bees.stop")),
+        )
+        .route(
+            "/recreate",
+            post(recreate_bees)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bees.recreate")),
+        )
         .with_state(app_state)
 }
 
-async fn get_bees(State(state): State<Arc<AppState>>) -> Result<Json<Vec<BeeData>>, HttpError> {
-    state
-        .bee_service
-        .get_bees()
-        .await
-        .map(Json)
-        .map_err(Into::into)
+/// `GET` actions of the unversioned API, `POST` only on `/v1`.
+pub fn init_legacy_bees_handlers(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/start",
+            get(start_bees)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bees.start")),
+        )
+        .route(
+            "/stop",
+            get(stop_bees)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bees.stop")),
+        )
+        .route(
+            "/recreate",
+            get(recreate_bees)
+                .route_layer(require_role(Role::Operator))
+                .route_layer(audit(&app_state, "bees.recreate")),
+        )
+        .with_state(app_state)
 }
 
-async fn start_bees(State(state): State<Arc<AppState>>) -> Result<(), HttpError> {
-    let bees_data = state
-        .bee_service
-        .get_bees()
-        .await?
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_bees,
+    get_bees_stats,
+    get_bees_disk_usage,
+    import_bees,
+    start_bees,
+    stop_bees,
+    recreate_bees,
+))]
+pub struct BeesApi;
+
+/// Lists a page of the accessible nodes, their total count in `X-Total-Count`.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "bees",
+    params(BeesQuery),
+    responses(
+        (
+            status = 200,
+            description = "Page of nodes, as `BeeInfo` when `full` is set",
+            body = Vec<BeeData>,
+            headers(("x-total-count" = usize, description = "Matching nodes count"))
+        ),
+        (status = 400, description = "Invalid filter or sort", body = HttpError),
+    )
+)]
+async fn get_bees(
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<BeesQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, HttpError> {
+    let key = BeeService::parse_sort_key(query.sort.as_deref())?;
+    let bees_data = get_tagged_bees(&principal, &state, query.tag.as_deref()).await?;
+    let (total, bees_data) = state.bee_service.list_bees(bees_data, &query, &key).await?;
+
+    let headers = [(TOTAL_COUNT_HEADER, total.to_string())];
+    if !query.full {
+        return Ok((headers, Json(bees_data)).into_response());
+    }
+    let bees = bees_data
         .iter()
-        .map(|bee_data| bee_data.name())
-        .collect();
+        .map(|bee_data| state.bee_service.bee_data_to_info(bee_data))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((headers, Json(bees)).into_response())
+}
 
-    state
-        .bee_service
-        .start_bee_containers(bees_data)
-        .await
-        .map_err(Into::into)
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "bees",
+    params(
+        TagQuery,
+        StatsQuery,
+    ),
+    responses(
+        (status = 200, description = "Stats, or server-sent events", body = Vec<ContainerStats>),
+        (status = 400, description = "Invalid tag filter", body = HttpError),
+    )
+)]
+async fn get_bees_stats(
+    Extension(principal): Extension<Principal>,
+    Query(tags): Query<TagQuery>,
+    Query(query): Query<StatsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, HttpError> {
+    let bees_data = get_tagged_bees(&principal, &state, tags.tag.as_deref()).await?;
+    let names = state.bee_service.get_managed_bee_names(&bees_data).await?;
+
+    if query.stream {
+        let stats = state
+            .bee_service
+            .stream_bees_stats(names, Duration::from_secs(query.interval()));
+        return Ok(to_json_sse(stats).into_response());
+    }
+
+    Ok(Json(state.bee_service.get_bees_stats(&names).await).into_response())
 }
 
-async fn stop_bees(State(state): State<Arc<AppState>>) -> Result<(), HttpError> {
-    let bees_data = state
-        .bee_service
-        .get_bees()
-        .await?
+#[utoipa::path(
+    get,
+    path = "/disk",
+    tag = "bees",
+    params(TagQuery),
+    responses(
+        (status = 200, description = "Data dirs usage", body = Vec<NodeDiskUsage>),
+        (status = 400, description = "Invalid tag filter", body = HttpError),
+    )
+)]
+async fn get_bees_disk_usage(
+    Extension(principal): Extension<Principal>,
+    Query(tags): Query<TagQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<NodeDiskUsage>>, HttpError> {
+    let bees_data = get_tagged_bees(&principal, &state, tags.tag.as_deref()).await?;
+    let usages = bees_data
         .iter()
-        .map(|bee_data| bee_data.name())
-        .collect();
+        .map(|bee_data| state.bee_service.get_node_disk_usage(bee_data));
+    Ok(Json(try_join_all(usages).await?))
+}
 
-    state
-        .bee_service
-        .stop_bee_containers(bees_data)
-        .await
-        .map_err(Into::into)
+#[utoipa::path(
+    post,
+    path = "/start",
+    tag = "bees",
+    params(TagQuery),
+    responses(
+        (status = 204, description = "Containers started"),
+        (status = 400, description = "Invalid tag filter", body = HttpError),
+    )
+)]
+async fn start_bees(
+    Extension(principal): Extension<Principal>,
+    Query(tags): Query<TagQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<(StatusCode, Extension<AuditTargets>), HttpError> {
+    let bees_data = get_tagged_bees(&principal, &state, tags.tag.as_deref()).await?;
+    let names = state.bee_service.get_managed_bee_names(&bees_data).await?;
+
+    state.bee_service.start_bee_containers(names).await?;
+
+    Ok((StatusCode::NO_CONTENT, Extension(audit_targets(&bees_data))))
+}
+
+#[utoipa::path(
+    post,
+    path = "/stop",
+    tag = "bees",
+    params(TagQuery),
+    responses(
+        (status = 204, description = "Containers stopped"),
+        (status = 400, description = "Invalid tag filter", body = HttpError),
+    )
+)]
+async fn stop_bees(
+    Extension(principal): Extension<Principal>,
+    Query(tags): Query<TagQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<(StatusCode, Extension<AuditTargets>), HttpError> {
+    let bees_data = get_tagged_bees(&principal, &state, tags.tag.as_deref()).await?;
+    let names = state.bee_service.get_managed_bee_names(&bees_data).await?;
+
+    state.bee_service.stop_bee_containers(names).await?;
+
+    Ok((StatusCode::NO_CONTENT, Extension(audit_targets(&bees_data))))
+}
+
+#[utoipa::path(
+    post,
+    path = "/recreate",
+    tag = "bees",
+    params(TagQuery),
+    responses(
+        (status = 204, description = "Containers recreated"),
+        (status = 400, description = "Invalid tag filter", body = HttpError),
+    )
+)]
+async fn recreate_bees(
+    Extension(principal): Extension<Principal>,
+    Query(tags): Query<TagQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<(StatusCode, Extension<AuditTargets>), HttpError> {
+    let bees_data = get_tagged_bees(&principal, &state, tags.tag.as_deref()).await?;
+    let bees = bees_data
+        .iter()
+        .map(|bd| state.bee_service.bee_data_to_info(bd))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    state.bee_service.recreate_bee_containers(bees).await?;
+
+    Ok((StatusCode::NO_CONTENT, Extension(audit_targets(&bees_data))))
 }
 
-async fn recreate_bees(State(state): State<Arc<AppState>>) -> Result<(), HttpError> {
-    let bees = state
+/// Bulk operations only ever see the nodes the principal is scoped to.
+pub async fn get_accessible_bees(
+    principal: &Principal,
+    state: &Arc<AppState>,
+) -> Result<Vec<BeeData>, HttpError> {
+    Ok(state
         .bee_service
         .get_bees()
         .await?
         .into_iter()
-        .map(|bd| state.bee_service.bee_data_to_info(&bd))
-        .collect::<anyhow::Result<Vec<_>>>()?;
+        .filter(|bee_data| principal.can_access(bee_data.id))
+        .collect())
+}
 
-    state
-        .bee_service
-        .recreate_bee_containers(bees)
-        .await
-        .map_err(Into::into)
+/// Accessible nodes matching the `tag` filters, if any.
+async fn get_tagged_bees(
+    principal: &Principal,
+    state: &Arc<AppState>,
+    tag: Option<&str>,
+) -> Result<Vec<BeeData>, HttpError> {
+    let bees_data = get_accessible_bees(principal, state).await?;
+    let Some(tag) = tag else {
+        return Ok(bees_data);
+    };
+    let filters = BeeService::parse_tag_filters(tag)?;
+    Ok(bees_data
+        .into_iter()
+        .filter(|bee_data| BeeService::matches_tags(bee_data, &filters))
+        .collect())
+}
+
+/// Registers nodes that exist in Docker or on disk but not in the database.
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "bees",
+    params(ImportQuery),
+    responses(
+        (status = 200, description = "Imported nodes", body = ImportReport),
+        (status = 403, description = "Scoped token", body = HttpError),
+    )
+)]
+async fn import_bees(
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<ImportQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<(Extension<AuditTargets>, Json<ImportReport>), HttpError> {
+    ensure_unscoped(&principal)?;
+
+    let report = state.bee_service.import_bees(query.dry_run).await?;
+    let targets = AuditTargets(report.imported.iter().map(|bee| bee.id).collect());
+    Ok((Extension(targets), Json(report)))
+}
+
+fn audit_targets(bees_data: &[BeeData]) -> AuditTargets {
+    AuditTargets(bees_data.iter().map(|bee_data| bee_data.id).collect())
 }
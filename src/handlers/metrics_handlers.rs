@@ -0,0 +1,42 @@
+use crate::middlewares::auth::require_role;
+use crate::models::http_error::HttpError;
+use crate::models::token::{Principal, Role};
+use crate::AppState;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Extension, Router};
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub fn init_metrics_handlers(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(get_metrics).route_layer(require_role(Role::Viewer)),
+        )
+        .with_state(app_state)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_metrics))]
+pub struct MetricsApi;
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Metrics", content_type = "text/plain", body = String),
+    )
+)]
+async fn get_metrics(
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let metrics = state.bee_service.get_metrics(&principal).await?;
+    Ok(([(header::CONTENT_TYPE, CONTENT_TYPE)], metrics))
+}
@@ -1,2 +1,251 @@
+use crate::middlewares::auth::authenticate;
+use crate::middlewares::deprecation::deprecate;
+use crate::models::http_error::{ApiError, HttpError};
+use crate::AppState;
+use admin_handlers::init_admin_handlers;
+use audit_handlers::init_audit_handlers;
+use axum::http::Uri;
+use axum::{middleware, Router};
+use bee_handlers::{init_bee_handlers, init_legacy_bee_handlers};
+use bees_handlers::{init_bees_handlers, init_legacy_bees_handlers};
+use metrics_handlers::init_metrics_handlers;
+use std::sync::Arc;
+use storage_handlers::init_storage_handlers;
+use tokens_handlers::init_tokens_handlers;
+
+pub mod admin_handlers;
+pub mod audit_handlers;
 pub mod bee_handlers;
 pub mod bees_handlers;
+pub mod metrics_handlers;
+pub mod openapi_handlers;
+pub mod storage_handlers;
+pub mod tokens_handlers;
+
+/// Current API, mounted under `/v1`.
+pub fn init_api(app_state: &Arc<AppState>) -> Router {
+    Router::new()
+        .nest("/bee", init_bee_handlers(app_state.clone()))
+        .nest("/bees", init_bees_handlers(app_state.clone()))
+        .nest("/tokens", init_tokens_handlers(app_state.clone()))
+        .nest("/audit", init_audit_handlers(app_state.clone()))
+        .nest("/metrics", init_metrics_handlers(app_state.clone()))
+        .nest("/storage", init_storage_handlers(app_state.clone()))
+        .nest("/admin", init_admin_handlers(app_state.clone()))
+}
+
+/// `/v1`, plus the unversioned routes when enabled, behind authentication.
+pub fn init_routes(app_state: &Arc<AppState>, legacy_routes: bool) -> Router {
+    let mut api = Router::new().nest("/v1", init_api(app_state));
+    if legacy_routes {
+        api = api.merge(init_legacy_api(app_state));
+    }
+    api.layer(middleware::from_fn_with_state(
+        app_state.clone(),
+        authenticate,
+    ))
+}
+
+/// Fallback of the whole app, unknown routes get the error body of the handlers.
+pub async fn route_not_found(uri: Uri) -> HttpError {
+    ApiError::NotFound(format!("No route for '{}'.", uri.path())).into()
+}
+
+/// Same routes without the prefix, plus the `GET` actions they used to have, all deprecated.
+pub fn init_legacy_api(app_state: &Arc<AppState>) -> Router {
+    init_api(app_state)
+        .merge(
+            Router::new()
+                .nest("/bee", init_legacy_bee_handlers(app_state.clone()))
+                .nest("/bees", init_legacy_bees_handlers(app_state.clone())),
+        )
+        .layer(middleware::from_fn(deprecate))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::bee_service::BeeService;
+    use crate::core::{database::MockDbService, docker::Docker, metrics::Metrics};
+    use crate::models::{
+        bee::BeeData,
+        config::{AuthToken, Config},
+        token::Role,
+    };
+    use axum::body::{to_bytes, Body};
+    use axum::extract::Request;
+    use axum::http::StatusCode;
+    use axum::response::Response;
+    use tower::ServiceExt;
+
+    /// State over an in-memory database and a Docker daemon that never answers,
+    /// authentication disabled.
+    pub fn app_state() -> Arc<AppState> {
+        let mut config = Config::default();
+        config.auth.enabled = false;
+        app_state_with(config)
+    }
+
+    pub fn app_state_with(config: Config) -> Arc<AppState> {
+        let metrics = Metrics::new();
+        let docker = Docker::unreachable(metrics.clone(), &config);
+        Arc::new(AppState {
+            bee_service: BeeService::new(
+                config,
+                Box::new(MockDbService::default()),
+                Box::new(docker),
+                metrics,
+            ),
+        })
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str) -> Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_serve_v1_and_legacy_routes() {
+        let app = init_routes(&app_state(), true);
+
+        let response = send(&app, "GET", "/v1/tokens").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key("deprecation"));
+
+        let response = send(&app, "GET", "/tokens").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key("deprecation"));
+
+        let app = init_routes(&app_state(), false).fallback(route_not_found);
+        let response = send(&app, "GET", "/tokens").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn should_keep_legacy_status_at_capacity() {
+        let state = app_state();
+        for id in 1..=99 {
+            let bee = BeeData {
+                id,
+                ..Default::default()
+            };
+            state.bee_service.save_bee(&bee).await.unwrap();
+        }
+        let app = init_routes(&state, true);
+
+        let response = send(&app, "POST", "/v1/bee").await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = send(&app, "POST", "/bee").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "capacity_reached");
+    }
+
+    #[tokio::test]
+    async fn should_refuse_scoped_admin_outside_node_routes() {
+        let mut config = Config::default();
+        config.auth.tokens = vec![AuthToken {
+            name: "node-1".to_owned(),
+            token: "scoped-token".to_owned(),
+            role: Role::Admin,
+            bee_ids: Some(vec![1]),
+        }];
+        let app = init_routes(&app_state_with(config), false);
+        let routes = [
+            ("GET", "/v1/tokens", ""),
+            ("POST", "/v1/tokens", r#"{"name":"ci"}"#),
+            ("GET", "/v1/tokens/some-id", ""),
+            ("DELETE", "/v1/tokens/some-id", ""),
+            ("GET", "/v1/audit", ""),
+            ("POST", "/v1/bee", ""),
+            (
+                "POST",
+                "/v1/bee/restore",
+                r#"{"backup":"node_01-1.tar.gz"}"#,
+            ),
+            ("POST", "/v1/bees/import", ""),
+        ];
+
+        for (method, uri, body) in routes {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", "Bearer scoped-token")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::FORBIDDEN,
+                "{} {}",
+                method,
+                uri
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn should_keep_deletion_token_when_deletion_fails() {
+        let mut config = Config::default();
+        config.auth.enabled = false;
+        config.network.api_port = "17xx".to_owned();
+        config.network.p2p_port = "18xx".to_owned();
+        let state = app_state_with(config);
+        // The in-memory database finds nodes by their position.
+        state
+            .bee_service
+            .save_bee(&BeeData::default())
+            .await
+            .unwrap();
+        let app = init_routes(&state, false);
+
+        let response = send(&app, "DELETE", "/v1/bee/0/req").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let token = body["token"].as_str().unwrap();
+
+        // The Docker daemon never answers, so removing the container fails each time.
+        for _ in 0..2 {
+            let request = Request::delete("/v1/bee/0?force=true")
+                .header("x-deletion-token", token)
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_ne!(response.status(), StatusCode::FORBIDDEN);
+            assert!(response.status().is_server_error());
+        }
+    }
+
+    #[tokio::test]
+    async fn should_serve_public_metrics_without_token() {
+        let mut config = Config::default();
+        config.auth.tokens = vec![AuthToken {
+            name: "admin".to_owned(),
+            token: "admin-token".to_owned(),
+            role: Role::Admin,
+            bee_ids: None,
+        }];
+        let app = init_routes(&app_state_with(config.clone()), false);
+        let response = send(&app, "GET", "/v1/metrics").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        config.metrics.public = true;
+        let app = init_routes(&app_state_with(config), false);
+        // Past authentication, the unreachable Docker then fails the collection.
+        let response = send(&app, "GET", "/v1/metrics").await;
+        assert_ne!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = send(&app, "GET", "/v1/tokens").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::handlers::admin_handlers::AdminApi;
+use crate::handlers::audit_handlers::AuditApi;
+use crate::handlers::bee_handlers::BeeApi;
+use crate::handlers::bees_handlers::BeesApi;
+use crate::handlers::metrics_handlers::MetricsApi;
+use crate::handlers::storage_handlers::StorageApi;
+use crate::handlers::tokens_handlers::TokensApi;
+use crate::models::bee::{BeeData, BeeInfo};
+use crate::models::http_error::HttpError;
+use axum::response::Html;
+use axum::routing::get;
+use axum::{Json, Router};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+/// Renders `/openapi.json` with Redoc, whose script is loaded from a CDN at a pinned version.
+const DOCS_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>ruche API</title>
+    <meta charset="utf-8" />
+  </head>
+  <body>
+    <redoc spec-url="/openapi.json"></redoc>
+    <script src="https://cdn.jsdelivr.net/npm/redoc@2.5.0/bundles/redoc.standalone.js" crossorigin="anonymous"></script>
+  </body>
+</html>
+"#;
+
+/// Served without authentication, the spec holds no fleet data.
+pub fn init_openapi_handlers() -> Router {
+    Router::new()
+        .route("/openapi.json", get(get_openapi))
+        .route("/docs", get(get_docs))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(get_openapi, get_docs),
+    components(schemas(BeeData, BeeInfo, HttpError)),
+    modifiers(&BearerAuth),
+    security(("bearer" = []))
+)]
+struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+/// Whole `/v1` API, each module nested under the prefix `init_api` mounts its router on.
+pub fn get_openapi_doc() -> utoipa::openapi::OpenApi {
+    [
+        ("/v1/bee", BeeApi::openapi()),
+        ("/v1/bees", BeesApi::openapi()),
+        ("/v1/tokens", TokensApi::openapi()),
+        ("/v1/audit", AuditApi::openapi()),
+        ("/v1/metrics", MetricsApi::openapi()),
+        ("/v1/storage", StorageApi::openapi()),
+        ("/v1/admin", AdminApi::openapi()),
+    ]
+    .into_iter()
+    .fold(ApiDoc::openapi(), |doc, (prefix, api)| {
+        // axum serves a nested "/" route at the bare prefix.
+        doc.nest_with_path_composer(prefix, api, |prefix, path| match path {
+            "/" => prefix.to_owned(),
+            path => format!("{}{}", prefix, path),
+        })
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "docs",
+    security(()),
+    responses(
+        (status = 200, description = "This document"),
+    )
+)]
+async fn get_openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(get_openapi_doc())
+}
+
+#[utoipa::path(
+    get,
+    path = "/docs",
+    tag = "docs",
+    security(()),
+    responses(
+        (status = 200, description = "Redoc page", content_type = "text/html"),
+    )
+)]
+async fn get_docs() -> Html<&'static str> {
+    Html(DOCS_PAGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{init_routes, tests::app_state};
+    use axum::body::Body;
+    use axum::extract::Request;
+    use axum::http::{header, StatusCode};
+    use regex::Regex;
+    use std::collections::BTreeSet;
+    use tower::ServiceExt;
+
+    const METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+    /// `METHOD /path` served by the routers `main` mounts. A request with an
+    /// unknown method gets a 405 listing the methods of its path, no handler runs.
+    async fn get_routes() -> BTreeSet<String> {
+        let app = init_routes(&app_state(), false).merge(init_openapi_handlers());
+        // axum only lists the registered paths in its Debug output, fallbacks last.
+        let debug = format!("{:?}", app);
+        let (debug, _) = debug.split_once("fallback_router").unwrap();
+        let paths = Regex::new(r#"RouteId\(\d+\): "([^"]+)""#)
+            .unwrap()
+            .captures_iter(debug)
+            .map(|path| path[1].to_owned())
+            .collect::<BTreeSet<_>>();
+
+        let params = Regex::new(r"\{[^}]+\}").unwrap();
+        let mut routes = BTreeSet::new();
+        for path in paths {
+            let request = Request::builder()
+                .method("PROBE")
+                .uri(params.replace_all(&path, "1").as_ref())
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "{}",
+                path
+            );
+
+            let allowed = response.headers()[header::ALLOW].to_str().unwrap();
+            routes.extend(
+                allowed
+                    .split(',')
+                    .filter(|method| METHODS.contains(method))
+                    .map(|method| format!("{} {}", method, path)),
+            );
+        }
+        routes
+    }
+
+    fn get_documented_routes() -> BTreeSet<String> {
+        let mut documented = BTreeSet::new();
+        for (path, item) in get_openapi_doc().paths.paths {
+            let operations = [&item.get, &item.post, &item.put, &item.patch, &item.delete];
+            for (method, operation) in METHODS.into_iter().zip(operations) {
+                if operation.is_some() {
+                    documented.insert(format!("{} {}", method, path));
+                }
+            }
+        }
+        documented
+    }
+
+    #[tokio::test]
+    async fn should_document_every_route() {
+        let routes = get_routes().await;
+        assert!(routes.contains("POST /v1/bee"));
+        assert!(routes.contains("POST /v1/bee/{bee_id}/start"));
+        assert!(routes.contains("GET /openapi.json"));
+
+        let documented = get_documented_routes();
+        let undocumented = routes.difference(&documented).collect::<Vec<_>>();
+        let unknown = documented.difference(&routes).collect::<Vec<_>>();
+        assert!(undocumented.is_empty(), "Undocumented: {:?}", undocumented);
+        assert!(
+            unknown.is_empty(),
+            "Documented without a route: {:?}",
+            unknown
+        );
+    }
+
+    #[test]
+    fn should_include_core_schemas() {
+        let doc = get_openapi_doc();
+        let schemas = doc.components.unwrap().schemas;
+
+        for name in ["BeeData", "BeeInfo", "HttpError", "Job"] {
+            assert!(schemas.contains_key(name), "Missing schema {}", name);
+        }
+    }
+
+    #[test]
+    fn should_describe_tag_changes_needing_recreate() {
+        let doc = serde_json::to_value(get_openapi_doc()).unwrap();
+        let tags = &doc["paths"]["/v1/bee/{bee_id}/tags"];
+
+        for method in ["put", "delete"] {
+            let description = tags[method]["description"].as_str().unwrap_or_default();
+            assert!(description.contains("/{bee_id}/recreate"), "{}", method);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::handlers::bees_handlers::get_accessible_bees;
+use crate::middlewares::auth::require_role;
+use crate::models::disk::ParentDirUsage;
+use crate::models::http_error::HttpError;
+use crate::models::token::{Principal, Role};
+use crate::AppState;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+pub fn init_storage_handlers(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(get_storage_usage).route_layer(require_role(Role::Viewer)),
+        )
+        .with_state(app_state)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_storage_usage))]
+pub struct StorageApi;
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "storage",
+    responses(
+        (status = 200, description = "Usage of the node disks", body = Vec<ParentDirUsage>),
+    )
+)]
+async fn get_storage_usage(
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ParentDirUsage>>, HttpError> {
+    let bees_data = get_accessible_bees(&principal, &state).await?;
+    state
+        .bee_service
+        .get_parent_dirs_usage(&bees_data)
+        .map(Json)
+        .map_err(Into::into)
+}
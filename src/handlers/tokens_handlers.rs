@@ -0,0 +1,147 @@
+use crate::middlewares::audit::audit;
+use crate::middlewares::auth::{ensure_unscoped, require_role};
+use crate::models::http_error::{ApiError, HttpError};
+use crate::models::token::{ApiTokenInfo, CreateTokenRequest, NewApiToken, Principal, Role};
+use crate::AppState;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderName, StatusCode};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+pub fn init_tokens_handlers(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/",
+            post(create_token)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "token.create")),
+        )
+        .route("/", get(get_tokens).route_layer(require_role(Role::Admin)))
+        .route(
+            "/{token_id}",
+            get(get_token).route_layer(require_role(Role::Admin)),
+        )
+        .route(
+            "/{token_id}",
+            delete(revoke_token)
+                .route_layer(require_role(Role::Admin))
+                .route_layer(audit(&app_state, "token.revoke")),
+        )
+        .with_state(app_state)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(create_token, get_tokens, get_token, revoke_token))]
+pub struct TokensApi;
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "tokens",
+    request_body = CreateTokenRequest,
+    responses(
+        (
+            status = 201,
+            description = "Token, shown only once",
+            body = NewApiToken,
+            headers(("location" = String, description = "URL of the token"))
+        ),
+        (status = 400, description = "Empty name", body = HttpError),
+        (status = 403, description = "Scoped token", body = HttpError),
+    )
+)]
+async fn create_token(
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+    Json(mut req): Json<CreateTokenRequest>,
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<NewApiToken>), HttpError> {
+    ensure_unscoped(&principal)?;
+
+    req.name = req.name.trim().to_owned();
+    if req.name.is_empty() {
+        return Err(ApiError::Validation("Token name must not be empty.".to_owned()).into());
+    }
+
+    let token = state.bee_service.create_token(&req).await?;
+    let url = format!("/v1/tokens/{}", token.id);
+    Ok((StatusCode::CREATED, [(header::LOCATION, url)], Json(token)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "tokens",
+    responses(
+        (status = 200, description = "Tokens, without their secret", body = Vec<ApiTokenInfo>),
+        (status = 403, description = "Scoped token", body = HttpError),
+    )
+)]
+async fn get_tokens(
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ApiTokenInfo>>, HttpError> {
+    ensure_unscoped(&principal)?;
+
+    state
+        .bee_service
+        .get_tokens()
+        .await
+        .map(Json)
+        .map_err(Into::into)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{token_id}",
+    tag = "tokens",
+    params(("token_id" = String, Path, description = "Token id")),
+    responses(
+        (status = 200, description = "Token, without its secret", body = ApiTokenInfo),
+        (status = 403, description = "Scoped token", body = HttpError),
+        (status = 404, description = "Unknown token", body = HttpError),
+    )
+)]
+async fn get_token(
+    Path(token_id): Path<String>,
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ApiTokenInfo>, HttpError> {
+    ensure_unscoped(&principal)?;
+
+    match state.bee_service.get_token(&token_id).await? {
+        Some(token) => Ok(Json(token)),
+        None => Err(token_not_found(&token_id).into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{token_id}",
+    tag = "tokens",
+    params(("token_id" = String, Path, description = "Token id")),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 403, description = "Scoped token", body = HttpError),
+        (status = 404, description = "Unknown token", body = HttpError),
+    )
+)]
+async fn revoke_token(
+    Path(token_id): Path<String>,
+    Extension(principal): Extension<Principal>,
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, HttpError> {
+    ensure_unscoped(&principal)?;
+
+    if state.bee_service.get_token(&token_id).await?.is_none() {
+        return Err(token_not_found(&token_id).into());
+    }
+
+    state.bee_service.revoke_token(&token_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn token_not_found(token_id: &str) -> ApiError {
+    ApiError::NotFound(format!("Unable to find token with id {}.", token_id))
+}
@@ -1,58 +1,119 @@
 mod bee_service;
+mod cli;
 mod core;
 mod handlers;
+mod middlewares;
 mod models;
 mod utils;
 
-use crate::core::database::Database;
-use crate::handlers::bee_handlers::init_bee_handlers;
-use axum::Router;
+use crate::core::database::{BeeDatabase, Database};
+use crate::core::sqlite::SqliteDatabase;
+use axum::error_handling::HandleErrorLayer;
+use axum::middleware;
 use bee_service::BeeService;
+use cli::Command;
 use core::docker::Docker;
-use handlers::bees_handlers::init_bees_handlers;
-use models::config::Config;
-use std::collections::HashMap;
-use std::net::SocketAddr;
+use core::metrics::Metrics;
+use handlers::openapi_handlers::init_openapi_handlers;
+use handlers::{init_routes, route_not_found};
+use middlewares::metrics::track_http_metrics;
+use middlewares::rejection::{describe_rejections, handle_layer_error};
+use middlewares::request_id::assign_request_id;
+use models::config::{Config, DatabaseBackend};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tower::timeout::TimeoutLayer;
 use tower::ServiceBuilder;
-use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::TraceLayer;
 use tower_http::ServiceBuilderExt;
 
 #[derive(Clone)]
 pub struct AppState {
     bee_service: BeeService,
-    last_bee_deletion_req: Arc<Mutex<HashMap<u8, SystemTime>>>,
+}
+
+async fn open_database(config: &Config) -> anyhow::Result<Box<dyn BeeDatabase>> {
+    match config.database.backend {
+        DatabaseBackend::Polodb => Ok(Box::new(Database::open()?)),
+        DatabaseBackend::Sqlite => Ok(Box::new(SqliteDatabase::open(
+            &config.database.sqlite_path,
+        )?)),
+    }
 }
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
 
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let command = Command::parse(&args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(2);
+    });
+
     let config = Config::parse().await;
-    let database = Database::new();
-    let docker = Docker::new();
+    if !config.auth.enabled {
+        tracing::warn!("API authentication is disabled");
+    }
+    if command == Command::DbMigrateSqlite {
+        if let Err(err) = cli::migrate_sqlite(&config).await {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let metrics = Metrics::new();
+    let database = open_database(&config).await.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let docker = Docker::new(metrics.clone(), &config);
 
     let app_state: Arc<AppState> = Arc::new(AppState {
-        bee_service: BeeService::new(config.clone(), Box::new(database), Box::new(docker)),
-        last_bee_deletion_req: Arc::new(Mutex::new(HashMap::new())),
+        bee_service: BeeService::new(config.clone(), database, Box::new(docker), metrics.clone()),
     });
 
-    let app = Router::new()
-        .nest("/bee", init_bee_handlers(app_state.clone()))
-        .nest("/bees", init_bees_handlers(app_state.clone()))
+    if command != Command::Serve {
+        if let Err(err) = cli::run(command, &app_state.bee_service).await {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Err(err) = app_state.bee_service.check_auth_tokens().await {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
+    // Before watching events, which only come from labeled containers.
+    match app_state.bee_service.adopt_bee_containers().await {
+        Ok(adopted) if !adopted.is_empty() => {
+            tracing::info!("Adopted containers of {}", adopted.join(", "))
+        }
+        Ok(_) => {}
+        Err(err) => tracing::error!("Failed to adopt existing containers: {}", err),
+    }
+
+    let bee_service = app_state.bee_service.clone();
+    tokio::spawn(async move { bee_service.watch_bee_events().await });
+    let bee_service = app_state.bee_service.clone();
+    tokio::spawn(async move { bee_service.run_scheduled_backups().await });
+
+    let app = init_routes(&app_state, config.server.legacy_routes)
+        .merge(init_openapi_handlers())
+        .fallback(route_not_found)
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
+                .layer(middleware::from_fn(assign_request_id))
+                .layer(middleware::from_fn_with_state(metrics, track_http_metrics))
+                .layer(middleware::from_fn(describe_rejections))
+                .layer(HandleErrorLayer::new(handle_layer_error))
                 .layer(TimeoutLayer::new(Duration::from_secs(15)))
                 .compression(),
         );
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
-    tracing::info!("Listening on {}", addr);
-
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    core::server::serve(&config, app).await.unwrap();
 }
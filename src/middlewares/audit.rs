@@ -0,0 +1,407 @@
+use crate::models::audit::{AuditEntry, AuditTargets};
+use crate::models::token::Principal;
+use crate::utils::time::now_secs;
+use crate::AppState;
+use axum::body::{Body, Bytes};
+use axum::extract::{ConnectInfo, FromRequestParts, Query, RawPathParams, Request};
+use axum::http::header;
+use axum::http::request::Parts;
+use axum::response::Response;
+use futures_util::future::BoxFuture;
+use futures_util::{stream, StreamExt};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Larger bodies, such as database dumps, aren't recorded.
+const MAX_AUDITED_BODY: usize = 16 * 1024;
+/// Body fields and query parameters never written to the audit log, matched on part of the key.
+const REDACTED_FIELDS: &[&str] = &["token", "password", "secret", "hash", "key"];
+const REDACTED: &str = "[redacted]";
+
+/// Route layer recording every call of a mutating route in the audit log.
+/// Handlers acting on nodes not named in the path attach them with `AuditTargets`.
+pub fn audit(state: &Arc<AppState>, action: &'static str) -> AuditLayer {
+    AuditLayer {
+        state: state.clone(),
+        action,
+    }
+}
+
+#[derive(Clone)]
+pub struct AuditLayer {
+    state: Arc<AppState>,
+    action: &'static str,
+}
+
+impl<S> Layer<S> for AuditLayer {
+    type Service = AuditService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditService {
+            inner,
+            state: self.state.clone(),
+            action: self.action,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuditService<S> {
+    inner: S,
+    state: Arc<AppState>,
+    action: &'static str,
+}
+
+impl<S> Service<Request> for AuditService<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let state = self.state.clone();
+        let action = self.action;
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+            let mut entry = new_audit_entry(action, &mut parts).await;
+            let body = if is_audited_body(&parts) {
+                let (bytes, body) = read_small_body(body).await;
+                match bytes {
+                    Some(bytes) => add_body_parameters(&mut entry.parameters, &bytes),
+                    None => tracing::warn!(
+                        action,
+                        "Body over {} bytes left out of the audit log",
+                        MAX_AUDITED_BODY
+                    ),
+                }
+                body
+            } else {
+                body
+            };
+
+            let mut pending = PendingAuditEntry {
+                state,
+                entry: Some(entry),
+            };
+            let response = inner.call(Request::from_parts(parts, body)).await?;
+
+            let mut entry = pending.entry.take().unwrap_or_default();
+            if let Some(AuditTargets(bee_ids)) = response.extensions().get::<AuditTargets>() {
+                entry.bee_ids = bee_ids.to_owned();
+            }
+            entry.status = response.status().as_u16();
+            entry.success = response.status().is_success();
+
+            // Each failing sink is already logged.
+            pending
+                .state
+                .bee_service
+                .record_audit_entry(entry)
+                .await
+                .ok();
+
+            Ok(response)
+        })
+    }
+}
+
+/// Records the entry of a request dropped before its handler answered, e.g. by the
+/// timeout, with no status: the action may have happened all the same.
+struct PendingAuditEntry {
+    state: Arc<AppState>,
+    entry: Option<AuditEntry>,
+}
+
+impl Drop for PendingAuditEntry {
+    fn drop(&mut self) {
+        if let Some(entry) = self.entry.take() {
+            let state = self.state.clone();
+            tokio::spawn(async move { state.bee_service.record_audit_entry(entry).await.ok() });
+        }
+    }
+}
+
+/// Reads a body of at most `MAX_AUDITED_BODY` bytes, whatever its `Content-Length` says.
+/// The body is handed back whole either way, its bytes only when they all fit.
+async fn read_small_body(body: Body) -> (Option<Bytes>, Body) {
+    let mut data = body.into_data_stream();
+    let mut chunks = Vec::new();
+    let mut length = 0;
+    while let Some(chunk) = data.next().await {
+        let fits = chunk
+            .as_ref()
+            .is_ok_and(|chunk| length + chunk.len() <= MAX_AUDITED_BODY);
+        length += chunk.as_ref().map_or(0, Bytes::len);
+        chunks.push(chunk);
+        if !fits {
+            return (None, Body::from_stream(stream::iter(chunks).chain(data)));
+        }
+    }
+
+    let bytes = Bytes::from(
+        chunks
+            .into_iter()
+            .flatten()
+            .flat_map(|chunk| chunk.to_vec())
+            .collect::<Vec<_>>(),
+    );
+    (Some(bytes.clone()), Body::from(bytes))
+}
+
+/// Only small JSON bodies are recorded, database dumps and uploads aren't. Those
+/// without a `Content-Length` are read up to the limit.
+fn is_audited_body(parts: &Parts) -> bool {
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let length = parts
+        .headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    is_json && length.is_none_or(|length| length <= MAX_AUDITED_BODY)
+}
+
+/// Top-level fields of a JSON object body, path and query parameters taking precedence.
+fn add_body_parameters(parameters: &mut BTreeMap<String, String>, body: &[u8]) {
+    let Ok(Value::Object(fields)) = serde_json::from_slice::<Value>(body) else {
+        return;
+    };
+    for (key, mut value) in fields {
+        redact(&key, &mut value);
+        let value = match value {
+            Value::String(value) => value,
+            value => value.to_string(),
+        };
+        parameters.entry(key).or_insert(value);
+    }
+}
+
+fn is_redacted(key: &str) -> bool {
+    let key = key.to_lowercase();
+    REDACTED_FIELDS.iter().any(|field| key.contains(field))
+}
+
+fn redact(key: &str, value: &mut Value) {
+    if is_redacted(key) {
+        *value = Value::String(REDACTED.to_owned());
+        return;
+    }
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                redact(key, value);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                redact("", value);
+            }
+        }
+        _ => {}
+    }
+}
+
+async fn new_audit_entry(action: &str, parts: &mut Parts) -> AuditEntry {
+    let principal = parts
+        .extensions
+        .get::<Principal>()
+        .map(|principal| principal.name.to_owned())
+        .unwrap_or_default();
+
+    let source_ip = parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+
+    let mut parameters = BTreeMap::new();
+    if let Ok(params) = RawPathParams::from_request_parts(parts, &()).await {
+        for (key, value) in params.iter() {
+            parameters.insert(key.to_owned(), value.to_owned());
+        }
+    }
+    if let Ok(Query(query)) = Query::<HashMap<String, String>>::try_from_uri(&parts.uri) {
+        for (key, value) in query {
+            let value = if is_redacted(&key) {
+                REDACTED.to_owned()
+            } else {
+                value
+            };
+            parameters.insert(key, value);
+        }
+    }
+
+    let bee_ids = parameters
+        .get("bee_id")
+        .and_then(|bee_id| bee_id.parse::<u8>().ok())
+        .into_iter()
+        .collect();
+
+    AuditEntry {
+        timestamp: now_secs(),
+        principal,
+        source_ip,
+        action: action.to_owned(),
+        bee_ids,
+        parameters,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{audit::AuditQuery, token::Role};
+    use axum::routing::post;
+    use axum::Router;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn should_build_entry_from_request() {
+        let req = Request::builder()
+            .uri("/bee/7/stop?force=true")
+            .extension(Principal {
+                name: "ops".to_owned(),
+                role: Role::Operator,
+                ..Default::default()
+            })
+            .extension(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4242))))
+            .body(Body::empty())
+            .unwrap();
+        let (mut parts, _) = req.into_parts();
+
+        let entry = new_audit_entry("bee.stop", &mut parts).await;
+
+        assert_eq!(entry.action, "bee.stop");
+        assert_eq!(entry.principal, "ops");
+        assert_eq!(entry.source_ip, Some("10.0.0.1".to_owned()));
+        assert_eq!(entry.parameters.get("force"), Some(&"true".to_owned()));
+        assert!(entry.timestamp > 0);
+    }
+
+    #[test]
+    fn should_record_redacted_body_fields() {
+        let mut parameters = BTreeMap::from([("bee_id".to_owned(), "7".to_owned())]);
+        let body = br#"{"bee_id": 9, "volume": "/mnt/disk2", "token": "secret-value",
+            "auth": {"password": "p", "user": "ops"}, "bee_ids": [1, 2]}"#;
+
+        add_body_parameters(&mut parameters, body);
+
+        assert_eq!(parameters["bee_id"], "7");
+        assert_eq!(parameters["volume"], "/mnt/disk2");
+        assert_eq!(parameters["token"], REDACTED);
+        assert_eq!(
+            parameters["auth"],
+            r#"{"password":"[redacted]","user":"ops"}"#
+        );
+        assert_eq!(parameters["bee_ids"], "[1,2]");
+    }
+
+    #[tokio::test]
+    async fn should_redact_query_parameters() {
+        let req = Request::builder()
+            .uri("/bee/7?force=true&token=secret-value")
+            .body(Body::empty())
+            .unwrap();
+        let (mut parts, _) = req.into_parts();
+
+        let entry = new_audit_entry("bee.delete", &mut parts).await;
+
+        assert_eq!(entry.parameters["force"], "true");
+        assert_eq!(entry.parameters["token"], REDACTED);
+    }
+
+    #[tokio::test]
+    async fn should_read_chunked_bodies_up_to_limit() {
+        let chunked = |chunks: Vec<String>| {
+            Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, Infallible>)))
+        };
+
+        let (bytes, body) = read_small_body(chunked(vec![
+            r#"{"volume":"#.to_owned(),
+            r#""/mnt"}"#.to_owned(),
+        ]))
+        .await;
+        assert_eq!(bytes.unwrap(), r#"{"volume":"/mnt"}"#);
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(body, r#"{"volume":"/mnt"}"#);
+
+        let large = "x".repeat(MAX_AUDITED_BODY);
+        let (bytes, body) =
+            read_small_body(chunked(vec!["{".to_owned(), large, "}".to_owned()])).await;
+        assert!(bytes.is_none());
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(body.len(), MAX_AUDITED_BODY + 2);
+    }
+
+    #[tokio::test]
+    async fn should_record_cancelled_requests() {
+        let state = crate::handlers::tests::app_state();
+        let app = Router::new()
+            .route(
+                "/bee/{bee_id}/stop",
+                post(std::future::pending::<()>).route_layer(audit(&state, "bee.stop")),
+            )
+            .with_state(state.clone());
+
+        let request = Request::post("/bee/7/stop").body(Body::empty()).unwrap();
+        let call = tokio::time::timeout(Duration::from_millis(10), app.oneshot(request));
+        assert!(call.await.is_err());
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let entries = state
+            .bee_service
+            .get_audit_entries(&AuditQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].bee_ids, vec![7]);
+        assert_eq!(entries[0].status, 0);
+        assert!(!entries[0].success);
+    }
+
+    #[test]
+    fn should_only_audit_small_json_bodies() {
+        let parts = |content_type: &str, length: usize| {
+            Request::post("/")
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::CONTENT_LENGTH, length)
+                .body(())
+                .unwrap()
+                .into_parts()
+                .0
+        };
+
+        assert!(is_audited_body(&parts("application/json", 64)));
+        assert!(!is_audited_body(&parts(
+            "application/json",
+            MAX_AUDITED_BODY + 1
+        )));
+        assert!(!is_audited_body(&parts("application/octet-stream", 64)));
+        let chunked = Request::post("/")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0;
+        assert!(is_audited_body(&chunked));
+    }
+}
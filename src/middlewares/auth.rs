@@ -0,0 +1,237 @@
+use crate::models::http_error::{ApiError, HttpError};
+use crate::models::token::{Principal, Role};
+use crate::AppState;
+use axum::extract::{FromRequestParts, RawPathParams, Request, State};
+use axum::http::header;
+use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures_util::future::BoxFuture;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Served without a token when `metrics.public` is set, the second one on legacy routes.
+const METRICS_PATHS: &[&str] = &["/v1/metrics", "/metrics"];
+
+fn anonymous(role: Role) -> Principal {
+    Principal {
+        token_id: "anonymous".to_owned(),
+        name: "anonymous".to_owned(),
+        role,
+        bee_ids: None,
+    }
+}
+
+pub async fn authenticate(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, HttpError> {
+    if !state.bee_service.is_auth_enabled() {
+        req.extensions_mut().insert(anonymous(Role::Admin));
+        return Ok(next.run(req).await);
+    }
+
+    let authorization = req.headers().get(header::AUTHORIZATION);
+    if authorization.is_none()
+        && state.bee_service.is_metrics_public()
+        && METRICS_PATHS.contains(&req.uri().path())
+    {
+        req.extensions_mut().insert(anonymous(Role::Viewer));
+        return Ok(next.run(req).await);
+    }
+
+    let token = authorization
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .ok_or_else(|| {
+            ApiError::Unauthorized("Missing bearer token in Authorization header.".to_owned())
+        })?;
+
+    let principal = state
+        .bee_service
+        .authenticate(token)
+        .await?
+        .ok_or_else(|| ApiError::Unauthorized("Invalid API token.".to_owned()))?;
+
+    req.extensions_mut().insert(principal);
+    Ok(next.run(req).await)
+}
+
+/// Route layer declaring the minimum role a route requires.
+/// Routes with a `bee_id` path parameter are also checked against the principal's node scope.
+pub fn require_role(role: Role) -> RequireRole {
+    RequireRole { role }
+}
+
+#[derive(Clone, Copy)]
+pub struct RequireRole {
+    role: Role,
+}
+
+impl<S> Layer<S> for RequireRole {
+    type Service = RequireRoleService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequireRoleService {
+            inner,
+            role: self.role,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequireRoleService<S> {
+    inner: S,
+    role: Role,
+}
+
+impl<S> Service<Request> for RequireRoleService<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let role = self.role;
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+            if let Err(err) = authorize(role, &mut parts).await {
+                return Ok(err.into_response());
+            }
+            inner.call(Request::from_parts(parts, body)).await
+        })
+    }
+}
+
+/// For routes reaching past any node scope: tokens, the audit log, the database
+/// and the creation of nodes.
+pub fn ensure_unscoped(principal: &Principal) -> Result<(), HttpError> {
+    if principal.bee_ids.is_some() {
+        return Err(ApiError::Forbidden(
+            "Tokens scoped to a subset of nodes can't use this route.".to_owned(),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+async fn authorize(role: Role, parts: &mut Parts) -> Result<(), HttpError> {
+    let principal = parts
+        .extensions
+        .get::<Principal>()
+        .cloned()
+        .ok_or_else(|| ApiError::Unauthorized("Unauthenticated request.".to_owned()))?;
+
+    if !principal.has_role(role) {
+        return Err(ApiError::Forbidden(format!(
+            "Token '{}' lacks the {:?} role required by this route.",
+            principal.name, role
+        ))
+        .into());
+    }
+
+    let bee_id = RawPathParams::from_request_parts(parts, &())
+        .await
+        .ok()
+        .and_then(|params| {
+            params
+                .iter()
+                .find(|(key, _)| *key == "bee_id")
+                .and_then(|(_, value)| value.parse::<u8>().ok())
+        });
+
+    if let Some(bee_id) = bee_id {
+        if !principal.can_access(bee_id) {
+            return Err(ApiError::Forbidden(format!(
+                "Token '{}' is not allowed to access bee node with id {}.",
+                principal.name, bee_id
+            ))
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::{Extension, Router};
+    use tower::ServiceExt;
+
+    async fn call(principal: Principal, role: Role, uri: &str) -> StatusCode {
+        let app = Router::new()
+            .route("/", get(|| async {}).route_layer(require_role(role)))
+            .route(
+                "/{bee_id}",
+                get(|| async {}).route_layer(require_role(role)),
+            )
+            .layer(Extension(principal));
+
+        app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    fn principal(role: Role, bee_ids: Option<Vec<u8>>) -> Principal {
+        Principal {
+            name: "test".to_owned(),
+            role,
+            bee_ids,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn should_allow_sufficient_role() {
+        let status = call(principal(Role::Admin, None), Role::Operator, "/1").await;
+
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_forbid_insufficient_role() {
+        let status = call(principal(Role::Viewer, None), Role::Operator, "/1").await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn should_forbid_node_outside_scope() {
+        let status = call(principal(Role::Operator, Some(vec![2])), Role::Viewer, "/1").await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn should_allow_node_inside_scope() {
+        let status = call(principal(Role::Operator, Some(vec![1])), Role::Viewer, "/1").await;
+
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_allow_scoped_principal_on_route_without_node() {
+        let status = call(principal(Role::Viewer, Some(vec![1])), Role::Viewer, "/").await;
+
+        assert_eq!(status, StatusCode::OK);
+    }
+}
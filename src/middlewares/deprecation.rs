@@ -0,0 +1,66 @@
+use axum::extract::Request;
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::models::http_error::{ErrorCode, CAPACITY_REACHED};
+
+/// When the unversioned API was deprecated, as an RFC 9745 `Deprecation` date.
+const DEPRECATED_AT: &str = "@1792281600";
+
+/// Marks responses of the unversioned API as deprecated and links their `/v1` successor.
+/// The 201 and 204 of `/v1` are answered as the 200 these routes always returned, and
+/// a reached capacity as their 400.
+pub async fn deprecate(req: Request, next: Next) -> Response {
+    let successor = format!("</v1{}>; rel=\"successor-version\"", req.uri().path());
+
+    let mut response = next.run(req).await;
+
+    if matches!(
+        response.status(),
+        StatusCode::CREATED | StatusCode::NO_CONTENT
+    ) {
+        *response.status_mut() = StatusCode::OK;
+    }
+    if response.extensions().get::<ErrorCode>() == Some(&ErrorCode(CAPACITY_REACHED)) {
+        *response.status_mut() = StatusCode::BAD_REQUEST;
+    }
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static(DEPRECATED_AT));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert(header::LINK, link);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::middleware;
+    use axum::routing::post;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn should_mark_response_deprecated() {
+        let app = Router::new()
+            .route(
+                "/bee/{bee_id}/start",
+                post(|| async { StatusCode::NO_CONTENT }),
+            )
+            .layer(middleware::from_fn(deprecate));
+
+        let response = app
+            .oneshot(Request::post("/bee/1/start").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["deprecation"], DEPRECATED_AT);
+        assert_eq!(
+            response.headers()[header::LINK],
+            "</v1/bee/1/start>; rel=\"successor-version\""
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use crate::core::metrics::Metrics;
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+
+/// Counts requests per matched route, so path parameters don't explode the label set.
+pub async fn track_http_metrics(
+    State(metrics): State<Metrics>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".to_owned());
+    let start = Instant::now();
+
+    let response = next.run(req).await;
+
+    metrics.observe_http_request(&method, &route, response.status().as_u16(), start.elapsed());
+    response
+}
@@ -0,0 +1,6 @@
+pub mod audit;
+pub mod auth;
+pub mod deprecation;
+pub mod metrics;
+pub mod rejection;
+pub mod request_id;
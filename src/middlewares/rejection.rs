@@ -0,0 +1,100 @@
+use axum::body::to_bytes;
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::BoxError;
+
+use crate::models::http_error::{ApiError, ErrorCode, HttpError};
+
+/// Rejection bodies are a line of text, anything longer isn't one.
+const MAX_REJECTION_LEN: usize = 4096;
+
+/// Gives the client errors axum answers by itself, e.g. a body that doesn't parse
+/// or a method the route doesn't have, the body every handler error has.
+pub async fn describe_rejections(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    let status = response.status();
+    if !status.is_client_error() || response.extensions().get::<ErrorCode>().is_some() {
+        return response;
+    }
+
+    let message = to_bytes(response.into_body(), MAX_REJECTION_LEN)
+        .await
+        .ok()
+        .and_then(|body| String::from_utf8(body.to_vec()).ok())
+        .filter(|message| !message.is_empty())
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or_default().to_owned());
+    HttpError::rejected(status, message).into_response()
+}
+
+/// Answers the errors of the layers under `HandleErrorLayer`, of which only the
+/// timeout can fail.
+pub async fn handle_layer_error(err: BoxError) -> HttpError {
+    if err.is::<tower::timeout::error::Elapsed>() {
+        ApiError::Timeout.into()
+    } else {
+        anyhow::anyhow!(err).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::error_handling::HandleErrorLayer;
+    use axum::http::{header, StatusCode};
+    use axum::routing::{get, post};
+    use axum::{middleware, Json, Router};
+    use std::time::Duration;
+    use tower::timeout::TimeoutLayer;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/echo",
+                post(|Json(body): Json<u8>| async move { Json(body) }),
+            )
+            .route("/slow", get(|| tokio::time::sleep(Duration::from_secs(60))))
+            .layer(
+                ServiceBuilder::new()
+                    .layer(middleware::from_fn(describe_rejections))
+                    .layer(HandleErrorLayer::new(handle_layer_error))
+                    .layer(TimeoutLayer::new(Duration::from_millis(10))),
+            )
+    }
+
+    async fn send(request: Request) -> (StatusCode, serde_json::Value) {
+        let response = app().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn should_describe_body_rejections() {
+        let (status, body) = send(
+            Request::post("/echo")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from("\"one\""))
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "invalid_request");
+        assert!(body["message"].as_str().unwrap().contains("deserialize"));
+
+        let (status, body) = send(Request::get("/echo").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(body["message"], "Method Not Allowed");
+    }
+
+    #[tokio::test]
+    async fn should_answer_timeouts_with_error_body() {
+        let (status, body) = send(Request::get("/slow").body(Body::empty()).unwrap()).await;
+
+        assert_eq!(status, StatusCode::REQUEST_TIMEOUT);
+        assert_eq!(body["code"], "request_timeout");
+    }
+}
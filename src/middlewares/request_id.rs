@@ -0,0 +1,99 @@
+use crate::utils::token::generate_request_id;
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+
+static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id of the request being handled, when running under `assign_request_id`.
+pub fn get_request_id() -> Option<String> {
+    REQUEST_ID.try_with(String::to_owned).ok()
+}
+
+/// Keeps the client's `x-request-id` when it is sane, generates one otherwise,
+/// and echoes it in the response.
+pub async fn assign_request_id(req: Request, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(str::to_owned)
+        .unwrap_or_else(generate_request_id);
+
+    let mut response = REQUEST_ID.scope(request_id.clone(), next.run(req)).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(X_REQUEST_ID.clone(), value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::http_error::{ApiError, HttpError};
+    use axum::body::{to_bytes, Body};
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    Err::<(), HttpError>(ApiError::NotFound("Nothing here.".to_owned()).into())
+                }),
+            )
+            .layer(middleware::from_fn(assign_request_id))
+    }
+
+    #[tokio::test]
+    async fn should_put_request_id_in_error_body() {
+        let response = app()
+            .oneshot(
+                Request::get("/")
+                    .header("x-request-id", "abc-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()["x-request-id"], "abc-123");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "not_found");
+        assert_eq!(body["request_id"], "abc-123");
+    }
+
+    #[tokio::test]
+    async fn should_replace_invalid_request_id() {
+        let response = app()
+            .oneshot(
+                Request::get("/")
+                    .header("x-request-id", "a b")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let request_id = response.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(request_id.len(), 16);
+    }
+}
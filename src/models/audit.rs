@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    pub principal: String,
+    pub source_ip: Option<String>,
+    pub action: String,
+    pub bee_ids: Vec<u8>,
+    pub parameters: BTreeMap<String, String>,
+    /// 0 when the request was dropped before its handler answered, e.g. on timeout.
+    pub status: u16,
+    pub success: bool,
+}
+
+/// Node ids a handler acted upon, attached to its response for the audit layer.
+#[derive(Clone, Default, Debug)]
+pub struct AuditTargets(pub Vec<u8>);
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub bee_id: Option<u8>,
+    pub action: Option<String>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.bee_id
+            .is_none_or(|bee_id| entry.bee_ids.contains(&bee_id))
+            && self
+                .action
+                .as_ref()
+                .is_none_or(|action| &entry.action == action)
+            && self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.to.is_none_or(|to| entry.timestamp <= to)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> AuditEntry {
+        AuditEntry {
+            timestamp: 100,
+            action: "bee.stop".to_owned(),
+            bee_ids: vec![3],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_match_everything_without_filters() {
+        assert!(AuditQuery::default().matches(&entry()));
+    }
+
+    #[test]
+    fn should_filter_by_node() {
+        let query = |bee_id| AuditQuery {
+            bee_id: Some(bee_id),
+            ..Default::default()
+        };
+
+        assert!(query(3).matches(&entry()));
+        assert!(!query(4).matches(&entry()));
+    }
+
+    #[test]
+    fn should_filter_by_action() {
+        let query = |action: &str| AuditQuery {
+            action: Some(action.to_owned()),
+            ..Default::default()
+        };
+
+        assert!(query("bee.stop").matches(&entry()));
+        assert!(!query("bee.start").matches(&entry()));
+    }
+
+    #[test]
+    fn should_filter_by_time_range() {
+        let query = |from, to| AuditQuery {
+            from,
+            to,
+            ..Default::default()
+        };
+
+        assert!(query(Some(100), Some(100)).matches(&entry()));
+        assert!(query(None, Some(150)).matches(&entry()));
+        assert!(!query(Some(101), None).matches(&entry()));
+        assert!(!query(None, Some(99)).matches(&entry()));
+    }
+}
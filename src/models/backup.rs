@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Archive of a node identity in the backup dir, named `node_xx-<timestamp>[-full].tar.gz`.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, ToSchema)]
+pub struct BackupInfo {
+    pub name: String,
+    pub bee_id: u8,
+    pub created_at: u64,
+    pub include_localstore: bool,
+    pub size_bytes: u64,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateBackupQuery {
+    /// Defaults to the `backup.include_localstore` setting.
+    pub include_localstore: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct RestoreBeeRequest {
+    /// Name of an archive in the backup dir.
+    pub backup: String,
+}
@@ -1,26 +1,49 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::bee_service::BeeService;
+use crate::models::bee_api::BeeFunds;
 
-#[derive(Deserialize, Serialize, Default, Clone)]
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
 pub struct BeeData {
     pub id: u8,
     pub neighborhood: String,
     pub full_node: bool,
     pub swap_enable: bool,
     pub reserve_doubling: bool,
+    #[schema(value_type = String)]
     pub data_dir: PathBuf,
+    #[serde(default)]
+    pub created_at: u64,
+    /// Free-form labels such as `owner=teamA`, also set on the container.
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 impl BeeData {
     pub fn name(&self) -> String {
         BeeService::get_node_name(self.id)
     }
+
+    pub fn mode(&self) -> &'static str {
+        get_node_mode(self.full_node, self.swap_enable)
+    }
+}
+
+/// Full nodes store chunks, light nodes only trade them and ultra-light nodes have no chequebook.
+pub fn get_node_mode(full_node: bool, swap_enable: bool) -> &'static str {
+    match (full_node, swap_enable) {
+        (true, _) => "full",
+        (false, true) => "light",
+        (false, false) => "ultra_light",
+    }
 }
 
-#[derive(Deserialize, Serialize, Default, Clone)]
+#[derive(Deserialize, Serialize, Default, Clone, ToSchema)]
 pub struct BeeInfo {
     pub id: u8,
     pub name: String,
@@ -29,9 +52,12 @@ pub struct BeeInfo {
     pub full_node: bool,
     pub swap_enable: bool,
     pub reserve_doubling: bool,
+    #[schema(value_type = String)]
     pub data_dir: PathBuf,
     pub api_port: String,
     pub p2p_port: String,
+    pub tags: BTreeMap<String, String>,
+    pub description: Option<String>,
 }
 
 impl BeeInfo {
@@ -47,6 +73,146 @@ impl BeeInfo {
             data_dir: data.data_dir.to_owned(),
             api_port: api_port.to_owned(),
             p2p_port: p2p_port.to_owned(),
+            tags: data.tags.to_owned(),
+            description: data.description.to_owned(),
+        }
+    }
+}
+
+/// What deleting a node removes from disk.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct BeeDeletion {
+    pub id: u8,
+    pub name: String,
+    #[schema(value_type = String)]
+    pub data_dir: PathBuf,
+    pub data_dir_exists: bool,
+    /// Only measured by a dry run, a deletion removes the data dir in the background.
+    pub size_bytes: Option<u64>,
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteBeeQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Deletes the node even though it holds funds or stake.
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Pending deletion of a node, only the hash of its confirmation token is stored.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+pub struct DeletionRequest {
+    pub bee_id: u8,
+    /// Token that requested the deletion, the only one able to confirm it.
+    pub token_id: String,
+    pub hash: String,
+    pub expires_at: u64,
+    /// Read when requested, so the confirmation doesn't wait on the node API.
+    pub funds: BeeFunds,
+}
+
+/// What a deletion would destroy, confirmed by sending `token` back once before `expires_at`.
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct PendingBeeDeletion {
+    pub token: String,
+    pub expires_at: u64,
+    pub deletion: BeeDeletion,
+    pub funds: BeeFunds,
+}
+
+/// Target of a node move, the path of a configured storage volume.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct MoveBeeRequest {
+    #[schema(value_type = String)]
+    pub volume: PathBuf,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Filters, sort and page of `GET /bees`, every filter is optional.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BeesQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub full_node: Option<bool>,
+    pub swap_enable: Option<bool>,
+    /// Matches neighborhoods starting with these bits.
+    pub neighborhood: Option<String>,
+    /// Container state as reported by Docker, or `missing`.
+    pub state: Option<String>,
+    /// `id`, `created_at` or a numeric field of the container stats.
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// Comma separated `key=value` or `key` tag filters, all of them must match.
+    pub tag: Option<String>,
+    /// Returns `BeeInfo` rather than `BeeData`.
+    #[serde(default)]
+    pub full: bool,
+}
+
+/// Selects the nodes of a bulk operation by tag, see `BeesQuery::tag`.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TagQuery {
+    pub tag: Option<String>,
+}
+
+/// Replaces the tags of a node, and its description when given.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct UpdateTagsRequest {
+    pub tags: BTreeMap<String, String>,
+    /// Kept when omitted or null, cleared when empty.
+    pub description: Option<String>,
+}
+
+/// Change of the tags and description of a node, applied by the database in one operation.
+#[derive(Clone, Debug)]
+pub enum TagsChange {
+    Replace(UpdateTagsRequest),
+    /// Removes the given keys, or every tag when `None`.
+    Remove(Option<Vec<String>>),
+}
+
+impl TagsChange {
+    pub fn apply(self, bee: &mut BeeData) {
+        match self {
+            TagsChange::Replace(request) => {
+                bee.tags = request.tags;
+                match request.description {
+                    Some(description) if description.is_empty() => bee.description = None,
+                    Some(description) => bee.description = Some(description),
+                    None => {}
+                }
+            }
+            TagsChange::Remove(Some(keys)) => bee.tags.retain(|key, _| !keys.contains(key)),
+            TagsChange::Remove(None) => bee.tags.clear(),
         }
     }
 }
+
+/// Node after a tags change. Its container keeps the previous labels until recreated.
+#[derive(Serialize, ToSchema)]
+pub struct TaggedBee {
+    #[serde(flatten)]
+    pub bee: BeeInfo,
+    /// The tags changed, `POST /{bee_id}/recreate` applies them to the container labels.
+    pub recreate_required: bool,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteTagsQuery {
+    /// Comma separated keys to remove, every tag when omitted.
+    pub keys: Option<String>,
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Subset of a bee node's `GET /status` response.
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BeeStatus {
+    #[serde(default)]
+    pub connected_peers: u64,
+    #[serde(default)]
+    pub storage_radius: u8,
+    #[serde(default)]
+    pub reserve_size: u64,
+}
+
+/// Subset of a bee node's `GET /stake` response, amount in PLUR.
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BeeStake {
+    pub staked_amount: String,
+}
+
+/// Subset of a bee node's `GET /wallet` response, balances in PLUR and wei.
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BeeWallet {
+    pub bzz_balance: String,
+    pub native_token_balance: String,
+}
+
+/// Funds held by a node, each one `None` when the node API didn't answer.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, ToSchema)]
+pub struct BeeFunds {
+    pub bzz_balance: Option<String>,
+    pub native_token_balance: Option<String>,
+    pub staked_amount: Option<String>,
+}
+
+impl BeeFunds {
+    /// Whether any amount isn't zero, or is unknown and might not be.
+    pub fn may_have_funds(&self) -> bool {
+        [
+            &self.bzz_balance,
+            &self.native_token_balance,
+            &self.staked_amount,
+        ]
+        .into_iter()
+        .any(|amount| match amount {
+            Some(amount) => amount.chars().any(|c| c.is_ascii_digit() && c != '0'),
+            None => true,
+        })
+    }
+}
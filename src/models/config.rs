@@ -1,5 +1,7 @@
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 
+use crate::models::token::Role;
 use crate::utils::regex::{RegexVisitor, PORT_REGEX, VOLUME_NAME_REGEX};
 use serde::{Deserialize, Deserializer};
 use tokio::fs::File;
@@ -22,10 +24,33 @@ where
 #[derive(Deserialize, Default, Clone)]
 pub struct Config {
     pub port: u16,
+    /// Set on the containers this instance manages, so they can be told apart
+    /// from those of another ruche on the same Docker host.
+    #[serde(default = "default_instance_id")]
+    pub instance_id: String,
+    /// Prepended to `node_xx` in container names, lets instances share a Docker host.
+    #[serde(default)]
+    pub name_prefix: String,
+    #[serde(default)]
+    pub server: Server,
+    #[serde(default)]
+    pub auth: Auth,
+    #[serde(default)]
+    pub audit: Audit,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
     pub bee: Bee,
     pub network: Network,
     pub chains: Chains,
     pub storage: Storage,
+    #[serde(default)]
+    pub backup: Backup,
+    #[serde(default)]
+    pub database: Database,
+}
+
+fn default_instance_id() -> String {
+    "ruche".to_owned()
 }
 
 impl Config {
@@ -41,6 +66,161 @@ impl Config {
     }
 }
 
+#[derive(Deserialize, Clone)]
+pub struct Server {
+    /// Use `::` to listen on IPv6 (and IPv4 on dual-stack hosts).
+    #[serde(default = "default_server_host")]
+    pub host: IpAddr,
+    /// Served in plain HTTP alongside the TCP listener.
+    pub unix_socket: Option<PathBuf>,
+    pub tls: Option<Tls>,
+    /// Keeps serving the unversioned routes, deprecated in favor of `/v1`.
+    #[serde(default = "default_legacy_routes")]
+    pub legacy_routes: bool,
+}
+
+fn default_server_host() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+}
+
+fn default_legacy_routes() -> bool {
+    true
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server {
+            host: default_server_host(),
+            unix_socket: None,
+            tls: None,
+            legacy_routes: default_legacy_routes(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone)]
+pub struct Tls {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// When set, clients must present a certificate signed by this CA.
+    pub client_ca_path: Option<PathBuf>,
+    #[serde(default = "default_tls_reload_interval")]
+    pub reload_interval_secs: u64,
+}
+
+fn default_tls_reload_interval() -> u64 {
+    60
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Auth {
+    #[serde(default = "default_auth_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub tokens: Vec<AuthToken>,
+}
+
+fn default_auth_enabled() -> bool {
+    true
+}
+
+impl Default for Auth {
+    fn default() -> Self {
+        Auth {
+            enabled: default_auth_enabled(),
+            tokens: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone)]
+pub struct AuthToken {
+    pub name: String,
+    pub token: String,
+    #[serde(default = "default_auth_token_role")]
+    pub role: Role,
+    #[serde(default)]
+    pub bee_ids: Option<Vec<u8>>,
+}
+
+/// Tokens from the config file are the bootstrap credentials, hence admin unless told otherwise.
+fn default_auth_token_role() -> Role {
+    Role::Admin
+}
+
+#[derive(Deserialize, Default, Clone)]
+pub struct Audit {
+    /// Entries are also appended to this JSONL file when set.
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Default, Clone)]
+pub struct MetricsConfig {
+    /// Serves `/v1/metrics` to requests without a token, for scrapers that can't send one.
+    #[serde(default)]
+    pub public: bool,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseBackend {
+    #[default]
+    Polodb,
+    Sqlite,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Database {
+    #[serde(default)]
+    pub backend: DatabaseBackend,
+    /// Database file of the `sqlite` backend.
+    #[serde(default = "default_sqlite_path")]
+    pub sqlite_path: PathBuf,
+}
+
+fn default_sqlite_path() -> PathBuf {
+    PathBuf::from("ruche.sqlite")
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Database {
+            backend: DatabaseBackend::default(),
+            sqlite_path: default_sqlite_path(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Backup {
+    /// Backups are disabled unless set.
+    pub dir: Option<PathBuf>,
+    /// Every node is backed up at this interval, `0` disables scheduled backups.
+    #[serde(default)]
+    pub interval_hours: u64,
+    /// Number of backups kept per node, `0` keeps them all.
+    #[serde(default = "default_backup_retention")]
+    pub retention: usize,
+    /// Whether scheduled backups also archive the localstore.
+    #[serde(default)]
+    pub include_localstore: bool,
+}
+
+fn default_backup_retention() -> usize {
+    7
+}
+
+impl Default for Backup {
+    fn default() -> Self {
+        Backup {
+            dir: None,
+            interval_hours: 0,
+            retention: default_backup_retention(),
+            include_localstore: false,
+        }
+    }
+}
+
 #[derive(Deserialize, Default, Clone)]
 pub struct Bee {
     pub image: String,
@@ -49,6 +229,9 @@ pub struct Bee {
     pub full_node: bool,
     pub swap_enable: bool,
     pub reserve_doubling: bool,
+    /// Refuses deleting nodes holding funds or stake, unless the deletion is forced.
+    #[serde(default)]
+    pub protect_funded: bool,
 }
 
 #[derive(Deserialize, Default, Clone)]
@@ -67,12 +250,57 @@ pub struct Chains {
     pub gno_rpc: String,
 }
 
-#[derive(Deserialize, Default, Clone)]
+#[derive(Deserialize, Clone)]
 pub struct Storage {
+    #[serde(default)]
     pub root_path: PathBuf,
-    #[serde(deserialize_with = "validate_volume_name")]
+    #[serde(default, deserialize_with = "validate_volume_name")]
     pub parent_dir_format: String,
+    #[serde(default)]
     pub parent_dir_capacity: u8,
+    /// Replace the `root_path/parent_dir_format` layout when set.
+    #[serde(default)]
+    pub volumes: Vec<Volume>,
+    #[serde(default)]
+    pub placement: Placement,
+    /// Parent directories whose filesystem is used above this percentage are flagged.
+    #[serde(default = "default_usage_warning_percent")]
+    pub usage_warning_percent: u8,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Storage {
+            root_path: PathBuf::new(),
+            parent_dir_format: String::new(),
+            parent_dir_capacity: 0,
+            volumes: Vec::new(),
+            placement: Placement::default(),
+            usage_warning_percent: default_usage_warning_percent(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct Volume {
+    pub path: PathBuf,
+    /// Maximum number of nodes stored in the volume.
+    pub capacity: u8,
+    /// No new node is placed once the nodes data in the volume reaches this size.
+    pub max_size_gb: Option<u64>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    #[default]
+    FillFirst,
+    MostFree,
+    RoundRobin,
+}
+
+fn default_usage_warning_percent() -> u8 {
+    85
 }
 
 #[cfg(test)]
@@ -111,6 +339,8 @@ mod tests {
         let config: Config = toml::from_str(mock_config).unwrap();
 
         assert_eq!(config.port, 3000);
+        assert_eq!(config.instance_id, "ruche");
+        assert_eq!(config.name_prefix, "");
         assert_eq!(config.bee.image, "ethersphere/bee:2.3.2");
         assert_eq!(config.bee.password, "some-password");
         assert_eq!(config.bee.welcome_msg, "Hello, Swarm!");
@@ -129,6 +359,132 @@ mod tests {
         assert_eq!(config.storage.root_path, PathBuf::from("/media"));
         assert_eq!(config.storage.parent_dir_format, "swarm_data_xx");
         assert_eq!(config.storage.parent_dir_capacity, 4);
+        assert_eq!(config.storage.usage_warning_percent, 85);
+        assert_eq!(config.storage.placement, Placement::FillFirst);
+        assert!(config.storage.volumes.is_empty());
+
+        assert!(config.auth.enabled);
+        assert!(config.auth.tokens.is_empty());
+
+        assert_eq!(config.server.host, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert!(config.server.unix_socket.is_none());
+        assert!(config.server.tls.is_none());
+
+        assert!(config.audit.file.is_none());
+
+        assert!(config.backup.dir.is_none());
+        assert_eq!(config.backup.interval_hours, 0);
+        assert_eq!(config.backup.retention, 7);
+    }
+
+    #[tokio::test]
+    async fn test_parsing_of_server_conf() {
+        let mock_config = r#"
+            host = "::"
+            unix_socket = "/run/ruche.sock"
+
+            [tls]
+            cert_path = "/etc/ruche/cert.pem"
+            key_path = "/etc/ruche/key.pem"
+            client_ca_path = "/etc/ruche/ca.pem"
+        "#;
+
+        let server_conf: Server = toml::from_str(mock_config).unwrap();
+
+        assert_eq!(server_conf.host, "::".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            server_conf.unix_socket,
+            Some(PathBuf::from("/run/ruche.sock"))
+        );
+        let tls = server_conf.tls.unwrap();
+        assert_eq!(tls.cert_path, PathBuf::from("/etc/ruche/cert.pem"));
+        assert_eq!(tls.key_path, PathBuf::from("/etc/ruche/key.pem"));
+        assert_eq!(tls.client_ca_path, Some(PathBuf::from("/etc/ruche/ca.pem")));
+        assert_eq!(tls.reload_interval_secs, 60);
+    }
+
+    #[tokio::test]
+    async fn test_failure_of_parsing_invalid_server_host() {
+        let result: Result<Server, _> = toml::from_str(r#"host = "localhost""#);
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parsing_of_storage_volumes() {
+        let mock_config = r#"
+            placement = "most_free"
+
+            [[volumes]]
+            path = "/mnt/disk1"
+            capacity = 4
+            max_size_gb = 3500
+
+            [[volumes]]
+            path = "/mnt/disk2"
+            capacity = 2
+        "#;
+
+        let storage: Storage = toml::from_str(mock_config).unwrap();
+
+        assert_eq!(storage.placement, Placement::MostFree);
+        assert_eq!(
+            storage.volumes,
+            vec![
+                Volume {
+                    path: PathBuf::from("/mnt/disk1"),
+                    capacity: 4,
+                    max_size_gb: Some(3500),
+                },
+                Volume {
+                    path: PathBuf::from("/mnt/disk2"),
+                    capacity: 2,
+                    max_size_gb: None,
+                }
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parsing_of_auth_conf() {
+        let mock_config = r#"
+            enabled = true
+
+            [[tokens]]
+            name = "ci"
+            token = "some-token"
+        "#;
+
+        let auth_conf: Auth = toml::from_str(mock_config).unwrap();
+
+        assert!(auth_conf.enabled);
+        assert_eq!(auth_conf.tokens.len(), 1);
+        assert_eq!(auth_conf.tokens[0].name, "ci");
+        assert_eq!(auth_conf.tokens[0].token, "some-token");
+        assert_eq!(auth_conf.tokens[0].role, Role::Admin);
+        assert!(auth_conf.tokens[0].bee_ids.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_parsing_of_scoped_auth_token() {
+        let mock_config = r#"
+            name = "monitoring"
+            token = "some-token"
+            role = "viewer"
+            bee_ids = [1, 2]
+        "#;
+
+        let token_conf: AuthToken = toml::from_str(mock_config).unwrap();
+
+        assert_eq!(token_conf.role, Role::Viewer);
+        assert_eq!(token_conf.bee_ids, Some(vec![1, 2]));
+    }
+
+    #[tokio::test]
+    async fn test_auth_enabled_by_default() {
+        let auth_conf: Auth = toml::from_str("").unwrap();
+
+        assert!(auth_conf.enabled);
     }
 
     #[tokio::test]
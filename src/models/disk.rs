@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Size on disk of a node data directory, split by bee's own subdirectories.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct NodeDiskUsage {
+    pub id: u8,
+    pub name: String,
+    #[schema(value_type = String)]
+    pub data_dir: PathBuf,
+    pub total_bytes: u64,
+    pub localstore_bytes: u64,
+    pub statestore_bytes: u64,
+    pub keys_bytes: u64,
+}
+
+/// Filesystem usage of a parent directory, i.e. of the disk it is mounted on.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct ParentDirUsage {
+    #[schema(value_type = String)]
+    pub path: PathBuf,
+    pub bee_ids: Vec<u8>,
+    pub capacity_bytes: Option<u64>,
+    pub used_bytes: Option<u64>,
+    pub available_bytes: Option<u64>,
+    pub used_percent: Option<f64>,
+    pub warning: bool,
+    /// Why the usage is unknown, e.g. a volume that isn't mounted.
+    pub error: Option<String>,
+}
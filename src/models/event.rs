@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BeeEventKind {
+    #[default]
+    Created,
+    Started,
+    Stopped,
+    Died,
+    OomKilled,
+    /// Started again by Docker's restart policy after dying, without a stop in between.
+    Restarted,
+    Recreated,
+    ImageChanged,
+    Health,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct BeeEvent {
+    pub bee_id: u8,
+    pub timestamp: u64,
+    pub kind: BeeEventKind,
+    pub exit_code: Option<i64>,
+    pub image: Option<String>,
+    pub previous_image: Option<String>,
+    pub health: Option<String>,
+}
+
+/// Raw lifecycle event of a `node_*` container, as reported by Docker.
+#[derive(Default, Clone, Debug)]
+pub struct ContainerEvent {
+    pub name: String,
+    pub action: String,
+    pub timestamp: u64,
+    pub image: Option<String>,
+    pub exit_code: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BeeEventQuery {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl BeeEventQuery {
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    pub fn matches(&self, event: &BeeEvent) -> bool {
+        self.from.is_none_or(|from| event.timestamp >= from)
+            && self.to.is_none_or(|to| event.timestamp <= to)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_filter_events_by_time_range() {
+        let event = BeeEvent {
+            timestamp: 100,
+            ..Default::default()
+        };
+        let query = |from, to| BeeEventQuery {
+            from,
+            to,
+            ..Default::default()
+        };
+
+        assert!(query(None, None).matches(&event));
+        assert!(query(Some(100), Some(100)).matches(&event));
+        assert!(!query(Some(101), None).matches(&event));
+        assert!(!query(None, Some(99)).matches(&event));
+    }
+
+    #[test]
+    fn should_serialize_kind_in_snake_case() {
+        assert_eq!(
+            serde_json::to_string(&BeeEventKind::OomKilled).unwrap(),
+            "\"oom_killed\""
+        );
+    }
+}
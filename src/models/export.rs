@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::{audit::AuditEntry, bee::BeeData, event::BeeEvent, job::Job, token::ApiToken};
+
+/// Layout version of `DatabaseExport`, bumped whenever a field changes meaning.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Readable dump of the database, for disaster recovery and diffing fleet state.
+#[derive(Deserialize, Serialize, Default, Clone, ToSchema)]
+pub struct DatabaseExport {
+    pub version: u32,
+    pub exported_at: u64,
+    pub instance_id: String,
+    pub bees: Vec<BeeData>,
+    pub tokens: Vec<ApiToken>,
+    pub audit: Vec<AuditEntry>,
+    pub events: Vec<BeeEvent>,
+    /// Jobs of the exporting process, informative only since jobs aren't persisted.
+    #[serde(default)]
+    pub jobs: Vec<Job>,
+}
+
+/// What to do with a node or token of the dump that already exists.
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictMode {
+    Skip,
+    Overwrite,
+    #[default]
+    Fail,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportImportQuery {
+    #[serde(default)]
+    pub conflict: ConflictMode,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, ToSchema)]
+pub struct RecordChanges<T> {
+    pub added: Vec<T>,
+    pub overwritten: Vec<T>,
+    pub skipped: Vec<T>,
+}
+
+/// Outcome of loading a dump. Audit entries and events are appended unless
+/// already present, conflicts only apply to nodes and tokens.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct ExportImportReport {
+    pub bees: RecordChanges<u8>,
+    pub tokens: RecordChanges<String>,
+    pub audit_entries: usize,
+    pub events: usize,
+    pub dry_run: bool,
+}
+
+/// Records of a validated dump, written by `BeeDatabase::import_records` at once.
+#[derive(Default, Clone)]
+pub struct RecordsImport {
+    pub added_bees: Vec<BeeData>,
+    pub overwritten_bees: Vec<BeeData>,
+    /// Each replaces the stored token of the same id, if any.
+    pub tokens: Vec<ApiToken>,
+    pub audit: Vec<AuditEntry>,
+    pub events: Vec<BeeEvent>,
+}
@@ -1,36 +1,271 @@
+use crate::middlewares::request_id::get_request_id;
 use anyhow::Error;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Serialize;
+use std::fmt;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize)]
+pub const CAPACITY_REACHED: &str = "capacity_reached";
+const INVALID_REQUEST: &str = "invalid_request";
+
+/// What went wrong with a request. Service functions return these wrapped in
+/// `anyhow::Error` when the failure is the caller's, anything else is classified
+/// from its source when it reaches a handler.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Capacity(String),
+    Validation(String),
+    Timeout,
+    DockerUnavailable,
+    Docker(String),
+    Upstream(String),
+    Storage,
+    Internal,
+}
+
+impl ApiError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) | ApiError::Capacity(_) => StatusCode::CONFLICT,
+            ApiError::Validation(_) => StatusCode::BAD_REQUEST,
+            ApiError::Timeout => StatusCode::REQUEST_TIMEOUT,
+            ApiError::DockerUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Docker(_) | ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Storage | ApiError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Machine-readable kind, part of the API contract.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Capacity(_) => CAPACITY_REACHED,
+            ApiError::Validation(_) => INVALID_REQUEST,
+            ApiError::Timeout => "request_timeout",
+            ApiError::DockerUnavailable => "docker_unavailable",
+            ApiError::Docker(_) => "docker_error",
+            ApiError::Upstream(_) => "upstream_error",
+            ApiError::Storage => "storage_error",
+            ApiError::Internal => "internal_error",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Unauthorized(message)
+            | ApiError::Forbidden(message)
+            | ApiError::NotFound(message)
+            | ApiError::Conflict(message)
+            | ApiError::Capacity(message)
+            | ApiError::Validation(message)
+            | ApiError::Docker(message)
+            | ApiError::Upstream(message) => f.write_str(message),
+            ApiError::Timeout => f.write_str("Request timed out."),
+            ApiError::DockerUnavailable => f.write_str("Docker is unavailable."),
+            ApiError::Storage => f.write_str("Storage error."),
+            ApiError::Internal => f.write_str("Internal error."),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Kind of the first cause in the chain that has one.
+fn classify(err: &Error) -> Option<ApiError> {
+    err.chain().find_map(|cause| {
+        if let Some(err) = cause.downcast_ref::<ApiError>() {
+            Some(err.to_owned())
+        } else if let Some(err) = cause.downcast_ref::<bollard::errors::Error>() {
+            Some(from_docker_error(err))
+        } else if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            Some(from_upstream_error(err))
+        } else if cause.is::<std::io::Error>()
+            || cause.is::<rusqlite::Error>()
+            || cause.is::<polodb_core::Error>()
+        {
+            Some(ApiError::Storage)
+        } else {
+            None
+        }
+    })
+}
+
+fn from_docker_error(err: &bollard::errors::Error) -> ApiError {
+    use bollard::errors::Error as DockerError;
+
+    match err {
+        DockerError::DockerResponseServerError {
+            status_code: 404, ..
+        } => ApiError::NotFound("Docker has no such container or image.".to_owned()),
+        DockerError::DockerResponseServerError {
+            status_code: 304, ..
+        } => ApiError::Conflict("Container is already in the requested state.".to_owned()),
+        DockerError::DockerResponseServerError {
+            status_code: 409, ..
+        } => ApiError::Conflict("Docker reports a conflicting container state.".to_owned()),
+        DockerError::DockerResponseServerError { status_code, .. } => ApiError::Docker(format!(
+            "Docker rejected the call with status {}.",
+            status_code
+        )),
+        DockerError::IOError { .. }
+        | DockerError::HyperResponseError { .. }
+        | DockerError::RequestTimeoutError
+        | DockerError::SocketNotFoundError(_) => ApiError::DockerUnavailable,
+        _ => ApiError::Docker("Unexpected answer from Docker.".to_owned()),
+    }
+}
+
+fn from_upstream_error(err: &reqwest::Error) -> ApiError {
+    match err.status() {
+        Some(status) => ApiError::Upstream(format!("Upstream API answered {}.", status)),
+        None if err.is_timeout() => ApiError::Upstream("Upstream API timed out.".to_owned()),
+        None => ApiError::Upstream("Upstream API is unreachable.".to_owned()),
+    }
+}
+
+/// Body of every error response, the status is only carried by the response itself.
+#[derive(Debug, Serialize, ToSchema)]
 pub struct HttpError {
     #[serde(skip_serializing)]
+    #[schema(ignore)]
     status_code: StatusCode,
+    /// Stable kind of the error, e.g. `not_found` or `docker_unavailable`.
+    code: &'static str,
     message: String,
+    /// Also sent in the `x-request-id` header, and logged with server errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+impl From<ApiError> for HttpError {
+    fn from(err: ApiError) -> Self {
+        HttpError {
+            status_code: err.status_code(),
+            code: err.code(),
+            message: err.to_string(),
+            request_id: get_request_id(),
+        }
+    }
 }
 
 impl HttpError {
-    pub fn new(status_code: StatusCode, message: &str) -> Self {
+    /// Request refused before reaching a handler, e.g. a body that doesn't parse,
+    /// keeping the status it was refused with.
+    pub fn rejected(status_code: StatusCode, message: String) -> Self {
         HttpError {
             status_code,
-            message: message.to_string(),
+            code: INVALID_REQUEST,
+            message,
+            request_id: get_request_id(),
         }
     }
 }
 
+/// Unclassified errors are logged, their message may hold internal details.
 impl From<Error> for HttpError {
     fn from(err: Error) -> Self {
-        HttpError {
-            status_code: StatusCode::INTERNAL_SERVER_ERROR,
-            message: err.to_string(),
+        let api_err = classify(&err).unwrap_or(ApiError::Internal);
+        if api_err.status_code().is_server_error() {
+            tracing::error!(
+                request_id = get_request_id().as_deref(),
+                code = api_err.code(),
+                "{:#}",
+                err
+            );
         }
+        api_err.into()
     }
 }
 
+/// Code of an error response, for middlewares that adapt it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ErrorCode(pub &'static str);
+
 impl IntoResponse for HttpError {
     fn into_response(self) -> Response {
-        (self.status_code, Json(self)).into_response()
+        let code = ErrorCode(self.code);
+        let mut response = (self.status_code, Json(self)).into_response();
+        response.extensions_mut().insert(code);
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn docker_error(status_code: u16) -> Error {
+        bollard::errors::Error::DockerResponseServerError {
+            status_code,
+            message: "No such container: node_01".to_owned(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn should_hide_unclassified_errors() {
+        let err = HttpError::from(anyhow!("Connection refused on /var/run/docker.sock"));
+
+        assert_eq!(err.status_code, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(err.code, "internal_error");
+        assert_eq!(err.message, "Internal error.");
+    }
+
+    #[test]
+    fn should_keep_api_errors_through_context() {
+        let err = Error::from(ApiError::Capacity("Max capacity reached".to_owned()))
+            .context("Unable to import");
+
+        let err = HttpError::from(err);
+
+        assert_eq!(err.status_code, StatusCode::CONFLICT);
+        assert_eq!(err.code, "capacity_reached");
+        assert_eq!(err.message, "Max capacity reached");
+    }
+
+    #[test]
+    fn should_map_docker_errors_by_kind() {
+        assert_eq!(
+            classify(&docker_error(404)).map(|err| err.code()),
+            Some("not_found")
+        );
+        assert_eq!(
+            classify(&docker_error(500)).map(|err| err.code()),
+            Some("docker_error")
+        );
+        assert_eq!(
+            classify(&docker_error(409)),
+            Some(ApiError::Conflict(
+                "Docker reports a conflicting container state.".to_owned()
+            ))
+        );
+        let unavailable =
+            bollard::errors::Error::SocketNotFoundError("/var/run/docker.sock".to_owned());
+        assert_eq!(
+            classify(&unavailable.into()),
+            Some(ApiError::DockerUnavailable)
+        );
+    }
+
+    #[test]
+    fn should_map_io_errors_to_storage() {
+        let err = Error::from(std::io::Error::other("disk full")).context("Unable to back up");
+
+        assert_eq!(classify(&err), Some(ApiError::Storage));
     }
 }
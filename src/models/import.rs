@@ -0,0 +1,83 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// A `node_*` container as found in Docker, whether ruche created it or not.
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+pub struct BeeContainer {
+    pub name: String,
+    pub image: String,
+    pub labels: HashMap<String, String>,
+    pub env: HashMap<String, String>,
+    /// Host paths by mount destination in the container.
+    pub mounts: HashMap<String, PathBuf>,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    #[default]
+    Container,
+    DataDir,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct ImportedBee {
+    pub id: u8,
+    pub name: String,
+    #[schema(value_type = String)]
+    pub data_dir: PathBuf,
+    pub source: ImportSource,
+}
+
+/// A node that was left untouched, with the reason why.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct ImportConflict {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct ImportReport {
+    pub imported: Vec<ImportedBee>,
+    pub conflicts: Vec<ImportConflict>,
+    /// Imported nodes whose settings had to be guessed.
+    pub warnings: Vec<ImportConflict>,
+    /// Nodes already registered with the same data dir.
+    pub already_registered: Vec<u8>,
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Outcome of rebuilding the node registry from the containers of this instance.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct RebuildReport {
+    /// Nodes added back to the database.
+    pub restored: Vec<u8>,
+    /// Nodes whose database entry matches their container.
+    pub consistent: Vec<u8>,
+    /// Nodes whose database entry differs from their container, left as is.
+    pub mismatched: Vec<ImportConflict>,
+    /// Registered nodes without a container.
+    pub missing_containers: Vec<u8>,
+    /// Containers without ruche labels, rebuilt from their env only.
+    pub unlabeled: Vec<String>,
+    /// Containers labeled by another ruche instance, ignored.
+    pub other_instances: Vec<String>,
+    pub conflicts: Vec<ImportConflict>,
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RebuildQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
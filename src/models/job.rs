@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    #[default]
+    Move,
+    Backup,
+    Restore,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    #[default]
+    Running,
+    Completed,
+    Failed,
+    RolledBack,
+}
+
+/// Long running operation on a node, polled by clients until it finishes.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, ToSchema)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    pub bee_id: u8,
+    pub status: JobStatus,
+    pub step: String,
+    pub total_bytes: u64,
+    pub processed_bytes: u64,
+    pub error: Option<String>,
+    /// What the job produced, such as the name of a backup.
+    pub output: Option<String>,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+impl Job {
+    pub fn is_running(&self) -> bool {
+        self.status == JobStatus::Running
+    }
+}
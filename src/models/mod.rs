@@ -1,3 +1,13 @@
+pub mod audit;
+pub mod backup;
 pub mod bee;
+pub mod bee_api;
 pub mod config;
+pub mod disk;
+pub mod event;
+pub mod export;
 pub mod http_error;
+pub mod import;
+pub mod job;
+pub mod stats;
+pub mod token;
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Resource usage of a node container at one point in time.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, ToSchema)]
+pub struct ContainerStats {
+    pub name: String,
+    pub cpu_percent: f64,
+    pub memory_usage: u64,
+    pub memory_limit: u64,
+    pub memory_percent: f64,
+    pub net_rx_bytes: u64,
+    pub net_tx_bytes: u64,
+    pub block_read_bytes: u64,
+    pub block_write_bytes: u64,
+    pub pids: u64,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsQuery {
+    #[serde(default)]
+    pub stream: bool,
+    pub interval: Option<u64>,
+}
+
+impl StatsQuery {
+    pub const DEFAULT_INTERVAL: u64 = 5;
+
+    /// Streaming interval in seconds, never shorter than one second.
+    pub fn interval(&self) -> u64 {
+        self.interval.unwrap_or(Self::DEFAULT_INTERVAL).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_clamp_stream_interval() {
+        let query = |interval| StatsQuery {
+            stream: true,
+            interval,
+        };
+
+        assert_eq!(query(None).interval(), StatsQuery::DEFAULT_INTERVAL);
+        assert_eq!(query(Some(0)).interval(), 1);
+        assert_eq!(query(Some(30)).interval(), 30);
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Ordered from least to most privileged, a role grants everything the lower ones do.
+#[derive(
+    Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Viewer,
+    Operator,
+    Admin,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, ToSchema)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub hash: String,
+    #[serde(default)]
+    pub role: Role,
+    #[serde(default)]
+    pub bee_ids: Option<Vec<u8>>,
+    pub created_at: u64,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, ToSchema)]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub role: Role,
+    pub bee_ids: Option<Vec<u8>>,
+    pub created_at: u64,
+}
+
+impl ApiTokenInfo {
+    pub fn new(token: &ApiToken) -> ApiTokenInfo {
+        ApiTokenInfo {
+            id: token.id.to_owned(),
+            name: token.name.to_owned(),
+            role: token.role,
+            bee_ids: token.bee_ids.to_owned(),
+            created_at: token.created_at,
+        }
+    }
+}
+
+/// Returned once on creation, the plain token is never stored nor shown again.
+#[derive(Deserialize, Serialize, Default, Clone, ToSchema)]
+pub struct NewApiToken {
+    pub id: String,
+    pub name: String,
+    pub token: String,
+    pub role: Role,
+    pub bee_ids: Option<Vec<u8>>,
+    pub created_at: u64,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, ToSchema)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    #[serde(default)]
+    pub role: Role,
+    #[serde(default)]
+    pub bee_ids: Option<Vec<u8>>,
+}
+
+/// Identity attached to an authenticated request.
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct Principal {
+    /// Id of the token the request came with, names aren't unique.
+    pub token_id: String,
+    pub name: String,
+    pub role: Role,
+    pub bee_ids: Option<Vec<u8>>,
+}
+
+impl Principal {
+    pub fn has_role(&self, role: Role) -> bool {
+        self.role >= role
+    }
+
+    /// Unscoped principals can access every node.
+    pub fn can_access(&self, bee_id: u8) -> bool {
+        match &self.bee_ids {
+            Some(bee_ids) => bee_ids.contains(&bee_id),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_order_roles_by_privilege() {
+        assert!(Role::Viewer < Role::Operator);
+        assert!(Role::Operator < Role::Admin);
+    }
+
+    #[test]
+    fn should_grant_lower_roles() {
+        let principal = Principal {
+            role: Role::Operator,
+            ..Default::default()
+        };
+
+        assert!(principal.has_role(Role::Viewer));
+        assert!(principal.has_role(Role::Operator));
+        assert!(!principal.has_role(Role::Admin));
+    }
+
+    #[test]
+    fn should_restrict_scoped_principal_to_its_nodes() {
+        let principal = Principal {
+            bee_ids: Some(vec![1, 3]),
+            ..Default::default()
+        };
+
+        assert!(principal.can_access(1));
+        assert!(!principal.can_access(2));
+        assert!(principal.can_access(3));
+    }
+
+    #[test]
+    fn should_allow_unscoped_principal_on_every_node() {
+        let principal = Principal::default();
+
+        assert!(principal.can_access(1));
+        assert!(principal.can_access(99));
+    }
+
+    #[test]
+    fn should_parse_lowercase_role() {
+        let request: CreateTokenRequest =
+            serde_json::from_str(r#"{"name": "monitoring", "role": "operator"}"#).unwrap();
+
+        assert_eq!(request.role, Role::Operator);
+        assert!(request.bee_ids.is_none());
+    }
+}
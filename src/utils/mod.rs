@@ -1 +1,4 @@
 pub mod regex;
+pub mod sse;
+pub mod time;
+pub mod token;
@@ -3,6 +3,8 @@ use serde::de::{Error, Visitor};
 
 pub const PORT_REGEX: &str = r"^\d{1,3}xx$";
 pub const VOLUME_NAME_REGEX: &str = r"^([\w-]+)*[^x]?xx$";
+pub const TAG_KEY_REGEX: &str = r"^[A-Za-z0-9][A-Za-z0-9._-]{0,62}$";
+pub const BACKUP_NAME_REGEX: &str = r"^node_(\d{2})-(\d+)(-full)?\.tar\.gz$";
 
 pub struct RegexVisitor(&'static str);
 
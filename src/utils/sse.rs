@@ -0,0 +1,11 @@
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures_util::stream::BoxStream;
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+
+/// Sends every item of the stream as a JSON server-sent event.
+pub fn to_json_sse<T: Serialize + 'static>(
+    stream: BoxStream<'static, T>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    Sse::new(stream.map(|item| Event::default().json_data(item))).keep_alive(KeepAlive::default())
+}
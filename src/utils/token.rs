@@ -0,0 +1,71 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+const TOKEN_BYTES: usize = 32;
+const TOKEN_ID_BYTES: usize = 8;
+const REQUEST_ID_BYTES: usize = 8;
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+pub fn generate_token() -> String {
+    random_hex(TOKEN_BYTES)
+}
+
+pub fn generate_token_id() -> String {
+    random_hex(TOKEN_ID_BYTES)
+}
+
+pub fn generate_request_id() -> String {
+    random_hex(REQUEST_ID_BYTES)
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Compares two token hashes without short-circuiting on the first differing byte.
+pub fn hashes_match(a: &str, b: &str) -> bool {
+    a.as_bytes().ct_eq(b.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_generate_unique_tokens() {
+        let token = generate_token();
+
+        assert_eq!(token.len(), TOKEN_BYTES * 2);
+        assert_ne!(token, generate_token());
+    }
+
+    #[test]
+    fn should_generate_unique_token_ids() {
+        let token_id = generate_token_id();
+
+        assert_eq!(token_id.len(), TOKEN_ID_BYTES * 2);
+        assert_ne!(token_id, generate_token_id());
+    }
+
+    #[test]
+    fn should_hash_token_deterministically() {
+        assert_eq!(hash_token("some-token"), hash_token("some-token"));
+        assert_ne!(hash_token("some-token"), hash_token("other-token"));
+        assert_eq!(hash_token("some-token").len(), 64);
+    }
+
+    #[test]
+    fn should_match_identical_hashes_only() {
+        let hash = hash_token("some-token");
+
+        assert!(hashes_match(&hash, &hash_token("some-token")));
+        assert!(!hashes_match(&hash, &hash_token("other-token")));
+        assert!(!hashes_match(&hash, ""));
+    }
+}